@@ -0,0 +1,87 @@
+//! Local receiver to try the webhooks of the node with, printing every payload it receives after
+//! checking its signature.
+//!
+//! ```sh
+//! cargo run -p solochain-template-node --example webhook-receiver -- 9000 <secret> [fail]
+//! ```
+//!
+//! With `fail`, every delivery is answered with 503 so that the retries and the dead-letter log of
+//! the node can be observed.
+
+use axum::{
+	extract::State,
+	http::{HeaderMap, StatusCode},
+	routing::post,
+	Router,
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::{net::Ipv4Addr, sync::Arc};
+
+struct Receiver {
+	secret: String,
+	fail: bool,
+}
+
+async fn receive(
+	State(receiver): State<Arc<Receiver>>,
+	headers: HeaderMap,
+	body: String,
+) -> StatusCode {
+	let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+	let (Some(delivery), Some(timestamp), Some(signature)) =
+		(header("X-Probo-Delivery"), header("X-Probo-Timestamp"), header("X-Probo-Signature"))
+	else {
+		println!("rejected a request without the webhook headers");
+		return StatusCode::BAD_REQUEST;
+	};
+
+	let mut mac = Hmac::<Sha256>::new_from_slice(receiver.secret.as_bytes())
+		.expect("HMAC takes keys of any size; qed");
+	mac.update(timestamp.as_bytes());
+	mac.update(b".");
+	mac.update(body.as_bytes());
+	let Some(signature) = signature.strip_prefix("sha256=").and_then(decode_hex) else {
+		println!("rejected {delivery}: malformed signature");
+		return StatusCode::BAD_REQUEST;
+	};
+	if mac.verify_slice(&signature).is_err() {
+		println!("rejected {delivery}: invalid signature");
+		return StatusCode::UNAUTHORIZED;
+	}
+
+	println!("{delivery}: {body}");
+	if receiver.fail {
+		StatusCode::SERVICE_UNAVAILABLE
+	} else {
+		StatusCode::OK
+	}
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+	(0..hex.len())
+		.step_by(2)
+		.map(|i| hex.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+		.collect()
+}
+
+#[tokio::main]
+async fn main() {
+	let mut args = std::env::args().skip(1);
+	let (Some(port), Some(secret)) =
+		(args.next().and_then(|port| port.parse::<u16>().ok()), args.next())
+	else {
+		eprintln!("usage: webhook-receiver <port> <secret> [fail]");
+		std::process::exit(1);
+	};
+	let fail = args.next().as_deref() == Some("fail");
+
+	let app = Router::new()
+		.route("/", post(receive))
+		.route("/{*path}", post(receive))
+		.with_state(Arc::new(Receiver { secret, fail }));
+	let listener =
+		tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await.expect("the port is free");
+	println!("receiving webhooks on http://127.0.0.1:{port}/");
+	axum::serve(listener, app).await.expect("the server runs");
+}
@@ -1,4 +1,6 @@
 use sc_cli::RunCmd;
+use solochain_template_runtime::AccountId;
+use std::path::PathBuf;
 
 #[derive(Debug, clap::Parser)]
 pub struct Cli {
@@ -7,6 +9,36 @@ pub struct Cli {
 
 	#[clap(flatten)]
 	pub run: RunCmd,
+
+	#[clap(flatten)]
+	pub proof_services: ProofServicesParams,
+}
+
+/// Optional services over the proof registry.
+#[derive(Debug, Clone, clap::Args)]
+pub struct ProofServicesParams {
+	/// Index the events of the issuance proof pallet into the SQLite database at this path, and
+	/// serve queries on it with the `indexer_*` RPC methods.
+	#[arg(long, value_name = "PATH")]
+	pub proof_indexer: Option<PathBuf>,
+
+	/// Serve a GraphQL API over the proof registry on this port of the local interface.
+	#[arg(long, value_name = "PORT", requires = "proof_indexer")]
+	pub graphql_port: Option<u16>,
+
+	/// Serve the REST gateway for issuers and verifiers on this port of the local interface.
+	#[arg(long, value_name = "PORT")]
+	pub rest_port: Option<u16>,
+
+	/// Sign the proofs stored through the REST gateway with this account, whose sr25519 key of
+	/// type `prob` must be in the node keystore. Without it, the gateway only answers lookups.
+	#[arg(long, value_name = "ADDRESS", requires = "rest_port")]
+	pub rest_signer: Option<AccountId>,
+
+	/// Post the events of the issuance proof pallet in finalized blocks to the webhook targets
+	/// configured in the JSON file at this path.
+	#[arg(long, value_name = "PATH")]
+	pub webhooks: Option<PathBuf>,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -0,0 +1,634 @@
+//! Optional REST gateway for issuers and verifiers whose systems don't speak the RPC of the node.
+//! It answers the status of proofs and the registration of entities at the best block, and stores
+//! proofs with the commit and reveal transactions of the pallet, signed with an issuer key of type
+//! [`KEY_TYPE`] from the node keystore.
+//!
+//! The gateway listens on the local interface only and describes itself in OpenAPI at
+//! `GET /v1/openapi.json`. Errors are answered as `{"error": ..., "message": ...}`, with the name
+//! of the pallet error, or of the reason a transaction was rejected, in `error`.
+
+use crate::{
+	metrics::{Metrics, Source},
+	service::{FullClient, FullPool},
+};
+use axum::{
+	extract::{rejection::JsonRejection, Path, State},
+	http::{header, StatusCode},
+	response::{IntoResponse, Response},
+	routing::{get, post},
+	Json, Router,
+};
+use codec::{Decode, Encode};
+use futures::StreamExt;
+use pallet_issuance_proof::{
+	extension::ValidityError, CompromiseWindow, EntityState, EntityStatus, InvalidReason,
+	ProofBytes, ProofStatus,
+};
+use pallet_issuance_proof_runtime_api::IssuanceProofApi;
+use pallet_issuer_bridge::KEY_TYPE;
+use sc_client_api::StorageProvider;
+use sc_transaction_pool_api::{
+	error::{Error as PoolError, IntoPoolError},
+	TransactionPool, TransactionSource, TransactionStatus,
+};
+use serde::{Deserialize, Serialize};
+use solochain_template_runtime::{
+	self as runtime, AccountId, Balance, Hash, Runtime, RuntimeCall, RuntimeError, RuntimeEvent,
+};
+use sp_api::{Core, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{
+	blake2_256, crypto::ByteArray, sr25519, storage::StorageKey, Bytes, Get, H256, U256,
+};
+use sp_keystore::KeystorePtr;
+use sp_runtime::{generic::Era, transaction_validity::InvalidTransaction, DispatchError};
+use std::{io::Write, net::Ipv4Addr, path::Path, sync::Arc, time::Duration};
+use substrate_frame_rpc_system::AccountNonceApi;
+
+const LOG_TARGET: &str = "proof-gateway";
+
+/// OpenAPI description of the gateway.
+const OPENAPI: &str = include_str!("gateway/openapi.json");
+
+/// How long a transaction of the gateway may take to get into a block.
+const INCLUSION_TIMEOUT: Duration = Duration::from_secs(60);
+
+type Proof = pallet_issuance_proof::Pallet<Runtime>;
+
+/// State shared by the handlers of the gateway.
+pub struct Gateway {
+	client: Arc<FullClient>,
+	pool: Arc<FullPool>,
+	keystore: KeystorePtr,
+	/// Issuer key the transactions are signed with, `None` if the gateway only answers lookups.
+	signer: Option<sr25519::Public>,
+	/// Secret the salts of commitments are derived from, unused without a signer.
+	salt_secret: [u8; 32],
+	/// Nonce of the next transaction, ahead of the chain while transactions wait in the pool.
+	next_nonce: tokio::sync::Mutex<u32>,
+	metrics: Option<Metrics>,
+}
+
+impl Gateway {
+	/// Create the gateway, signing with `signer` which must be a key of type [`KEY_TYPE`] in
+	/// `keystore`. With a signer, the secret of the salts is kept in the file at
+	/// `salt_secret_path`.
+	pub fn new(
+		client: Arc<FullClient>,
+		pool: Arc<FullPool>,
+		keystore: KeystorePtr,
+		salt_secret_path: &Path,
+		signer: Option<AccountId>,
+		metrics: Option<Metrics>,
+	) -> Result<Self, String> {
+		let signer = signer.map(|account| sr25519::Public::from_raw(account.into()));
+		let mut salt_secret = [0; 32];
+		if let Some(signer) = &signer {
+			if !keystore.has_keys(&[(signer.to_raw_vec(), KEY_TYPE)]) {
+				return Err(format!("no `prob` key {signer} in the keystore"));
+			}
+			salt_secret = load_salt_secret(salt_secret_path)?;
+		}
+		Ok(Self {
+			client,
+			pool,
+			keystore,
+			signer,
+			salt_secret,
+			next_nonce: Default::default(),
+			metrics,
+		})
+	}
+
+	fn signer(&self) -> Result<sr25519::Public, ApiError> {
+		self.signer.ok_or_else(|| {
+			ApiError::new(
+				StatusCode::SERVICE_UNAVAILABLE,
+				"NoSigner",
+				"the gateway was started without `--rest-signer`",
+			)
+		})
+	}
+
+	/// Decode the storage entry under `key` at block `at`.
+	fn read<T: Decode>(&self, at: Hash, key: Vec<u8>) -> Result<Option<T>, ApiError> {
+		let data = self.client.storage(at, &StorageKey(key)).map_err(ApiError::internal)?;
+		data.map(|data| T::decode(&mut &data.0[..]).map_err(ApiError::internal)).transpose()
+	}
+
+	/// Salt of the commitment to a proof. It is derived from a secret of the node, so it can't be
+	/// guessed by someone who knows the proof, and a proof stored again gets the same commitment.
+	fn salt(&self, signer: &sr25519::Public, proof_key: Hash) -> [u8; 32] {
+		(b"probo/gateway-salt", self.salt_secret, signer, proof_key).using_encoded(blake2_256)
+	}
+
+	/// Sign `call` with the key of the gateway, submit it, and wait until it is in a block.
+	/// Returns the block, or the error the transaction was rejected or failed with.
+	async fn submit(&self, signer: &sr25519::Public, call: RuntimeCall) -> Result<Hash, ApiError> {
+		let mut watcher = {
+			let mut next_nonce = self.next_nonce.lock().await;
+			let best_hash = self.client.info().best_hash;
+			let nonce = self
+				.client
+				.runtime_api()
+				.account_nonce(best_hash, AccountId::from(*signer))
+				.map_err(ApiError::internal)?
+				.max(*next_nonce);
+			let xt = self.sign(signer, call, nonce)?;
+			let watcher = self
+				.pool
+				.submit_and_watch(best_hash, TransactionSource::External, xt.into())
+				.await
+				.map_err(pool_error)?;
+			*next_nonce = nonce + 1;
+			watcher
+		};
+
+		let inclusion = tokio::time::timeout(INCLUSION_TIMEOUT, async {
+			while let Some(status) = watcher.next().await {
+				match status {
+					TransactionStatus::InBlock(inclusion) => return Some(inclusion),
+					TransactionStatus::Usurped(_) |
+					TransactionStatus::Dropped |
+					TransactionStatus::Invalid => break,
+					_ => {},
+				}
+			}
+			None
+		})
+		.await
+		.map_err(|_| {
+			ApiError::new(
+				StatusCode::GATEWAY_TIMEOUT,
+				"Timeout",
+				"the transaction did not get into a block in time, it may still do so later",
+			)
+		})?;
+		let Some((block_hash, index)) = inclusion else {
+			// the nonce may have been left unused, count again from the chain
+			*self.next_nonce.lock().await = 0;
+			return Err(ApiError::new(
+				StatusCode::SERVICE_UNAVAILABLE,
+				"TransactionDropped",
+				"the transaction was dropped from the pool",
+			));
+		};
+		self.dispatch_result(block_hash, index)?;
+		Ok(block_hash)
+	}
+
+	fn sign(
+		&self,
+		signer: &sr25519::Public,
+		call: RuntimeCall,
+		nonce: u32,
+	) -> Result<runtime::UncheckedExtrinsic, ApiError> {
+		let info = self.client.info();
+		let version =
+			self.client.runtime_api().version(info.best_hash).map_err(ApiError::internal)?;
+		let period = runtime::configs::BlockHashCount::get()
+			.checked_next_power_of_two()
+			.map(|c| c / 2)
+			.unwrap_or(2) as u64;
+		let tx_ext: runtime::TxExtension = (
+			frame_system::CheckNonZeroSender::<Runtime>::new(),
+			frame_system::CheckSpecVersion::<Runtime>::new(),
+			frame_system::CheckTxVersion::<Runtime>::new(),
+			frame_system::CheckGenesis::<Runtime>::new(),
+			frame_system::CheckEra::<Runtime>::from(Era::mortal(period, info.best_number.into())),
+			frame_system::CheckNonce::<Runtime>::from(nonce),
+			frame_system::CheckWeight::<Runtime>::new(),
+			pallet_issuance_proof::extension::ValidateProofIssuance::<Runtime>::new(),
+			pallet_issuance_proof::extension::ChargeIssuanceQuota::from(
+				pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+			),
+			frame_metadata_hash_extension::CheckMetadataHash::<Runtime>::new(false),
+			frame_system::WeightReclaim::<Runtime>::new(),
+		);
+		let payload = runtime::SignedPayload::from_raw(
+			call.clone(),
+			tx_ext.clone(),
+			(
+				(),
+				version.spec_version,
+				version.transaction_version,
+				info.genesis_hash,
+				info.best_hash,
+				(),
+				(),
+				(),
+				(),
+				None,
+				(),
+			),
+		);
+		let signature = payload
+			.using_encoded(|payload| self.keystore.sr25519_sign(KEY_TYPE, signer, payload))
+			.map_err(ApiError::internal)?
+			.ok_or_else(|| ApiError::internal("the signer key left the keystore"))?;
+
+		Ok(runtime::UncheckedExtrinsic::new_signed(
+			call,
+			AccountId::from(*signer).into(),
+			runtime::Signature::Sr25519(signature),
+			tx_ext,
+		))
+	}
+
+	/// The error the extrinsic at `index` of block `block_hash` failed with, if it did.
+	fn dispatch_result(&self, block_hash: Hash, index: usize) -> Result<(), ApiError> {
+		let events: Vec<frame_system::EventRecord<RuntimeEvent, Hash>> = self
+			.read(block_hash, frame_system::Events::<Runtime>::hashed_key().to_vec())?
+			.unwrap_or_default();
+		let failure = events.into_iter().find_map(|record| match (record.phase, record.event) {
+			(
+				frame_system::Phase::ApplyExtrinsic(i),
+				RuntimeEvent::System(frame_system::Event::ExtrinsicFailed {
+					dispatch_error, ..
+				}),
+			) if i as usize == index => Some(dispatch_error),
+			_ => None,
+		});
+		failure.map_or(Ok(()), |error| Err(dispatch_error(error)))
+	}
+}
+
+/// Error answered by the gateway.
+#[derive(Debug)]
+pub struct ApiError {
+	status: StatusCode,
+	error: String,
+	message: String,
+}
+
+impl ApiError {
+	fn new(status: StatusCode, error: impl Into<String>, message: impl Into<String>) -> Self {
+		Self { status, error: error.into(), message: message.into() }
+	}
+
+	fn invalid(message: impl Into<String>) -> Self {
+		Self::new(StatusCode::BAD_REQUEST, "InvalidRequest", message)
+	}
+
+	fn internal(error: impl ToString) -> Self {
+		Self::new(StatusCode::INTERNAL_SERVER_ERROR, "Internal", error.to_string())
+	}
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+	error: String,
+	message: String,
+}
+
+impl IntoResponse for ApiError {
+	fn into_response(self) -> Response {
+		if self.status.is_server_error() {
+			log::warn!(target: LOG_TARGET, "{}: {}", self.error, self.message);
+		}
+		(self.status, Json(ErrorBody { error: self.error, message: self.message })).into_response()
+	}
+}
+
+/// HTTP status of a transaction that failed with the pallet error `error`.
+fn pallet_error_status(error: &pallet_issuance_proof::Error<Runtime>) -> StatusCode {
+	use pallet_issuance_proof::Error::*;
+	match error {
+		NotEnoughFund => StatusCode::PAYMENT_REQUIRED,
+		NotWhitelistEntity | NotVerifierEntity | NotChallengeVerifier | NotBatchIssuer =>
+			StatusCode::FORBIDDEN,
+		ProofNotFound | ChallengeNotFound | BatchNotFound | CommitmentNotFound =>
+			StatusCode::NOT_FOUND,
+		ProofAlreadyExist |
+		IsAlreadyWhitelisted |
+		IsAlreadyVerifier |
+		BatchAlreadyAnchored |
+		CommitmentAlreadyExist |
+		CommitmentNotMatured => StatusCode::CONFLICT,
+		ProofExpired | ProofExhausted | ProofRevoked | ChallengeExpired => StatusCode::GONE,
+		_ => StatusCode::UNPROCESSABLE_ENTITY,
+	}
+}
+
+fn dispatch_error(error: DispatchError) -> ApiError {
+	match RuntimeError::from_dispatch_error(error) {
+		Some(RuntimeError::Proof(error)) => ApiError::new(
+			pallet_error_status(&error),
+			<&'static str>::from(error),
+			"the transaction failed in the block",
+		),
+		_ => ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "DispatchError", format!("{error:?}")),
+	}
+}
+
+/// Error of a transaction the pool did not accept.
+fn pool_error(error: impl IntoPoolError) -> ApiError {
+	use ValidityError::*;
+	let error = match error.into_pool_error() {
+		Ok(error) => error,
+		Err(error) => return ApiError::internal(error),
+	};
+	match error {
+		PoolError::InvalidTransaction(InvalidTransaction::Custom(code)) => {
+			let Some(reason) = [
+				NotWhitelistEntity,
+				ProofAlreadyExist,
+				BatchAlreadyAnchored,
+				CommitmentNotFound,
+				CommitmentAlreadyExist,
+			]
+			.into_iter()
+			.find(|reason| *reason as u8 == code) else {
+				return ApiError::new(
+					StatusCode::BAD_REQUEST,
+					"InvalidTransaction",
+					error.to_string(),
+				)
+			};
+			let status = if reason == NotWhitelistEntity {
+				StatusCode::FORBIDDEN
+			} else {
+				StatusCode::CONFLICT
+			};
+			ApiError::new(status, format!("{reason:?}"), "the transaction was rejected")
+		},
+		PoolError::InvalidTransaction(InvalidTransaction::Payment) => ApiError::new(
+			StatusCode::PAYMENT_REQUIRED,
+			"NotEnoughFund",
+			"the signer can't pay for the transaction",
+		),
+		error => ApiError::new(StatusCode::BAD_REQUEST, "InvalidTransaction", error.to_string()),
+	}
+}
+
+/// Answer of `GET /v1/proofs/{key}/status`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofStatusResponse {
+	proof_key: Hash,
+	valid: bool,
+	/// Why the proof, or its component at `component_index`, is not valid.
+	reason: Option<String>,
+	component_index: Option<u32>,
+}
+
+/// Answer of `GET /v1/entities/{id}`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityResponse {
+	id: AccountId,
+	/// Registration as an issuer, if it is whitelisted.
+	issuer: Option<IssuerResponse>,
+	verifier: bool,
+}
+
+/// Registration of an issuer.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssuerResponse {
+	active: bool,
+	/// Amount held for the registration, as a decimal string.
+	held: String,
+	registered_at: Option<u64>,
+	proof_count: u32,
+	/// Blocks in which its key is declared compromised.
+	compromised: Option<CompromiseResponse>,
+}
+
+/// Blocks in which the key of an issuer is declared compromised.
+#[derive(Serialize)]
+pub struct CompromiseResponse {
+	since: u64,
+	until: u64,
+}
+
+/// Body of `POST /v1/proofs`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct StoreProofRequest {
+	/// The proof, hex encoded.
+	proof: Bytes,
+	/// Number of blocks the proof stays valid for.
+	expiration: u64,
+	max_uses: Option<u32>,
+	holder: Option<AccountId>,
+	attribute_root: Option<H256>,
+	status_index: Option<u32>,
+}
+
+impl StoreProofRequest {
+	/// Check the request against the limits of the pallet, and return the proof.
+	fn validate(&self) -> Result<ProofBytes<Runtime>, ApiError> {
+		let max_length = <Runtime as pallet_issuance_proof::Config>::MaxProofLength::get();
+		let status_list_length =
+			<Runtime as pallet_issuance_proof::Config>::StatusListLength::get();
+		if self.proof.is_empty() {
+			return Err(ApiError::invalid("`proof` is empty"));
+		}
+		if self.expiration == 0 {
+			return Err(ApiError::invalid("`expiration` must be at least one block"));
+		}
+		if self.max_uses == Some(0) {
+			return Err(ApiError::invalid("`maxUses` must be at least one"));
+		}
+		if self.status_index.is_some_and(|index| index >= status_list_length) {
+			return Err(ApiError::invalid(format!(
+				"`statusIndex` must be lower than {status_list_length}"
+			)));
+		}
+		self.proof.0.clone().try_into().map_err(|_| {
+			ApiError::invalid(format!("`proof` must be at most {max_length} bytes long"))
+		})
+	}
+}
+
+/// Answer of `POST /v1/proofs`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredProof {
+	proof_key: Hash,
+	/// Block the proof was stored in.
+	block_hash: Hash,
+}
+
+fn to_u64(value: U256) -> u64 {
+	value.try_into().unwrap_or(u64::MAX)
+}
+
+async fn proof_status(
+	State(gateway): State<Arc<Gateway>>,
+	Path(key): Path<String>,
+) -> Result<Json<ProofStatusResponse>, ApiError> {
+	let proof_key: Hash =
+		key.parse().map_err(|_| ApiError::invalid("the proof key must be 32 hex encoded bytes"))?;
+	let status = Metrics::status_call(gateway.metrics.as_ref(), Source::Rest, || {
+		gateway.client.runtime_api().proof_status_by_key(gateway.client.info().best_hash, proof_key)
+	})
+	.map_err(ApiError::internal)?;
+	let (reason, component_index) = match status {
+		ProofStatus::Valid => (None, None),
+		ProofStatus::Invalid(InvalidReason::NotFound) =>
+			return Err(ApiError::new(
+				StatusCode::NOT_FOUND,
+				"ProofNotFound",
+				"no proof is stored under this key",
+			)),
+		ProofStatus::Invalid(reason) => (Some(reason), None),
+		ProofStatus::InvalidComponent { index, reason } => (Some(reason), Some(index)),
+	};
+	Ok(Json(ProofStatusResponse {
+		proof_key,
+		valid: reason.is_none(),
+		reason: reason.map(|reason| format!("{reason:?}")),
+		component_index,
+	}))
+}
+
+async fn entity(
+	State(gateway): State<Arc<Gateway>>,
+	Path(id): Path<String>,
+) -> Result<Json<EntityResponse>, ApiError> {
+	let id: AccountId =
+		id.parse().map_err(|_| ApiError::invalid("the entity id must be an SS58 address"))?;
+	let best_hash = gateway.client.info().best_hash;
+	let registration: Option<EntityStatus<Balance>> = gateway
+		.read(best_hash, pallet_issuance_proof::WhitelistEntity::<Runtime>::hashed_key_for(&id))?;
+	let verifier = gateway
+		.read(best_hash, pallet_issuance_proof::VerifierEntity::<Runtime>::hashed_key_for(&id))?
+		.unwrap_or(false);
+
+	let issuer = match registration {
+		Some(registration) => {
+			let proof_count = gateway
+				.read(
+					best_hash,
+					pallet_issuance_proof::ProofCountByIssuer::<Runtime>::hashed_key_for(&id),
+				)?
+				.unwrap_or(0);
+			let compromised: Option<CompromiseWindow> = gateway.read(
+				best_hash,
+				pallet_issuance_proof::CompromisedKeys::<Runtime>::hashed_key_for(&id),
+			)?;
+			Some(IssuerResponse {
+				active: registration.state == EntityState::Active,
+				held: registration.held.to_string(),
+				registered_at: registration.registered_at.map(to_u64),
+				proof_count,
+				compromised: compromised.map(|window| CompromiseResponse {
+					since: to_u64(window.since),
+					until: to_u64(window.until),
+				}),
+			})
+		},
+		None if verifier => None,
+		None =>
+			return Err(ApiError::new(
+				StatusCode::NOT_FOUND,
+				"EntityNotFound",
+				"the account is neither an issuer nor a verifier",
+			)),
+	};
+	Ok(Json(EntityResponse { id, issuer, verifier }))
+}
+
+async fn store_proof(
+	State(gateway): State<Arc<Gateway>>,
+	body: Result<Json<StoreProofRequest>, JsonRejection>,
+) -> Result<(StatusCode, Json<StoredProof>), ApiError> {
+	let Json(request) = body.map_err(|rejection| ApiError::invalid(rejection.body_text()))?;
+	let proof = request.validate()?;
+	let signer = gateway.signer()?;
+
+	let proof_key = Proof::proof_key(&proof);
+	let status = gateway
+		.client
+		.runtime_api()
+		.proof_status_by_key(gateway.client.info().best_hash, proof_key)
+		.map_err(ApiError::internal)?;
+	if status != ProofStatus::Invalid(InvalidReason::NotFound) {
+		// don't spend a commitment on a proof that can't be stored
+		return Err(ApiError::new(
+			StatusCode::CONFLICT,
+			"ProofAlreadyExist",
+			"the proof is already stored",
+		));
+	}
+
+	let salt = gateway.salt(&signer, proof_key);
+	let commitment = Proof::commitment(&AccountId::from(signer), &proof_key, salt);
+	let committed: Option<U256> = gateway.read(
+		gateway.client.info().best_hash,
+		pallet_issuance_proof::ProofCommitments::<Runtime>::hashed_key_for(
+			AccountId::from(signer),
+			commitment,
+		),
+	)?;
+	// an earlier request may have committed and failed to reveal
+	if committed.is_none() {
+		gateway
+			.submit(&signer, pallet_issuance_proof::Call::commit_proof { commitment }.into())
+			.await?;
+	}
+	let call = pallet_issuance_proof::Call::store_proof {
+		proof,
+		expiration: request.expiration.into(),
+		max_uses: request.max_uses,
+		holder: request.holder,
+		attribute_root: request.attribute_root,
+		status_index: request.status_index,
+		salt,
+	};
+	let block_hash = gateway.submit(&signer, call.into()).await?;
+	Ok((StatusCode::CREATED, Json(StoredProof { proof_key, block_hash })))
+}
+
+/// Read the secret of the salts from the file at `path`, or create it on the first start. The
+/// file is only readable by the user running the node.
+fn load_salt_secret(path: &Path) -> Result<[u8; 32], String> {
+	match std::fs::read(path) {
+		Ok(secret) =>
+			return secret
+				.try_into()
+				.map_err(|_| format!("{} does not hold a 32 byte secret", path.display())),
+		Err(error) if error.kind() != std::io::ErrorKind::NotFound =>
+			return Err(format!("cannot read {}: {error}", path.display())),
+		Err(_) => {},
+	}
+
+	let mut secret = [0; 32];
+	rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut secret);
+	let mut options = std::fs::OpenOptions::new();
+	options.write(true).create_new(true);
+	#[cfg(unix)]
+	std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+	options
+		.open(path)
+		.and_then(|mut file| file.write_all(&secret).and_then(|()| file.sync_all()))
+		.map_err(|error| format!("cannot write {}: {error}", path.display()))?;
+	Ok(secret)
+}
+
+/// Serve the REST gateway on `port` of the local interface.
+pub async fn serve(port: u16, gateway: Gateway) {
+	let app = Router::new()
+		.route(
+			"/v1/openapi.json",
+			get(|| std::future::ready(([(header::CONTENT_TYPE, "application/json")], OPENAPI))),
+		)
+		.route("/v1/proofs", post(store_proof))
+		.route("/v1/proofs/{key}/status", get(proof_status))
+		.route("/v1/entities/{id}", get(entity))
+		.with_state(Arc::new(gateway));
+
+	let listener = match tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await {
+		Ok(listener) => listener,
+		Err(error) => {
+			log::error!(target: LOG_TARGET, "Failed to listen on port {port}: {error}");
+			return;
+		},
+	};
+	log::info!(target: LOG_TARGET, "REST gateway listening on http://127.0.0.1:{port}/v1");
+	if let Err(error) = axum::serve(listener, app).await {
+		log::error!(target: LOG_TARGET, "REST gateway stopped: {error}");
+	}
+}
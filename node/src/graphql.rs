@@ -0,0 +1,488 @@
+//! Optional GraphQL server over the proof registry, for dashboards. Lists come from the database
+//! of the proof indexer, the current status and crossings of a proof from the runtime APIs at the
+//! best block. The enums of the schema are generated from the types of the pallet. Both are read
+//! on the blocking thread pool, so slow queries do not hold up the server.
+//!
+//! The server listens on the local interface only: `POST /graphql` answers queries and
+//! `GET /graphql/schema` returns the schema in SDL.
+
+use crate::{
+	indexer::{self, EventFilter, IndexedEvent, IndexedProof, Indexer, Page, MAX_PAGE_SIZE},
+	metrics::{Metrics, Source},
+	service::FullClient,
+};
+use async_graphql::{
+	connection::{query, Connection, Edge, EmptyFields},
+	Context, EmptyMutation, EmptySubscription, Enum, InputObject, Json, Object, Result, Schema,
+	SimpleObject,
+};
+use async_graphql_axum::GraphQL;
+use pallet_issuance_proof_runtime_api::IssuanceProofApi;
+use solochain_template_runtime::{Hash, Runtime};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use std::{net::Ipv4Addr, sync::Arc};
+
+const LOG_TARGET: &str = "proof-graphql";
+
+/// Items per page when `first` is left out.
+const DEFAULT_PAGE_SIZE: usize = 20;
+
+/// Schema of the GraphQL API.
+pub type ProofSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Reason for which a proof is not valid.
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "pallet_issuance_proof::InvalidReason")]
+pub enum InvalidReason {
+	NotFound,
+	Expired,
+	Exhausted,
+	Revoked,
+	KeyCompromised,
+}
+
+/// Direction of a border crossing.
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "pallet_issuance_proof::CrossingDirection")]
+pub enum CrossingDirection {
+	Entry,
+	Exit,
+}
+
+/// Status of a proof in the index.
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "crate::indexer::IndexedStatus")]
+pub enum IndexedStatus {
+	Valid,
+	Expired,
+	Exhausted,
+	Revoked,
+	KeyCompromised,
+	InvalidComponent,
+	Pruned,
+}
+
+/// Role of an entity.
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+pub enum EntityRole {
+	/// Whitelisted to issue proofs.
+	Issuer,
+	/// Authorized to verify proofs.
+	Verifier,
+}
+
+impl EntityRole {
+	fn event(self) -> &'static str {
+		match self {
+			Self::Issuer => "WhitelistEntityRegistered",
+			Self::Verifier => "VerifierAuthorized",
+		}
+	}
+}
+
+/// How proofs were revoked.
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+pub enum RevocationKind {
+	/// Indexes of the status list of an issuer were updated.
+	StatusList,
+	/// An issuer declared its key compromised.
+	KeyCompromise,
+	/// Documents of an anchored batch were revoked.
+	BatchLeaves,
+}
+
+impl RevocationKind {
+	const ALL: [Self; 3] = [Self::StatusList, Self::KeyCompromise, Self::BatchLeaves];
+
+	fn event(self) -> &'static str {
+		match self {
+			Self::StatusList => "RevocationStatusUpdated",
+			Self::KeyCompromise => "KeyCompromiseDeclared",
+			Self::BatchLeaves => "BatchLeavesRevoked",
+		}
+	}
+}
+
+/// Current status of a proof, as returned by the runtime.
+#[derive(SimpleObject)]
+pub struct ProofStatus {
+	valid: bool,
+	/// Why the proof, or its component at `componentIndex`, is not valid.
+	reason: Option<InvalidReason>,
+	component_index: Option<u32>,
+}
+
+impl From<pallet_issuance_proof::ProofStatus> for ProofStatus {
+	fn from(status: pallet_issuance_proof::ProofStatus) -> Self {
+		use pallet_issuance_proof::ProofStatus::*;
+		match status {
+			Valid => Self { valid: true, reason: None, component_index: None },
+			Invalid(reason) =>
+				Self { valid: false, reason: Some(reason.into()), component_index: None },
+			InvalidComponent { index, reason } =>
+				Self { valid: false, reason: Some(reason.into()), component_index: Some(index) },
+		}
+	}
+}
+
+/// Crossings recorded with a proof, as returned by the runtime.
+#[derive(SimpleObject)]
+pub struct CrossingSummary {
+	entries: u32,
+	exits: u32,
+	last_direction: Option<CrossingDirection>,
+	last_checkpoint: Option<String>,
+	/// Unix time of the last crossing, in milliseconds.
+	last_timestamp: Option<u64>,
+	/// The holder entered and did not leave again although the proof expired.
+	overstayed: bool,
+}
+
+impl From<pallet_issuance_proof::CrossingSummary> for CrossingSummary {
+	fn from(summary: pallet_issuance_proof::CrossingSummary) -> Self {
+		let last = summary.last_crossing;
+		Self {
+			entries: summary.entries,
+			exits: summary.exits,
+			last_direction: last.as_ref().map(|record| record.direction.into()),
+			last_checkpoint: last
+				.as_ref()
+				.map(|record| String::from_utf8_lossy(&record.checkpoint).into_owned()),
+			last_timestamp: last.map(|record| record.timestamp),
+			overstayed: summary.overstayed,
+		}
+	}
+}
+
+/// Which proofs to return.
+#[derive(InputObject, Default)]
+pub struct ProofFilter {
+	/// SS58 address of the issuer.
+	issuer: Option<String>,
+	/// Stored at or after this Unix time, in milliseconds.
+	from: Option<u64>,
+	/// Stored before this Unix time, in milliseconds.
+	to: Option<u64>,
+	status: Option<IndexedStatus>,
+	/// Only proofs stored in finalized blocks.
+	#[graphql(default)]
+	finalized_only: bool,
+}
+
+impl From<ProofFilter> for indexer::ProofFilter {
+	fn from(filter: ProofFilter) -> Self {
+		Self {
+			proof_key: None,
+			issuer: filter.issuer,
+			from: filter.from,
+			to: filter.to,
+			status: filter.status.map(Into::into),
+			finalized_only: filter.finalized_only,
+		}
+	}
+}
+
+/// A proof stored in the registry.
+pub struct Proof(IndexedProof);
+
+#[Object]
+impl Proof {
+	async fn proof_key(&self) -> String {
+		format!("{:?}", self.0.proof_key)
+	}
+
+	/// SS58 address of the issuer.
+	async fn issuer(&self) -> &str {
+		&self.0.issuer
+	}
+
+	/// Block the proof was stored in.
+	async fn block_number(&self) -> u32 {
+		self.0.block_number
+	}
+
+	/// Unix time of that block, in milliseconds.
+	async fn timestamp(&self) -> u64 {
+		self.0.timestamp
+	}
+
+	/// Last block in which the proof is valid.
+	async fn expiry_block(&self) -> u64 {
+		self.0.expiry_block
+	}
+
+	/// Status as last seen by the indexer.
+	async fn indexed_status(&self) -> IndexedStatus {
+		self.0.status.into()
+	}
+
+	/// Whether the block the proof was stored in is finalized.
+	async fn finalized(&self) -> bool {
+		self.0.finalized
+	}
+
+	/// Status at the best block.
+	async fn status(&self, ctx: &Context<'_>) -> Result<ProofStatus> {
+		let metrics = ctx.data_opt::<Metrics>().cloned();
+		let status = self
+			.runtime_call(ctx, move |client, at, proof| {
+				Ok(Metrics::status_call(metrics.as_ref(), Source::Graphql, || {
+					client.runtime_api().proof_status(at, proof)
+				})?)
+			})
+			.await?;
+		Ok(status.into())
+	}
+
+	/// Crossings at the best block.
+	async fn crossing_summary(&self, ctx: &Context<'_>) -> Result<Option<CrossingSummary>> {
+		let summary = self
+			.runtime_call(ctx, |client, at, proof| {
+				Ok(client.runtime_api().crossing_summary(at, proof)?)
+			})
+			.await?;
+		Ok(summary.map(Into::into))
+	}
+
+	/// Events about the proof, oldest first.
+	async fn events(
+		&self,
+		ctx: &Context<'_>,
+		after: Option<String>,
+		first: Option<i32>,
+	) -> Result<Connection<usize, Event, EmptyFields, EmptyFields>> {
+		let filter = EventFilter { proof_key: Some(self.0.proof_key), ..Default::default() };
+		let indexer = indexer(ctx).clone();
+		paginate(after, first, move |offset, limit| indexer.events(&filter, offset, limit), Event)
+			.await
+	}
+}
+
+impl Proof {
+	/// Call the runtime at the best block with the bytes of the proof.
+	async fn runtime_call<R: Send + 'static>(
+		&self,
+		ctx: &Context<'_>,
+		call: impl FnOnce(&FullClient, Hash, pallet_issuance_proof::ProofBytes<Runtime>) -> Result<R>
+			+ Send
+			+ 'static,
+	) -> Result<R> {
+		let indexer = indexer(ctx).clone();
+		let client = ctx.data_unchecked::<Arc<FullClient>>().clone();
+		let proof_key = self.0.proof_key;
+		blocking(move || {
+			let proof = indexer.proof_bytes(proof_key)?.unwrap_or_default();
+			let proof = proof.try_into().map_err(|_| "proof is too long")?;
+			call(&client, client.info().best_hash, proof)
+		})
+		.await
+	}
+}
+
+/// An event of the pallet.
+pub struct Event(IndexedEvent);
+
+#[Object]
+impl Event {
+	async fn block_number(&self) -> u32 {
+		self.0.block_number
+	}
+
+	/// Name of the event, e.g. `ProofConsumed`.
+	async fn kind(&self) -> &str {
+		&self.0.kind
+	}
+
+	/// SS58 address of the issuer or verifier of the event.
+	async fn account(&self) -> Option<&str> {
+		self.0.account.as_deref()
+	}
+
+	/// The other fields of the event.
+	async fn data(&self) -> Json<&serde_json::Value> {
+		Json(&self.0.data)
+	}
+
+	/// Whether the block of the event is finalized.
+	async fn finalized(&self) -> bool {
+		self.0.finalized
+	}
+}
+
+/// An issuer or verifier.
+#[derive(SimpleObject)]
+pub struct Entity {
+	/// SS58 address of the entity.
+	account: String,
+	role: EntityRole,
+	/// Block the entity was registered or authorized in.
+	block_number: u32,
+	finalized: bool,
+}
+
+/// A revocation of proofs.
+#[derive(SimpleObject)]
+pub struct Revocation {
+	kind: RevocationKind,
+	/// SS58 address of the issuer, unknown for batch leaves.
+	issuer: Option<String>,
+	block_number: u32,
+	/// The other fields of the event, e.g. the number of revoked entries.
+	data: Json<serde_json::Value>,
+	finalized: bool,
+}
+
+/// Queries of the registry.
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+	/// The proof with the given key, if it was indexed.
+	async fn proof(&self, ctx: &Context<'_>, proof_key: String) -> Result<Option<Proof>> {
+		let proof_key: Hash = proof_key.parse().map_err(|_| "invalid proof key")?;
+		let filter = indexer::ProofFilter { proof_key: Some(proof_key), ..Default::default() };
+		let indexer = indexer(ctx).clone();
+		blocking(move || Ok(indexer.proofs(&filter, 0, 1)?.items.pop().map(Proof))).await
+	}
+
+	/// Proofs matching `filter`, in the order they were stored.
+	async fn proofs(
+		&self,
+		ctx: &Context<'_>,
+		filter: Option<ProofFilter>,
+		after: Option<String>,
+		first: Option<i32>,
+	) -> Result<Connection<usize, Proof, EmptyFields, EmptyFields>> {
+		let filter = filter.unwrap_or_default().into();
+		let indexer = indexer(ctx).clone();
+		paginate(after, first, move |offset, limit| indexer.proofs(&filter, offset, limit), Proof)
+			.await
+	}
+
+	/// Issuers and verifiers, in the order they were registered.
+	async fn entities(
+		&self,
+		ctx: &Context<'_>,
+		role: Option<EntityRole>,
+		after: Option<String>,
+		first: Option<i32>,
+	) -> Result<Connection<usize, Entity, EmptyFields, EmptyFields>> {
+		let roles = role.map_or(vec![EntityRole::Issuer, EntityRole::Verifier], |role| vec![role]);
+		let filter = EventFilter {
+			kinds: roles.iter().map(|role| role.event().to_owned()).collect(),
+			..Default::default()
+		};
+		let indexer = indexer(ctx).clone();
+		paginate(
+			after,
+			first,
+			move |offset, limit| indexer.events(&filter, offset, limit),
+			|event| Entity {
+				account: event.account.unwrap_or_default(),
+				role: if event.kind == EntityRole::Issuer.event() {
+					EntityRole::Issuer
+				} else {
+					EntityRole::Verifier
+				},
+				block_number: event.block_number,
+				finalized: event.finalized,
+			},
+		)
+		.await
+	}
+
+	/// Revocations, oldest first.
+	async fn revocations(
+		&self,
+		ctx: &Context<'_>,
+		issuer: Option<String>,
+		kind: Option<RevocationKind>,
+		after: Option<String>,
+		first: Option<i32>,
+	) -> Result<Connection<usize, Revocation, EmptyFields, EmptyFields>> {
+		let kinds = kind.map_or(RevocationKind::ALL.to_vec(), |kind| vec![kind]);
+		let filter = EventFilter {
+			account: issuer,
+			kinds: kinds.iter().map(|kind| kind.event().to_owned()).collect(),
+			..Default::default()
+		};
+		let indexer = indexer(ctx).clone();
+		paginate(
+			after,
+			first,
+			move |offset, limit| indexer.events(&filter, offset, limit),
+			|event| Revocation {
+				kind: RevocationKind::ALL
+					.into_iter()
+					.find(|kind| kind.event() == event.kind)
+					.unwrap_or(RevocationKind::StatusList),
+				issuer: event.account,
+				block_number: event.block_number,
+				data: Json(event.data),
+				finalized: event.finalized,
+			},
+		)
+		.await
+	}
+}
+
+fn indexer<'a>(ctx: &Context<'a>) -> &'a Indexer {
+	ctx.data_unchecked::<Indexer>()
+}
+
+/// Run `call`, which reads the database or calls the runtime, on the blocking thread pool.
+async fn blocking<R: Send + 'static>(
+	call: impl FnOnce() -> Result<R> + Send + 'static,
+) -> Result<R> {
+	tokio::task::spawn_blocking(call).await?
+}
+
+/// Page through the results of `fetch` with cursors made of the position of each item.
+async fn paginate<T: Send + 'static, N: async_graphql::OutputType>(
+	after: Option<String>,
+	first: Option<i32>,
+	fetch: impl FnOnce(u32, u32) -> Result<Page<T>, indexer::Error> + Send + 'static,
+	node: impl Fn(T) -> N,
+) -> Result<Connection<usize, N, EmptyFields, EmptyFields>> {
+	query(after, None, first, None, |after: Option<usize>, _, first, _| async move {
+		let offset = after.map_or(0, |after| after + 1);
+		let limit = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE as usize);
+		let page = blocking(move || Ok(fetch(offset as u32, limit as u32)?)).await?;
+		let mut connection =
+			Connection::new(offset > 0, offset + page.items.len() < page.total as usize);
+		connection.edges.extend(
+			page.items.into_iter().enumerate().map(|(i, item)| Edge::new(offset + i, node(item))),
+		);
+		Ok::<_, async_graphql::Error>(connection)
+	})
+	.await
+}
+
+/// Serve the GraphQL API on `port` of the local interface.
+pub async fn serve(port: u16, indexer: Indexer, client: Arc<FullClient>, metrics: Option<Metrics>) {
+	use axum::routing::{get, post_service};
+
+	let mut schema =
+		ProofSchema::build(QueryRoot, EmptyMutation, EmptySubscription).data(indexer).data(client);
+	if let Some(metrics) = metrics {
+		schema = schema.data(metrics);
+	}
+	let schema = schema.finish();
+	let sdl = schema.sdl();
+	let app = axum::Router::new()
+		.route("/graphql", post_service(GraphQL::new(schema)))
+		.route("/graphql/schema", get(move || std::future::ready(sdl.clone())));
+
+	let listener = match tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await {
+		Ok(listener) => listener,
+		Err(error) => {
+			log::error!(target: LOG_TARGET, "Failed to listen on port {port}: {error}");
+			return;
+		},
+	};
+	log::info!(target: LOG_TARGET, "GraphQL server listening on http://127.0.0.1:{port}/graphql");
+	if let Err(error) = axum::serve(listener, app).await {
+		log::error!(target: LOG_TARGET, "GraphQL server stopped: {error}");
+	}
+}
@@ -0,0 +1,819 @@
+//! Optional indexer of the events of the issuance proof pallet into a local SQLite database, so
+//! that proofs can be queried by issuer, date and status.
+//!
+//! The indexer follows the best chain and marks blocks as finalized as finality comes in. When
+//! the best chain is reorganized, the blocks that left it are reverted from the database before
+//! the new ones are indexed; finalized blocks are never reverted.
+//!
+//! The status of a proof is asked to the runtime when one of its events is indexed, when its
+//! issuer updates its entry of the status list, and when its issuer declares a key compromise
+//! covering the block it was stored in. The runtime is called before the block is written, so
+//! queries are not held up meanwhile. Expiry is tracked from the block number and pruned proofs
+//! are marked from the keys in the event. The status of a composite proof is not updated when one
+//! of its components changes.
+
+use crate::service::FullClient;
+use codec::Decode;
+use pallet_issuance_proof::{
+	CrossingDirection, Event as ProofEvent, InvalidReason, ProofBytes, ProofStatus,
+};
+use pallet_issuance_proof_runtime_api::IssuanceProofApi;
+use rusqlite::{params, params_from_iter, types::Value, Connection, OptionalExtension};
+use sc_client_api::{BlockchainEvents, StorageProvider};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use solochain_template_runtime::{BlockNumber, Hash, Runtime, RuntimeEvent};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{storage::StorageKey, twox_128, U256};
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	path::Path,
+	sync::{Arc, Mutex},
+};
+
+const LOG_TARGET: &str = "proof-indexer";
+
+/// Most items returned by one query.
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+const SCHEMA: &str = "
+	CREATE TABLE IF NOT EXISTS blocks (
+		number INTEGER PRIMARY KEY,
+		hash BLOB NOT NULL,
+		timestamp INTEGER NOT NULL,
+		finalized INTEGER NOT NULL DEFAULT 0
+	);
+	CREATE TABLE IF NOT EXISTS proofs (
+		proof_key BLOB PRIMARY KEY,
+		proof BLOB NOT NULL,
+		issuer TEXT NOT NULL,
+		block_number INTEGER NOT NULL REFERENCES blocks (number) ON DELETE CASCADE,
+		timestamp INTEGER NOT NULL,
+		expiry_block INTEGER NOT NULL,
+		status TEXT NOT NULL,
+		status_block INTEGER NOT NULL,
+		status_index INTEGER
+	);
+	CREATE INDEX IF NOT EXISTS proofs_by_issuer ON proofs (issuer, timestamp);
+	CREATE INDEX IF NOT EXISTS proofs_by_timestamp ON proofs (timestamp);
+	CREATE INDEX IF NOT EXISTS proofs_by_status_block ON proofs (status_block);
+	CREATE INDEX IF NOT EXISTS proofs_by_status_index ON proofs (issuer, status_index);
+	CREATE TABLE IF NOT EXISTS events (
+		block_number INTEGER NOT NULL REFERENCES blocks (number) ON DELETE CASCADE,
+		event_index INTEGER NOT NULL,
+		kind TEXT NOT NULL,
+		proof_key BLOB,
+		account TEXT,
+		data TEXT NOT NULL,
+		PRIMARY KEY (block_number, event_index)
+	);
+	CREATE INDEX IF NOT EXISTS events_by_proof ON events (proof_key);
+	CREATE INDEX IF NOT EXISTS events_by_account ON events (account);
+";
+
+/// Status of an indexed proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IndexedStatus {
+	/// The proof can be used.
+	Valid,
+	/// The proof expired.
+	Expired,
+	/// All the uses of the proof were consumed.
+	Exhausted,
+	/// The issuer revoked the proof in its status list.
+	Revoked,
+	/// The proof was stored while the key of the issuer was compromised.
+	KeyCompromised,
+	/// One of the components of the composite proof is not valid.
+	InvalidComponent,
+	/// The proof was pruned from the registry after it expired.
+	Pruned,
+}
+
+impl IndexedStatus {
+	fn as_str(self) -> &'static str {
+		match self {
+			Self::Valid => "valid",
+			Self::Expired => "expired",
+			Self::Exhausted => "exhausted",
+			Self::Revoked => "revoked",
+			Self::KeyCompromised => "keyCompromised",
+			Self::InvalidComponent => "invalidComponent",
+			Self::Pruned => "pruned",
+		}
+	}
+
+	fn parse(status: &str) -> Option<Self> {
+		[
+			Self::Valid,
+			Self::Expired,
+			Self::Exhausted,
+			Self::Revoked,
+			Self::KeyCompromised,
+			Self::InvalidComponent,
+			Self::Pruned,
+		]
+		.into_iter()
+		.find(|candidate| candidate.as_str() == status)
+	}
+}
+
+impl From<ProofStatus> for IndexedStatus {
+	fn from(status: ProofStatus) -> Self {
+		match status {
+			ProofStatus::Valid => Self::Valid,
+			ProofStatus::Invalid(InvalidReason::NotFound) => Self::Pruned,
+			ProofStatus::Invalid(InvalidReason::Expired) => Self::Expired,
+			ProofStatus::Invalid(InvalidReason::Exhausted) => Self::Exhausted,
+			ProofStatus::Invalid(InvalidReason::Revoked) => Self::Revoked,
+			ProofStatus::Invalid(InvalidReason::KeyCompromised) => Self::KeyCompromised,
+			ProofStatus::InvalidComponent { .. } => Self::InvalidComponent,
+		}
+	}
+}
+
+/// Which proofs to return. Every field left out matches all proofs.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofFilter {
+	/// Key of the proof.
+	pub proof_key: Option<Hash>,
+	/// SS58 address of the issuer.
+	pub issuer: Option<String>,
+	/// Stored at or after this Unix time, in milliseconds.
+	pub from: Option<u64>,
+	/// Stored before this Unix time, in milliseconds.
+	pub to: Option<u64>,
+	/// Current status of the proof.
+	pub status: Option<IndexedStatus>,
+	/// Only proofs stored in finalized blocks.
+	#[serde(default)]
+	pub finalized_only: bool,
+}
+
+/// Which events to return. Every field left out matches all events.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventFilter {
+	/// Key of the proof the event is about.
+	pub proof_key: Option<Hash>,
+	/// SS58 address of the issuer or verifier of the event.
+	pub account: Option<String>,
+	/// Names of the events, e.g. `["ProofConsumed"]`. Empty matches all events.
+	#[serde(default)]
+	pub kinds: Vec<String>,
+}
+
+/// A proof in the index.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedProof {
+	/// Key of the proof.
+	pub proof_key: Hash,
+	/// SS58 address of the issuer.
+	pub issuer: String,
+	/// Block the proof was stored in.
+	pub block_number: BlockNumber,
+	/// Unix time of that block, in milliseconds.
+	pub timestamp: u64,
+	/// Last block in which the proof is valid.
+	pub expiry_block: u64,
+	/// Current status of the proof.
+	pub status: IndexedStatus,
+	/// Whether the block the proof was stored in is finalized.
+	pub finalized: bool,
+}
+
+/// An event in the index.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedEvent {
+	/// Block of the event.
+	pub block_number: BlockNumber,
+	/// Position of the event in the block.
+	pub event_index: u32,
+	/// Name of the event.
+	pub kind: String,
+	/// Key of the proof the event is about, if any.
+	pub proof_key: Option<Hash>,
+	/// SS58 address of the issuer or verifier of the event, if any.
+	pub account: Option<String>,
+	/// The other fields of the event.
+	pub data: serde_json::Value,
+	/// Whether the block is finalized.
+	pub finalized: bool,
+}
+
+/// A page of query results.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<T> {
+	/// Number of items matching the query.
+	pub total: u32,
+	/// The items of the page.
+	pub items: Vec<T>,
+}
+
+/// Errors of the indexer.
+#[derive(Debug)]
+pub enum Error {
+	/// The database could not be read or written.
+	Database(rusqlite::Error),
+	/// The client could not be read.
+	Client(sp_blockchain::Error),
+	/// A runtime API call failed.
+	Api(sp_api::ApiError),
+	/// A block of the best chain is not known to the client anymore.
+	UnknownBlock(BlockNumber),
+	/// A finalized block of the database is not on the chain of the node.
+	Diverged(BlockNumber),
+}
+
+impl std::fmt::Display for Error {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Database(error) => write!(f, "database error: {error}"),
+			Self::Client(error) => write!(f, "client error: {error}"),
+			Self::Api(error) => write!(f, "runtime API error: {error}"),
+			Self::UnknownBlock(number) => write!(f, "block #{number} is not known"),
+			Self::Diverged(number) => write!(
+				f,
+				"finalized block #{number} is not on the chain, the database belongs to another chain"
+			),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl From<rusqlite::Error> for Error {
+	fn from(error: rusqlite::Error) -> Self {
+		Self::Database(error)
+	}
+}
+
+impl From<sp_blockchain::Error> for Error {
+	fn from(error: sp_blockchain::Error) -> Self {
+		Self::Client(error)
+	}
+}
+
+impl From<sp_api::ApiError> for Error {
+	fn from(error: sp_api::ApiError) -> Self {
+		Self::Api(error)
+	}
+}
+
+/// Handle on the index, shared by the indexing task and the query RPCs.
+#[derive(Clone)]
+pub struct Indexer {
+	db: Arc<Mutex<Connection>>,
+}
+
+impl Indexer {
+	/// Open the database at `path`, creating it if needed.
+	pub fn open(path: &Path) -> Result<Self, Error> {
+		let db = Connection::open(path)?;
+		db.pragma_update(None, "journal_mode", "WAL")?;
+		db.pragma_update(None, "foreign_keys", true)?;
+		db.execute_batch(SCHEMA)?;
+		Ok(Self { db: Arc::new(Mutex::new(db)) })
+	}
+
+	fn db(&self) -> std::sync::MutexGuard<'_, Connection> {
+		self.db.lock().expect("the database lock is never held across a panic; qed")
+	}
+
+	/// Follow the chain of `client`, until its notification streams end or the database turns
+	/// out to belong to another chain.
+	pub async fn run(self, client: Arc<FullClient>) {
+		use futures::StreamExt;
+
+		let mut notifications = futures::stream::select(
+			client
+				.import_notification_stream()
+				.filter(|notification| futures::future::ready(notification.is_new_best))
+				.map(|_| ()),
+			client.finality_notification_stream().map(|_| ()),
+		);
+		loop {
+			match self.sync(&client) {
+				Ok(()) => {},
+				Err(error @ Error::Diverged(_)) => {
+					log::error!(target: LOG_TARGET, "Stopping: {error}");
+					return;
+				},
+				Err(error) => log::warn!(target: LOG_TARGET, "Failed to index blocks: {error}"),
+			}
+			if notifications.next().await.is_none() {
+				return;
+			}
+		}
+	}
+
+	/// Bring the index to the best block of `client`.
+	fn sync(&self, client: &FullClient) -> Result<(), Error> {
+		let info = client.info();
+
+		// revert the blocks that left the best chain
+		let mut reverted = BTreeSet::new();
+		let mut tip = self.tip()?;
+		while let Some((number, hash, finalized)) = tip {
+			if client.hash(number)? == Some(hash) {
+				break;
+			}
+			if finalized {
+				return Err(Error::Diverged(number));
+			}
+			reverted.extend(self.revert(number)?);
+			tip = self.tip()?;
+		}
+
+		let start = tip.map_or(1, |(number, ..)| number + 1);
+		for number in start..=info.best_number {
+			let hash = client.hash(number)?.ok_or(Error::UnknownBlock(number))?;
+			self.index_block(client, number, hash)?;
+		}
+
+		// the proofs whose status was set in reverted blocks are checked again at the new best
+		if !reverted.is_empty() {
+			if let Some((number, hash, _)) = self.tip()? {
+				let proofs = self.proofs_to_refresh(reverted, &[], &[])?;
+				let statuses = statuses(client, hash, proofs)?;
+				let mut db = self.db();
+				let tx = db.transaction()?;
+				set_statuses(&tx, number, statuses)?;
+				tx.commit()?;
+			}
+		}
+
+		self.db().execute(
+			"UPDATE blocks SET finalized = 1 WHERE number <= ?1 AND finalized = 0",
+			[info.finalized_number],
+		)?;
+		Ok(())
+	}
+
+	/// Number of the last indexed block.
+	pub fn indexed_number(&self) -> Result<Option<BlockNumber>, Error> {
+		Ok(self.tip()?.map(|(number, ..)| number))
+	}
+
+	/// Number, hash and finality of the last indexed block.
+	fn tip(&self) -> Result<Option<(BlockNumber, Hash, bool)>, Error> {
+		let tip = self
+			.db()
+			.query_row(
+				"SELECT number, hash, finalized FROM blocks ORDER BY number DESC LIMIT 1",
+				[],
+				|row| {
+					let hash: Vec<u8> = row.get(1)?;
+					Ok((row.get(0)?, Hash::from_slice(&hash), row.get(2)?))
+				},
+			)
+			.optional()?;
+		Ok(tip)
+	}
+
+	/// Remove the blocks from `number` on, with the proofs stored and the events emitted in
+	/// them. Returns the remaining proofs whose status was set in those blocks.
+	fn revert(&self, number: BlockNumber) -> Result<Vec<Hash>, Error> {
+		let mut db = self.db();
+		let tx = db.transaction()?;
+		tx.execute("DELETE FROM blocks WHERE number >= ?1", [number])?;
+		let reverted =
+			proof_keys(&tx, "SELECT proof_key FROM proofs WHERE status_block >= ?1", [number])?;
+		tx.commit()?;
+		log::info!(target: LOG_TARGET, "Reverted blocks from #{number}");
+		Ok(reverted)
+	}
+
+	fn index_block(&self, client: &FullClient, number: BlockNumber, hash: Hash) -> Result<(), Error> {
+		let events: Vec<frame_system::EventRecord<RuntimeEvent, Hash>> =
+			read_storage(client, hash, b"System", b"Events")?.unwrap_or_default();
+		let timestamp: u64 = read_storage(client, hash, b"Timestamp", b"Now")?.unwrap_or_default();
+		let events: Vec<(u32, ProofEvent<Runtime>)> = events
+			.into_iter()
+			.enumerate()
+			.filter_map(|(index, record)| match record.event {
+				RuntimeEvent::Proof(event) => Some((index as u32, event)),
+				_ => None,
+			})
+			.collect();
+
+		// the statuses are asked to the runtime before the database is written, so that queries
+		// are not held up by the runtime calls
+		let mut stored = BTreeMap::new();
+		let mut touched = BTreeSet::new();
+		let mut compromised = Vec::new();
+		let mut status_updates = Vec::new();
+		let mut pruned = Vec::new();
+		for (_, event) in &events {
+			match event {
+				ProofEvent::ProofStored { proof_key, proof, .. } => {
+					stored.insert(*proof_key, proof.to_vec());
+				},
+				// only the proofs holding the updated entries change
+				ProofEvent::RevocationStatusUpdated { issuer, indexes, .. } =>
+					status_updates.push((issuer.to_string(), indexes.to_vec())),
+				// only the proofs stored within the window are revoked
+				ProofEvent::KeyCompromiseDeclared { key, since, until } =>
+					compromised.push((key.to_string(), saturated(*since), saturated(*until))),
+				ProofEvent::ProofsPruned { proof_keys } =>
+					pruned.extend(proof_keys.iter().copied()),
+				_ => {},
+			}
+			touched.extend(describe(event.clone()).and_then(|(_, proof_key, ..)| proof_key));
+		}
+		let mut proofs = self.proofs_to_refresh(touched, &compromised, &status_updates)?;
+		proofs.extend(stored);
+		// pruned proofs are gone from the registry, there is nothing to ask the runtime
+		for proof_key in &pruned {
+			proofs.remove(proof_key);
+		}
+		let mut statuses = statuses(client, hash, proofs)?;
+		statuses.extend(pruned.into_iter().map(|proof_key| (proof_key, IndexedStatus::Pruned)));
+
+		let mut db = self.db();
+		let tx = db.transaction()?;
+		tx.execute(
+			"INSERT INTO blocks (number, hash, timestamp) VALUES (?1, ?2, ?3)",
+			params![number, hash.as_bytes(), timestamp],
+		)?;
+		for (index, event) in events {
+			if let ProofEvent::ProofStored {
+				proof_key,
+				ref proof,
+				ref issuer,
+				expiry_block,
+				status_index,
+				..
+			} = event
+			{
+				tx.execute(
+					"INSERT OR REPLACE INTO proofs
+						(proof_key, proof, issuer, block_number, timestamp, expiry_block, status,
+						status_block, status_index)
+					VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?4, ?8)",
+					params![
+						proof_key.as_bytes(),
+						proof.as_slice(),
+						issuer.to_string(),
+						number,
+						timestamp,
+						saturated(expiry_block),
+						IndexedStatus::Valid.as_str(),
+						status_index,
+					],
+				)?;
+			}
+			let Some((kind, proof_key, account, data)) = describe(event) else { continue };
+			tx.execute(
+				"INSERT INTO events (block_number, event_index, kind, proof_key, account, data)
+				VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+				params![
+					number,
+					index,
+					kind,
+					proof_key.as_ref().map(Hash::as_bytes),
+					account,
+					data.to_string(),
+				],
+			)?;
+		}
+
+		tx.execute(
+			"UPDATE proofs SET status = ?1, status_block = ?2
+			WHERE status = ?3 AND expiry_block < ?2",
+			params![IndexedStatus::Expired.as_str(), number, IndexedStatus::Valid.as_str()],
+		)?;
+		set_statuses(&tx, number, statuses)?;
+		tx.commit()?;
+		Ok(())
+	}
+
+	/// Bytes of the indexed proofs among `proof_keys`, of the proofs stored by the issuers in
+	/// `compromised` within the given window of blocks, and of the proofs holding the entries in
+	/// `status_updates` of the status list of their issuer.
+	fn proofs_to_refresh(
+		&self,
+		proof_keys: BTreeSet<Hash>,
+		compromised: &[(String, i64, i64)],
+		status_updates: &[(String, Vec<u32>)],
+	) -> Result<BTreeMap<Hash, Vec<u8>>, Error> {
+		let db = self.db();
+		let mut proofs = BTreeMap::new();
+		for proof_key in proof_keys {
+			let proof = db
+				.query_row(
+					"SELECT proof FROM proofs WHERE proof_key = ?1",
+					[proof_key.as_bytes()],
+					|row| row.get(0),
+				)
+				.optional()?;
+			proofs.extend(proof.map(|proof| (proof_key, proof)));
+		}
+		for (issuer, since, until) in compromised {
+			let query = "SELECT proof_key, proof FROM proofs
+				WHERE issuer = ?1 AND block_number BETWEEN ?2 AND ?3";
+			proofs.extend(keyed_proofs(&db, query, params![issuer, since, until])?);
+		}
+		for (issuer, indexes) in status_updates {
+			// an entry released by an expired proof may be held by a newer one
+			let query =
+				"SELECT proof_key, proof FROM proofs WHERE issuer = ?1 AND status_index = ?2";
+			for index in indexes.iter().collect::<BTreeSet<_>>() {
+				proofs.extend(keyed_proofs(&db, query, params![issuer, index])?);
+			}
+		}
+		Ok(proofs)
+	}
+
+	/// Bytes of the proof with the given key.
+	pub fn proof_bytes(&self, proof_key: Hash) -> Result<Option<Vec<u8>>, Error> {
+		let proof = self
+			.db()
+			.query_row("SELECT proof FROM proofs WHERE proof_key = ?1", [proof_key.as_bytes()], |row| {
+				row.get(0)
+			})
+			.optional()?;
+		Ok(proof)
+	}
+
+	/// Proofs matching `filter`, oldest first.
+	pub fn proofs(
+		&self,
+		filter: &ProofFilter,
+		offset: u32,
+		limit: u32,
+	) -> Result<Page<IndexedProof>, Error> {
+		let mut clauses = vec!["1 = 1"];
+		let mut values = Vec::<Value>::new();
+		if let Some(proof_key) = filter.proof_key {
+			clauses.push("p.proof_key = ?");
+			values.push(proof_key.as_bytes().to_vec().into());
+		}
+		if let Some(issuer) = &filter.issuer {
+			clauses.push("p.issuer = ?");
+			values.push(issuer.clone().into());
+		}
+		if let Some(from) = filter.from {
+			clauses.push("p.timestamp >= ?");
+			values.push(saturated(from.into()).into());
+		}
+		if let Some(to) = filter.to {
+			clauses.push("p.timestamp < ?");
+			values.push(saturated(to.into()).into());
+		}
+		if let Some(status) = filter.status {
+			clauses.push("p.status = ?");
+			values.push(status.as_str().to_owned().into());
+		}
+		if filter.finalized_only {
+			clauses.push("b.finalized = 1");
+		}
+		let from = format!(
+			"FROM proofs p JOIN blocks b ON b.number = p.block_number WHERE {}",
+			clauses.join(" AND ")
+		);
+
+		let db = self.db();
+		let total = db.query_row(
+			&format!("SELECT COUNT(*) {from}"),
+			params_from_iter(values.iter()),
+			|row| row.get(0),
+		)?;
+		values.extend([Value::from(limit.min(MAX_PAGE_SIZE)), Value::from(offset)]);
+		let mut statement = db.prepare(&format!(
+			"SELECT p.proof_key, p.issuer, p.block_number, p.timestamp, p.expiry_block, p.status,
+				b.finalized
+			{from} ORDER BY p.block_number, p.rowid LIMIT ? OFFSET ?"
+		))?;
+		let items = statement
+			.query_map(params_from_iter(values.iter()), |row| {
+				let proof_key: Vec<u8> = row.get(0)?;
+				let status: String = row.get(5)?;
+				Ok(IndexedProof {
+					proof_key: Hash::from_slice(&proof_key),
+					issuer: row.get(1)?,
+					block_number: row.get(2)?,
+					timestamp: row.get(3)?,
+					expiry_block: row.get(4)?,
+					status: IndexedStatus::parse(&status).unwrap_or(IndexedStatus::Valid),
+					finalized: row.get(6)?,
+				})
+			})?
+			.collect::<Result<_, _>>()?;
+		Ok(Page { total, items })
+	}
+
+	/// Events matching `filter`, oldest first.
+	pub fn events(
+		&self,
+		filter: &EventFilter,
+		offset: u32,
+		limit: u32,
+	) -> Result<Page<IndexedEvent>, Error> {
+		let mut clauses = vec!["1 = 1".to_owned()];
+		let mut values = Vec::<Value>::new();
+		if let Some(proof_key) = filter.proof_key {
+			clauses.push("e.proof_key = ?".to_owned());
+			values.push(proof_key.as_bytes().to_vec().into());
+		}
+		if let Some(account) = &filter.account {
+			clauses.push("e.account = ?".to_owned());
+			values.push(account.clone().into());
+		}
+		if !filter.kinds.is_empty() {
+			clauses.push(format!("e.kind IN ({})", vec!["?"; filter.kinds.len()].join(", ")));
+			values.extend(filter.kinds.iter().cloned().map(Value::from));
+		}
+		let from = format!(
+			"FROM events e JOIN blocks b ON b.number = e.block_number WHERE {}",
+			clauses.join(" AND ")
+		);
+
+		let db = self.db();
+		let total = db.query_row(
+			&format!("SELECT COUNT(*) {from}"),
+			params_from_iter(values.iter()),
+			|row| row.get(0),
+		)?;
+		values.extend([Value::from(limit.min(MAX_PAGE_SIZE)), Value::from(offset)]);
+		let mut statement = db.prepare(&format!(
+			"SELECT e.block_number, e.event_index, e.kind, e.proof_key, e.account, e.data,
+				b.finalized
+			{from} ORDER BY e.block_number, e.event_index LIMIT ? OFFSET ?"
+		))?;
+		let items = statement
+			.query_map(params_from_iter(values.iter()), |row| {
+				let proof_key: Option<Vec<u8>> = row.get(3)?;
+				let data: String = row.get(5)?;
+				Ok(IndexedEvent {
+					block_number: row.get(0)?,
+					event_index: row.get(1)?,
+					kind: row.get(2)?,
+					proof_key: proof_key.map(|key| Hash::from_slice(&key)),
+					account: row.get(4)?,
+					data: serde_json::from_str(&data).unwrap_or_default(),
+					finalized: row.get(6)?,
+				})
+			})?
+			.collect::<Result<_, _>>()?;
+		Ok(Page { total, items })
+	}
+}
+
+/// Status of each of `proofs` at block `hash`, by proof key.
+fn statuses(
+	client: &FullClient,
+	hash: Hash,
+	proofs: BTreeMap<Hash, Vec<u8>>,
+) -> Result<Vec<(Hash, IndexedStatus)>, Error> {
+	let api = client.runtime_api();
+	let mut statuses = Vec::with_capacity(proofs.len());
+	for (proof_key, proof) in proofs {
+		let Ok(proof) = ProofBytes::<Runtime>::try_from(proof) else { continue };
+		statuses.push((proof_key, IndexedStatus::from(api.proof_status(hash, proof)?)));
+	}
+	Ok(statuses)
+}
+
+fn set_statuses(
+	tx: &rusqlite::Transaction,
+	number: BlockNumber,
+	statuses: Vec<(Hash, IndexedStatus)>,
+) -> Result<(), Error> {
+	for (proof_key, status) in statuses {
+		tx.execute(
+			"UPDATE proofs SET status = ?1, status_block = ?2 WHERE proof_key = ?3",
+			params![status.as_str(), number, proof_key.as_bytes()],
+		)?;
+	}
+	Ok(())
+}
+
+fn keyed_proofs(
+	db: &Connection,
+	query: &str,
+	params: impl rusqlite::Params,
+) -> Result<Vec<(Hash, Vec<u8>)>, Error> {
+	let mut statement = db.prepare_cached(query)?;
+	let proofs = statement
+		.query_map(params, |row| Ok((Hash::from_slice(&row.get::<_, Vec<u8>>(0)?), row.get(1)?)))?
+		.collect::<Result<_, _>>()?;
+	Ok(proofs)
+}
+
+fn proof_keys(
+	tx: &rusqlite::Transaction,
+	query: &str,
+	params: impl rusqlite::Params,
+) -> Result<Vec<Hash>, Error> {
+	let mut statement = tx.prepare(query)?;
+	let keys = statement
+		.query_map(params, |row| row.get::<_, Vec<u8>>(0))?
+		.map(|key| key.map(|key| Hash::from_slice(&key)))
+		.collect::<Result<_, _>>()?;
+	Ok(keys)
+}
+
+/// Decode the storage value `item` of `pallet` at block `hash`.
+pub(crate) fn read_storage<T: Decode>(
+	client: &FullClient,
+	hash: Hash,
+	pallet: &[u8],
+	item: &[u8],
+) -> Result<Option<T>, Error> {
+	let key = StorageKey([twox_128(pallet), twox_128(item)].concat());
+	let Some(data) = client.storage(hash, &key)? else { return Ok(None) };
+	// a runtime upgrade may change the types; the block is indexed without them
+	match T::decode(&mut &data.0[..]) {
+		Ok(value) => Ok(Some(value)),
+		Err(error) => {
+			let (pallet, item) = (String::from_utf8_lossy(pallet), String::from_utf8_lossy(item));
+			log::warn!(target: LOG_TARGET, "Failed to decode {pallet}::{item} at {hash}: {error}");
+			Ok(None)
+		},
+	}
+}
+
+/// Name, proof key, account and other fields of the events kept in the index.
+pub(crate) fn describe(
+	event: ProofEvent<Runtime>,
+) -> Option<(&'static str, Option<Hash>, Option<String>, serde_json::Value)> {
+	let described = match event {
+		ProofEvent::ProofStored {
+			proof_key, issuer, expiry_block, max_uses, status_index, ..
+		} => (
+			"ProofStored",
+			Some(proof_key),
+			Some(issuer.to_string()),
+			json!({
+				"expiryBlock": saturated(expiry_block),
+				"maxUses": max_uses,
+				"statusIndex": status_index,
+			}),
+		),
+		ProofEvent::CompositeProofStored { proof_key, issuer, components } => (
+			"CompositeProofStored",
+			Some(proof_key),
+			Some(issuer.to_string()),
+			json!({ "components": components }),
+		),
+		ProofEvent::ProofConsumed { proof_key, verifier, remaining_uses } => (
+			"ProofConsumed",
+			Some(proof_key),
+			Some(verifier.to_string()),
+			json!({ "remainingUses": remaining_uses }),
+		),
+		ProofEvent::ProofExhausted { proof_key, verifier } =>
+			("ProofExhausted", Some(proof_key), Some(verifier.to_string()), json!({})),
+		ProofEvent::CrossingRecorded { proof_key, direction, checkpoint } => (
+			"CrossingRecorded",
+			Some(proof_key),
+			None,
+			json!({
+				"entry": direction == CrossingDirection::Entry,
+				"checkpoint": String::from_utf8_lossy(&checkpoint),
+			}),
+		),
+		ProofEvent::RevocationStatusUpdated { issuer, revoked, reinstated, indexes } => (
+			"RevocationStatusUpdated",
+			None,
+			Some(issuer.to_string()),
+			json!({ "revoked": revoked, "reinstated": reinstated, "indexes": indexes.to_vec() }),
+		),
+		ProofEvent::KeyCompromiseDeclared { key, since, until } => (
+			"KeyCompromiseDeclared",
+			None,
+			Some(key.to_string()),
+			json!({ "since": saturated(since), "until": saturated(until) }),
+		),
+		ProofEvent::ProofsPruned { proof_keys } => (
+			"ProofsPruned",
+			None,
+			None,
+			json!({ "count": proof_keys.len(), "proofKeys": proof_keys.to_vec() }),
+		),
+		ProofEvent::BatchLeavesRevoked { root, revoked } =>
+			("BatchLeavesRevoked", None, None, json!({ "root": root, "revoked": revoked })),
+		ProofEvent::WhitelistEntityRegistered { entity, .. } =>
+			("WhitelistEntityRegistered", None, Some(entity.to_string()), json!({})),
+		ProofEvent::VerifierAuthorized { verifier } =>
+			("VerifierAuthorized", None, Some(verifier.to_string()), json!({})),
+		_ => return None,
+	};
+	Some(described)
+}
+
+/// `value` as an SQLite integer, saturating at its maximum.
+fn saturated(value: U256) -> i64 {
+	if value > U256::from(i64::MAX) {
+		i64::MAX
+	} else {
+		value.as_u64() as i64
+	}
+}
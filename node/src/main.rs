@@ -5,8 +5,13 @@ mod benchmarking;
 mod chain_spec;
 mod cli;
 mod command;
+mod gateway;
+mod graphql;
+mod indexer;
+mod metrics;
 mod rpc;
 mod service;
+mod webhooks;
 
 fn main() -> sc_cli::Result<()> {
 	command::run()
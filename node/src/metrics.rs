@@ -0,0 +1,270 @@
+//! Prometheus metrics of the proof registry, exported on the endpoint of the node, so that
+//! operators can alert on issuance anomalies.
+//!
+//! The proofs stored, revoked, reinstated and expired are counted from the pallet events of
+//! finalized blocks, and the entities from the `entity_counts` runtime API at the last finalized
+//! block. Expiry is passive in the pallet, so expired proofs are counted when they are pruned. The
+//! proofs a key compromise revokes are not known on chain, so the declarations are counted
+//! instead. The gateway, the GraphQL API and the status subscriptions time their calls to the
+//! status runtime APIs.
+
+use crate::{
+	indexer::{self, Indexer},
+	service::FullClient,
+};
+use futures::StreamExt;
+use pallet_issuance_proof::{Event as ProofEvent, ProofStatus};
+use pallet_issuance_proof_runtime_api::IssuanceProofApi;
+use sc_client_api::BlockchainEvents;
+use solochain_template_runtime::{Hash, RuntimeEvent};
+use sp_api::ProvideRuntimeApi;
+use sp_runtime::traits::Header;
+use std::{sync::Arc, time::Instant};
+use substrate_prometheus_endpoint::{
+	exponential_buckets, register, CounterVec, Gauge, GaugeVec, HistogramOpts, HistogramVec, Opts,
+	PrometheusError, Registry, U64,
+};
+
+const LOG_TARGET: &str = "proof-metrics";
+
+/// Where a status call comes from.
+#[derive(Clone, Copy, Debug)]
+pub enum Source {
+	Rest,
+	Graphql,
+	Subscription,
+}
+
+impl Source {
+	fn as_str(self) -> &'static str {
+		match self {
+			Self::Rest => "rest",
+			Self::Graphql => "graphql",
+			Self::Subscription => "subscription",
+		}
+	}
+}
+
+/// Metrics of the proof registry, cheap to clone.
+#[derive(Clone)]
+pub struct Metrics {
+	/// Proof events of finalized blocks, by kind.
+	events: CounterVec<U64>,
+	/// Proofs stored, revoked, reinstated and expired, and key compromises, in each finalized
+	/// block.
+	per_block: HistogramVec,
+	/// Entities at the last finalized block, by kind.
+	entities: GaugeVec<U64>,
+	/// Status calls by source and result.
+	status_calls: CounterVec<U64>,
+	/// Duration of the status calls by source.
+	status_duration: HistogramVec,
+	/// Blocks the proof indexer is behind the best block.
+	indexer_lag: Gauge<U64>,
+}
+
+impl Metrics {
+	/// Create the metrics and register them in `registry`.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			events: register(
+				CounterVec::new(
+					Opts::new("probo_proof_events_total", "Proof events of finalized blocks"),
+					&["event"],
+				)?,
+				registry,
+			)?,
+			per_block: register(
+				HistogramVec::new(
+					HistogramOpts::new(
+						"probo_proofs_per_block",
+						"Proofs stored, revoked, reinstated and expired, and key compromises, in each finalized block",
+					)
+					.buckets(vec![0.0, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0]),
+					&["event"],
+				)?,
+				registry,
+			)?,
+			entities: register(
+				GaugeVec::new(
+					Opts::new("probo_entities", "Entities at the last finalized block"),
+					&["kind"],
+				)?,
+				registry,
+			)?,
+			status_calls: register(
+				CounterVec::new(
+					Opts::new("probo_status_calls_total", "Calls to the proof status runtime APIs"),
+					&["source", "result"],
+				)?,
+				registry,
+			)?,
+			status_duration: register(
+				HistogramVec::new(
+					HistogramOpts::new(
+						"probo_status_call_duration_seconds",
+						"Duration of the calls to the proof status runtime APIs",
+					)
+					.buckets(exponential_buckets(0.0005, 2.0, 12)?),
+					&["source"],
+				)?,
+				registry,
+			)?,
+			indexer_lag: register(
+				Gauge::new(
+					"probo_indexer_lag_blocks",
+					"Blocks the proof indexer is behind the best block",
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	/// Run the status call `call` from `source`, recording it in `metrics` if there are some.
+	pub fn status_call<E>(
+		metrics: Option<&Self>,
+		source: Source,
+		call: impl FnOnce() -> Result<ProofStatus, E>,
+	) -> Result<ProofStatus, E> {
+		let Some(metrics) = metrics else { return call() };
+		let started = Instant::now();
+		let status = call();
+		metrics
+			.status_duration
+			.with_label_values(&[source.as_str()])
+			.observe(started.elapsed().as_secs_f64());
+		let result = match &status {
+			Ok(ProofStatus::Valid) => "valid",
+			Ok(_) => "invalid",
+			Err(_) => "error",
+		};
+		metrics.status_calls.with_label_values(&[source.as_str(), result]).inc();
+		status
+	}
+
+	/// Follow the finalized blocks of `client`, and its best blocks to measure how far `indexer`
+	/// is behind, until the notification streams end.
+	pub async fn run(self, client: Arc<FullClient>, indexer: Option<Indexer>) {
+		let mut finality = client.finality_notification_stream().fuse();
+		let mut imports = client
+			.import_notification_stream()
+			.filter(|notification| futures::future::ready(notification.is_new_best))
+			.fuse();
+		loop {
+			futures::select! {
+				notification = finality.next() => {
+					let Some(notification) = notification else { return };
+					let finalized = notification.hash;
+					let hashes: Vec<Hash> =
+						notification.tree_route.iter().copied().chain([finalized]).collect();
+					let (metrics, client) = (self.clone(), client.clone());
+					blocking(move || {
+						for hash in hashes {
+							metrics.observe_block(&client, hash);
+						}
+						metrics.observe_entities(&client, finalized);
+					})
+					.await;
+				},
+				notification = imports.next() => {
+					let Some(notification) = notification else { return };
+					if let Some(indexer) = &indexer {
+						let (metrics, indexer) = (self.clone(), indexer.clone());
+						let best = *notification.header.number();
+						blocking(move || metrics.observe_lag(&indexer, best)).await;
+					}
+				},
+			}
+		}
+	}
+
+	fn observe_block(&self, client: &FullClient, hash: Hash) {
+		let events: Vec<frame_system::EventRecord<RuntimeEvent, Hash>> =
+			match indexer::read_storage(client, hash, b"System", b"Events") {
+				Ok(events) => events.unwrap_or_default(),
+				Err(error) => {
+					log::warn!(target: LOG_TARGET, "Failed to read the events of {hash}: {error}");
+					return;
+				},
+			};
+
+		let (mut stored, mut revoked, mut reinstated, mut compromised, mut expired) =
+			(0u64, 0u64, 0u64, 0u64, 0u64);
+		for record in events {
+			let RuntimeEvent::Proof(event) = record.event else { continue };
+			let (kind, count) = match event {
+				ProofEvent::ProofStored { .. } | ProofEvent::CompositeProofStored { .. } => {
+					stored += 1;
+					("stored", 1)
+				},
+				ProofEvent::BatchLeavesRevoked { revoked: count, .. } => {
+					revoked += u64::from(count);
+					("batch_leaves_revoked", count.into())
+				},
+				ProofEvent::RevocationStatusUpdated {
+					revoked: set, reinstated: cleared, ..
+				} => {
+					revoked += u64::from(set);
+					reinstated += u64::from(cleared);
+					self.events
+						.with_label_values(&["status_list_reinstated"])
+						.inc_by(cleared.into());
+					("status_list_revoked", set.into())
+				},
+				ProofEvent::KeyCompromiseDeclared { .. } => {
+					compromised += 1;
+					("key_compromised", 1)
+				},
+				ProofEvent::ProofsPruned { proof_keys } => {
+					let count = proof_keys.len() as u64;
+					expired += count;
+					("expired", count)
+				},
+				ProofEvent::ProofConsumed { .. } => ("consumed", 1),
+				ProofEvent::ProofExhausted { .. } => ("exhausted", 1),
+				ProofEvent::WhitelistEntityRegistered { .. } => ("entity_registered", 1),
+				ProofEvent::VerifierAuthorized { .. } => ("verifier_authorized", 1),
+				_ => continue,
+			};
+			self.events.with_label_values(&[kind]).inc_by(count);
+		}
+		for (kind, count) in [
+			("stored", stored),
+			("revoked", revoked),
+			("reinstated", reinstated),
+			("key_compromised", compromised),
+			("expired", expired),
+		] {
+			self.per_block.with_label_values(&[kind]).observe(count as f64);
+		}
+	}
+
+	fn observe_entities(&self, client: &FullClient, hash: Hash) {
+		match client.runtime_api().entity_counts(hash) {
+			Ok(counts) =>
+				for (kind, count) in [
+					("active_issuer", counts.active_issuers),
+					("suspended_issuer", counts.suspended_issuers),
+					("verifier", counts.verifiers),
+				] {
+					self.entities.with_label_values(&[kind]).set(count.into());
+				},
+			// runtimes before version 3 of the API don't count entities
+			Err(error) => log::debug!(target: LOG_TARGET, "Failed to count entities: {error}"),
+		}
+	}
+
+	fn observe_lag(&self, indexer: &Indexer, best: u32) {
+		match indexer.indexed_number() {
+			Ok(indexed) => self.indexer_lag.set(best.saturating_sub(indexed.unwrap_or(0)).into()),
+			Err(error) => log::warn!(target: LOG_TARGET, "Failed to read the indexer tip: {error}"),
+		}
+	}
+}
+
+/// Run `call`, which reads storage or the indexer database, on the blocking thread pool.
+async fn blocking(call: impl FnOnce() + Send + 'static) {
+	if let Err(error) = tokio::task::spawn_blocking(call).await {
+		std::panic::resume_unwind(error.into_panic());
+	}
+}
@@ -5,44 +5,76 @@
 
 #![warn(missing_docs)]
 
+pub mod indexer;
+pub mod probo;
+pub mod proof_index;
+
 use std::sync::Arc;
 
+use crate::service::FullBackend;
 use jsonrpsee::RpcModule;
+use pallet_issuance_proof::ProofBytes;
+use pallet_issuance_proof_runtime_api::IssuanceProofApi;
+use sc_client_api::{BlockchainEvents, StorageProvider};
 use sc_transaction_pool_api::TransactionPool;
-use solochain_template_runtime::{opaque::Block, AccountId, Balance, Nonce};
+use solochain_template_runtime::{
+	opaque::Block, AccountId, Balance, Hash, Nonce, Runtime, Signature,
+};
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
+use sp_core::offchain::OffchainStorage;
 
 /// Full client dependencies.
-pub struct FullDeps<C, P> {
+pub struct FullDeps<C, P, S> {
 	/// The client instance to use.
 	pub client: Arc<C>,
 	/// Transaction pool instance.
 	pub pool: Arc<P>,
+	/// Offchain database holding the indexed proof records.
+	pub offchain_db: Option<S>,
+	/// Proof indexer, if the node runs one.
+	pub indexer: Option<crate::indexer::Indexer>,
+	/// Metrics of the proof registry, if the node exports some.
+	pub metrics: Option<crate::metrics::Metrics>,
 }
 
 /// Instantiate all full RPC extensions.
-pub fn create_full<C, P>(
-	deps: FullDeps<C, P>,
+pub fn create_full<C, P, S>(
+	deps: FullDeps<C, P, S>,
 ) -> Result<RpcModule<()>, Box<dyn std::error::Error + Send + Sync>>
 where
 	C: ProvideRuntimeApi<Block>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error = BlockChainError> + 'static,
+	C: BlockchainEvents<Block> + StorageProvider<Block, FullBackend>,
 	C: Send + Sync + 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: BlockBuilder<Block>,
+	C::Api: IssuanceProofApi<Block, AccountId, ProofBytes<Runtime>, Hash, Signature>,
 	P: TransactionPool + 'static,
+	S: OffchainStorage + 'static,
 {
+	use indexer::{IndexerApiServer, IndexerRpc};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
+	use probo::{Probo, ProboApiServer};
+	use proof_index::{ProofIndex, ProofIndexApiServer};
 	use substrate_frame_rpc_system::{System, SystemApiServer};
 
 	let mut module = RpcModule::new(());
-	let FullDeps { client, pool } = deps;
+	let FullDeps { client, pool, offchain_db, indexer, metrics } = deps;
 
 	module.merge(System::new(client.clone(), pool).into_rpc())?;
-	module.merge(TransactionPayment::new(client).into_rpc())?;
+	module.merge(TransactionPayment::new(client.clone()).into_rpc())?;
+	module.merge(Probo::<_, FullBackend>::new(client, metrics).into_rpc())?;
+	if let Some(offchain_db) = offchain_db {
+		module.merge(
+			ProofIndexApiServer::<AccountId, Hash>::into_rpc(ProofIndex::new(offchain_db)),
+		)?;
+	}
+	if let Some(indexer) = indexer {
+		module.merge(IndexerRpc::new(indexer).into_rpc())?;
+	}
 
 	// Extend this RPC with a custom API by using the following syntax.
 	// `YourRpcStruct` should have a reference to a client, which is needed
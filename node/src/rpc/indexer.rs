@@ -0,0 +1,75 @@
+//! RPC methods querying the SQLite database of the proof indexer, available on nodes run with
+//! `--proof-indexer <PATH>`.
+
+use crate::indexer::{
+	EventFilter, IndexedEvent, IndexedProof, Indexer, Page, ProofFilter, MAX_PAGE_SIZE,
+};
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{ErrorObject, ErrorObjectOwned},
+};
+
+/// Queries of the proof indexer.
+#[rpc(server)]
+pub trait IndexerApi {
+	/// Proofs matching `filter`, in the order they were stored. At most `limit` of them, and at
+	/// most `MAX_PAGE_SIZE`, are returned after skipping the first `offset`.
+	#[method(name = "indexer_proofs", blocking)]
+	fn proofs(
+		&self,
+		filter: Option<ProofFilter>,
+		offset: Option<u32>,
+		limit: Option<u32>,
+	) -> RpcResult<Page<IndexedProof>>;
+
+	/// Events matching `filter`, in the order they were emitted. Paginated like
+	/// `indexer_proofs`.
+	#[method(name = "indexer_events", blocking)]
+	fn events(
+		&self,
+		filter: Option<EventFilter>,
+		offset: Option<u32>,
+		limit: Option<u32>,
+	) -> RpcResult<Page<IndexedEvent>>;
+}
+
+/// Serves [`IndexerApiServer`] from the database of an [`Indexer`].
+pub struct IndexerRpc {
+	indexer: Indexer,
+}
+
+impl IndexerRpc {
+	/// Create a new instance querying `indexer`.
+	pub fn new(indexer: Indexer) -> Self {
+		Self { indexer }
+	}
+}
+
+fn query_failed(error: crate::indexer::Error) -> ErrorObjectOwned {
+	ErrorObject::owned(1, "Query of the proof index failed", Some(error.to_string()))
+}
+
+impl IndexerApiServer for IndexerRpc {
+	fn proofs(
+		&self,
+		filter: Option<ProofFilter>,
+		offset: Option<u32>,
+		limit: Option<u32>,
+	) -> RpcResult<Page<IndexedProof>> {
+		self.indexer
+			.proofs(&filter.unwrap_or_default(), offset.unwrap_or(0), limit.unwrap_or(MAX_PAGE_SIZE))
+			.map_err(query_failed)
+	}
+
+	fn events(
+		&self,
+		filter: Option<EventFilter>,
+		offset: Option<u32>,
+		limit: Option<u32>,
+	) -> RpcResult<Page<IndexedEvent>> {
+		self.indexer
+			.events(&filter.unwrap_or_default(), offset.unwrap_or(0), limit.unwrap_or(MAX_PAGE_SIZE))
+			.map_err(query_failed)
+	}
+}
@@ -0,0 +1,561 @@
+//! Subscriptions pushing what happens to proofs in finalized blocks, so that verifiers learn of a
+//! revocation without polling the chain. A watched proof is only queried again in blocks whose
+//! events touch it, its components or their issuers, and in the block it expires in. A block that
+//! can't be read is skipped, and its proofs are queried again in the next one.
+
+use crate::metrics::{Metrics, Source};
+use codec::Decode;
+use futures::{
+	future::{self, Either},
+	StreamExt,
+};
+use jsonrpsee::{
+	core::{async_trait, SubscriptionResult},
+	proc_macros::rpc,
+	types::error::ErrorObject,
+	ConnectionId, PendingSubscriptionSink, SubscriptionMessage, SubscriptionSink,
+};
+use pallet_issuance_proof::{BatchMeta, InvalidReason, ProofBytes, ProofMeta, ProofStatus};
+use pallet_issuance_proof_runtime_api::IssuanceProofApi;
+use sc_client_api::{Backend, BlockchainEvents, StorageProvider};
+use serde::{Deserialize, Serialize};
+use solochain_template_runtime::{
+	opaque::Block, AccountId, BlockNumber, Hash, Runtime, RuntimeEvent, Signature,
+};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{storage::StorageKey, H256, U256};
+use std::{
+	collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap},
+	marker::PhantomData,
+	sync::{Arc, Mutex},
+};
+
+const LOG_TARGET: &str = "probo-rpc";
+
+/// Most proofs one `probo_subscribeProofStatus` subscription can watch.
+pub const MAX_WATCHED_PROOFS: usize = 1000;
+
+/// Most subscriptions of this API one connection can keep open.
+pub const MAX_SUBSCRIPTIONS_PER_CONNECTION: usize = 16;
+
+/// Why a proof is not valid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Reason {
+	NotFound,
+	Expired,
+	Exhausted,
+	Revoked,
+	KeyCompromised,
+}
+
+impl From<InvalidReason> for Reason {
+	fn from(reason: InvalidReason) -> Self {
+		match reason {
+			InvalidReason::NotFound => Self::NotFound,
+			InvalidReason::Expired => Self::Expired,
+			InvalidReason::Exhausted => Self::Exhausted,
+			InvalidReason::Revoked => Self::Revoked,
+			InvalidReason::KeyCompromised => Self::KeyCompromised,
+		}
+	}
+}
+
+/// Status of a watched proof at a finalized block.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofStatusUpdate {
+	pub proof_key: Hash,
+	pub block_hash: Hash,
+	pub block_number: BlockNumber,
+	pub valid: bool,
+	/// Why the proof, or its component at `component_index`, is not valid.
+	pub reason: Option<Reason>,
+	pub component_index: Option<u32>,
+	/// The issuer of the proof was suspended in the block. Its proofs stored before the key was
+	/// compromised stay valid.
+	pub issuer_suspended: bool,
+}
+
+/// Kind of an alert.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AlertKind {
+	KeyCompromised,
+	StatusListUpdated,
+	BatchLeavesRevoked,
+}
+
+/// Something that makes proofs of an issuer untrustworthy.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum Alert {
+	/// The key of an issuer was declared compromised. Its proofs stored from block `since` to
+	/// block `until` are revoked, and the issuer is suspended.
+	#[serde(rename_all = "camelCase")]
+	KeyCompromised { issuer: AccountId, since: u64, until: u64 },
+	/// An issuer revoked, or reinstated, entries of its status list.
+	#[serde(rename_all = "camelCase")]
+	StatusListUpdated { issuer: AccountId, revoked: u32, reinstated: u32 },
+	/// Documents of an anchored batch were revoked.
+	#[serde(rename_all = "camelCase")]
+	BatchLeavesRevoked { issuer: Option<AccountId>, root: H256, revoked: u32 },
+}
+
+impl Alert {
+	fn kind(&self) -> AlertKind {
+		match self {
+			Self::KeyCompromised { .. } => AlertKind::KeyCompromised,
+			Self::StatusListUpdated { .. } => AlertKind::StatusListUpdated,
+			Self::BatchLeavesRevoked { .. } => AlertKind::BatchLeavesRevoked,
+		}
+	}
+
+	fn issuer(&self) -> Option<&AccountId> {
+		match self {
+			Self::KeyCompromised { issuer, .. } | Self::StatusListUpdated { issuer, .. } =>
+				Some(issuer),
+			Self::BatchLeavesRevoked { issuer, .. } => issuer.as_ref(),
+		}
+	}
+}
+
+/// An alert of a finalized block.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertNotification {
+	pub block_hash: Hash,
+	pub block_number: BlockNumber,
+	#[serde(flatten)]
+	pub alert: Alert,
+}
+
+/// Which alerts to push.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct AlertFilter {
+	/// Only alerts about these issuers, all of them when empty.
+	#[serde(default)]
+	pub issuers: Vec<AccountId>,
+	/// Only alerts of these kinds, all of them when empty.
+	#[serde(default)]
+	pub kinds: Vec<AlertKind>,
+}
+
+impl AlertFilter {
+	fn matches(&self, alert: &Alert) -> bool {
+		(self.issuers.is_empty() || alert.issuer().is_some_and(|i| self.issuers.contains(i))) &&
+			(self.kinds.is_empty() || self.kinds.contains(&alert.kind()))
+	}
+}
+
+/// Subscriptions to proofs and issuers.
+#[rpc(server)]
+pub trait ProboApi {
+	/// Watch the status of proofs in finalized blocks. The status of each proof at the last
+	/// finalized block is pushed first, then again whenever it changes or the issuer of the proof
+	/// is suspended.
+	#[subscription(
+		name = "probo_subscribeProofStatus" => "probo_proofStatus",
+		unsubscribe = "probo_unsubscribeProofStatus",
+		item = ProofStatusUpdate,
+	)]
+	async fn subscribe_proof_status(&self, proof_keys: Vec<Hash>) -> SubscriptionResult;
+
+	/// Push the alerts of finalized blocks that match `filter`.
+	#[subscription(
+		name = "probo_subscribeAlerts" => "probo_alert",
+		unsubscribe = "probo_unsubscribeAlerts",
+		item = AlertNotification,
+	)]
+	async fn subscribe_alerts(&self, filter: Option<AlertFilter>) -> SubscriptionResult;
+}
+
+/// Number of open subscriptions of each connection.
+#[derive(Clone, Default)]
+struct Connections(Arc<Mutex<HashMap<ConnectionId, usize>>>);
+
+impl Connections {
+	/// Count a subscription of `connection`, `None` if it has too many already.
+	fn open(&self, connection: ConnectionId) -> Option<Opened> {
+		let mut open = self.lock();
+		let count = open.entry(connection).or_default();
+		if *count >= MAX_SUBSCRIPTIONS_PER_CONNECTION {
+			return None;
+		}
+		*count += 1;
+		Some(Opened { connections: self.clone(), connection })
+	}
+
+	fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<ConnectionId, usize>> {
+		self.0.lock().expect("the connections lock is never held across a panic; qed")
+	}
+}
+
+/// A subscription counted in [`Connections`] until it is dropped.
+struct Opened {
+	connections: Connections,
+	connection: ConnectionId,
+}
+
+impl Drop for Opened {
+	fn drop(&mut self) {
+		if let Entry::Occupied(mut count) = self.connections.lock().entry(self.connection) {
+			*count.get_mut() -= 1;
+			if *count.get() == 0 {
+				count.remove();
+			}
+		}
+	}
+}
+
+/// Serves [`ProboApiServer`] from the finality notifications of the client.
+pub struct Probo<C, B> {
+	client: Arc<C>,
+	metrics: Option<Metrics>,
+	connections: Connections,
+	_backend: PhantomData<B>,
+}
+
+impl<C, B> Probo<C, B>
+where
+	B: Backend<Block>,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + StorageProvider<Block, B>,
+	C::Api: IssuanceProofApi<Block, AccountId, ProofBytes<Runtime>, Hash, Signature>,
+{
+	/// Create a new instance reading `client`, timing its status calls in `metrics`.
+	pub fn new(client: Arc<C>, metrics: Option<Metrics>) -> Self {
+		Self { client, metrics, connections: Default::default(), _backend: PhantomData }
+	}
+
+	fn read<T: Decode>(&self, at: Hash, key: Vec<u8>) -> Result<Option<T>, String> {
+		let data = self.client.storage(at, &StorageKey(key)).map_err(|e| e.to_string())?;
+		Ok(data.and_then(|data| T::decode(&mut &data.0[..]).ok()))
+	}
+
+	fn events(&self, at: Hash) -> Result<Vec<pallet_issuance_proof::Event<Runtime>>, String> {
+		let records: Vec<frame_system::EventRecord<RuntimeEvent, Hash>> = self
+			.read(at, frame_system::Events::<Runtime>::hashed_key().to_vec())?
+			.unwrap_or_default();
+		Ok(records
+			.into_iter()
+			.filter_map(|record| match record.event {
+				RuntimeEvent::Proof(event) => Some(event),
+				_ => None,
+			})
+			.collect())
+	}
+
+	fn status(&self, at: Hash, proof_key: Hash) -> Result<ProofStatus, String> {
+		Metrics::status_call(self.metrics.as_ref(), Source::Subscription, || {
+			self.client.runtime_api().proof_status_by_key(at, proof_key)
+		})
+		.map_err(|e| e.to_string())
+	}
+
+	/// Status of `proof_key` at `at`, with what it depends on.
+	fn watch(&self, at: Hash, proof_key: Hash) -> Result<Watched, String> {
+		let components: Vec<Hash> = self
+			.read(
+				at,
+				pallet_issuance_proof::CompositeComponents::<Runtime>::hashed_key_for(proof_key),
+			)?
+			.unwrap_or_default();
+		let mut watched = Watched {
+			status: self.status(at, proof_key)?,
+			issuer: None,
+			keys: BTreeSet::new(),
+			issuers: BTreeSet::new(),
+			expiry_blocks: BTreeSet::new(),
+		};
+		for key in [proof_key].into_iter().chain(components) {
+			let meta: Option<ProofMeta<Runtime>> =
+				self.read(at, pallet_issuance_proof::Proofs::<Runtime>::hashed_key_for(key))?;
+			if let Some(meta) = meta {
+				watched.issuers.insert(meta.issuer.clone());
+				watched.expiry_blocks.insert(meta.expiry_block);
+				if key == proof_key {
+					watched.issuer = Some(meta.issuer);
+				}
+			}
+			watched.keys.insert(key);
+		}
+		Ok(watched)
+	}
+
+	fn alerts(&self, at: Hash) -> Result<Vec<Alert>, String> {
+		use pallet_issuance_proof::Event::*;
+		let to_u64 = |block: U256| -> u64 { block.try_into().unwrap_or(u64::MAX) };
+		self.events(at)?
+			.into_iter()
+			.filter_map(|event| match event {
+				KeyCompromiseDeclared { key, since, until } => Some(Ok(Alert::KeyCompromised {
+					issuer: key,
+					since: to_u64(since),
+					until: to_u64(until),
+				})),
+				RevocationStatusUpdated { issuer, revoked, reinstated, .. } =>
+					Some(Ok(Alert::StatusListUpdated { issuer, revoked, reinstated })),
+				BatchLeavesRevoked { root, revoked } => Some(
+					self.read::<BatchMeta<Runtime>>(
+						at,
+						pallet_issuance_proof::Batches::<Runtime>::hashed_key_for(root),
+					)
+					.map(|batch| Alert::BatchLeavesRevoked {
+						issuer: batch.map(|batch| batch.issuer),
+						root,
+						revoked,
+					}),
+				),
+				_ => None,
+			})
+			.collect()
+	}
+
+	/// Number of the finalized block `hash`.
+	fn number(&self, hash: Hash) -> Result<BlockNumber, String> {
+		self.client
+			.number(hash)
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| format!("unknown block {hash}"))
+	}
+}
+
+/// A watched proof, with what its status depends on.
+struct Watched {
+	status: ProofStatus,
+	issuer: Option<AccountId>,
+	/// The proof and its components.
+	keys: BTreeSet<Hash>,
+	/// Issuers of the proof and of its components.
+	issuers: BTreeSet<AccountId>,
+	/// Last blocks in which the proof and its components are valid.
+	expiry_blocks: BTreeSet<U256>,
+}
+
+/// What the events of a block touch.
+#[derive(Default)]
+struct Touched {
+	keys: BTreeSet<Hash>,
+	/// Issuers that updated their status list or declared their key compromised.
+	issuers: BTreeSet<AccountId>,
+	/// Issuers that declared their key compromised.
+	suspended: BTreeSet<AccountId>,
+	pruned: bool,
+	rekeyed: bool,
+}
+
+impl Touched {
+	fn new(events: Vec<pallet_issuance_proof::Event<Runtime>>) -> Self {
+		use pallet_issuance_proof::Event::*;
+		let mut touched = Self::default();
+		for event in events {
+			match event {
+				ProofStored { proof_key, .. } |
+				CompositeProofStored { proof_key, .. } |
+				ProofConsumed { proof_key, .. } |
+				ProofExhausted { proof_key, .. } => {
+					touched.keys.insert(proof_key);
+				},
+				RevocationStatusUpdated { issuer, .. } => {
+					touched.issuers.insert(issuer);
+				},
+				KeyCompromiseDeclared { key, .. } => {
+					touched.issuers.insert(key.clone());
+					touched.suspended.insert(key);
+				},
+				ProofsPruned { .. } => touched.pruned = true,
+				ProofsRekeyed => touched.rekeyed = true,
+				_ => {},
+			}
+		}
+		touched
+	}
+
+	/// Whether the status of `proof` may have changed in block `number`.
+	fn affects(&self, proof: &Watched, number: BlockNumber) -> bool {
+		self.rekeyed ||
+			// only expired proofs are pruned
+			(self.pruned && proof.status != ProofStatus::Valid) ||
+			proof.expiry_blocks.contains(&U256::from(number.saturating_sub(1))) ||
+			!proof.keys.is_disjoint(&self.keys) ||
+			!proof.issuers.is_disjoint(&self.issuers)
+	}
+}
+
+fn update(
+	proof_key: Hash,
+	block: (Hash, BlockNumber),
+	watched: &Watched,
+	issuer_suspended: bool,
+) -> ProofStatusUpdate {
+	let (reason, component_index) = match watched.status {
+		ProofStatus::Valid => (None, None),
+		ProofStatus::Invalid(reason) => (Some(reason.into()), None),
+		ProofStatus::InvalidComponent { index, reason } => (Some(reason.into()), Some(index)),
+	};
+	ProofStatusUpdate {
+		proof_key,
+		block_hash: block.0,
+		block_number: block.1,
+		valid: reason.is_none(),
+		reason,
+		component_index,
+		issuer_suspended,
+	}
+}
+
+/// Send `item` to `sink`, false once the subscriber is gone.
+async fn send(sink: &SubscriptionSink, item: &impl Serialize) -> Result<bool, String> {
+	let message = SubscriptionMessage::from_json(item).map_err(|e| e.to_string())?;
+	Ok(sink.send(message).await.is_ok())
+}
+
+async fn reject_too_many(pending: PendingSubscriptionSink) -> SubscriptionResult {
+	let message =
+		format!("At most {MAX_SUBSCRIPTIONS_PER_CONNECTION} subscriptions per connection are open");
+	pending.reject(ErrorObject::owned(1, message, None::<()>)).await;
+	Ok(())
+}
+
+#[async_trait]
+impl<C, B> ProboApiServer for Probo<C, B>
+where
+	B: Backend<Block> + Send + Sync + 'static,
+	C: ProvideRuntimeApi<Block>
+		+ HeaderBackend<Block>
+		+ BlockchainEvents<Block>
+		+ StorageProvider<Block, B>
+		+ Send
+		+ Sync
+		+ 'static,
+	C::Api: IssuanceProofApi<Block, AccountId, ProofBytes<Runtime>, Hash, Signature>,
+{
+	async fn subscribe_proof_status(
+		&self,
+		pending: PendingSubscriptionSink,
+		proof_keys: Vec<Hash>,
+	) -> SubscriptionResult {
+		if proof_keys.is_empty() || proof_keys.len() > MAX_WATCHED_PROOFS {
+			let message = format!("Between 1 and {MAX_WATCHED_PROOFS} proof keys can be watched");
+			pending.reject(ErrorObject::owned(1, message, None::<()>)).await;
+			return Ok(());
+		}
+		let Some(_opened) = self.connections.open(pending.connection_id()) else {
+			return reject_too_many(pending).await;
+		};
+		// subscribe first, so that no block is missed between the initial status and the updates
+		let mut finality = self.client.finality_notification_stream();
+
+		let info = self.client.info();
+		let finalized = (info.finalized_hash, info.finalized_number);
+		let mut watched = BTreeMap::new();
+		for proof_key in proof_keys {
+			match self.watch(finalized.0, proof_key) {
+				Ok(proof) => watched.insert(proof_key, proof),
+				Err(error) => {
+					let message = format!("Failed to read the status of {proof_key}: {error}");
+					pending.reject(ErrorObject::owned(1, message, None::<()>)).await;
+					return Ok(());
+				},
+			};
+		}
+		let sink = pending.accept().await?;
+		for (proof_key, proof) in &watched {
+			if !send(&sink, &update(*proof_key, finalized, proof, false)).await? {
+				return Ok(());
+			}
+		}
+
+		// proofs whose status couldn't be read, to be queried again in the next block
+		let mut stale = BTreeSet::new();
+		let closed = sink.closed();
+		futures::pin_mut!(closed);
+		loop {
+			let notification = match future::select(closed.as_mut(), finality.next()).await {
+				Either::Right((Some(notification), _)) => notification,
+				_ => return Ok(()),
+			};
+			for hash in notification.tree_route.iter().copied().chain([notification.hash]) {
+				let (number, touched) = match self
+					.number(hash)
+					.and_then(|number| Ok((number, Touched::new(self.events(hash)?))))
+				{
+					Ok(block) => block,
+					Err(error) => {
+						log::warn!(target: LOG_TARGET, "Skipping block {hash}: {error}");
+						stale.extend(watched.keys().copied());
+						continue;
+					},
+				};
+				let block = (hash, number);
+				for (proof_key, proof) in watched.iter_mut() {
+					if !stale.remove(proof_key) && !touched.affects(proof, number) {
+						continue;
+					}
+					let watch = match self.watch(hash, *proof_key) {
+						Ok(watch) => watch,
+						Err(error) => {
+							log::warn!(
+								target: LOG_TARGET,
+								"Failed to read the status of {proof_key} at {hash}: {error}"
+							);
+							stale.insert(*proof_key);
+							continue;
+						},
+					};
+					let changed = watch.status != proof.status;
+					*proof = watch;
+					let issuer_suspended = proof
+						.issuer
+						.as_ref()
+						.is_some_and(|issuer| touched.suspended.contains(issuer));
+					if (changed || issuer_suspended) &&
+						!send(&sink, &update(*proof_key, block, proof, issuer_suspended)).await?
+					{
+						return Ok(());
+					}
+				}
+			}
+		}
+	}
+
+	async fn subscribe_alerts(
+		&self,
+		pending: PendingSubscriptionSink,
+		filter: Option<AlertFilter>,
+	) -> SubscriptionResult {
+		let filter = filter.unwrap_or_default();
+		let Some(_opened) = self.connections.open(pending.connection_id()) else {
+			return reject_too_many(pending).await;
+		};
+		let mut finality = self.client.finality_notification_stream();
+		let sink = pending.accept().await?;
+
+		let closed = sink.closed();
+		futures::pin_mut!(closed);
+		loop {
+			let notification = match future::select(closed.as_mut(), finality.next()).await {
+				Either::Right((Some(notification), _)) => notification,
+				_ => return Ok(()),
+			};
+			for hash in notification.tree_route.iter().copied().chain([notification.hash]) {
+				let (block_number, alerts) =
+					match self.number(hash).and_then(|number| Ok((number, self.alerts(hash)?))) {
+						Ok(block) => block,
+						Err(error) => {
+							log::warn!(target: LOG_TARGET, "Skipping block {hash}: {error}");
+							continue;
+						},
+					};
+				for alert in alerts.into_iter().filter(|alert| filter.matches(alert)) {
+					let notification = AlertNotification { block_hash: hash, block_number, alert };
+					if !send(&sink, &notification).await? {
+						return Ok(());
+					}
+				}
+			}
+		}
+	}
+}
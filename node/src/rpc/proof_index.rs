@@ -0,0 +1,341 @@
+//! RPC methods answering lookups from the proof records that the issuance proof pallet writes to
+//! the offchain database of the node. The node has to be run with
+//! `--enable-offchain-indexing true` for the records to be written.
+//!
+//! The pallet writes the proof events of each block, [`fold_finalized`] adds those of finalized
+//! blocks to the lists of the issuers and proofs, and the RPC methods read the lists. The events of
+//! blocks that left the finalized chain are removed.
+
+use crate::service::FullClient;
+use codec::{Decode, Encode};
+use futures::StreamExt;
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{ErrorObject, ErrorObjectOwned},
+};
+use pallet_issuance_proof::{
+	indexing::{self, BlockRecord, HistoryRecord, ProofEvent},
+	CrossingDirection,
+};
+use sc_client_api::BlockchainEvents;
+use serde::Serialize;
+use solochain_template_runtime::{opaque::Header, AccountId, Hash};
+use sp_blockchain::HeaderBackend;
+use sp_core::{
+	offchain::{OffchainStorage, STORAGE_PREFIX},
+	U256,
+};
+use std::{collections::BTreeSet, sync::Arc};
+
+const LOG_TARGET: &str = "proof-index";
+
+/// Offchain database key of the last block folded into the lists.
+const FOLDED_KEY: &[u8] = b"probo::proof-index::folded";
+
+/// Most proofs returned by one `proofIndex_proofsByIssuer` call.
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// A page of the proofs of an issuer.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssuerProofs<ProofKey> {
+	/// Number of proofs indexed under the issuer.
+	pub total: u32,
+	/// Keys of the proofs on the page, in the order they were stored.
+	pub proof_keys: Vec<ProofKey>,
+}
+
+/// An entry of the history of a proof.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry<AccountId> {
+	/// Block of the event.
+	pub block: U256,
+	/// What happened to the proof.
+	#[serde(flatten)]
+	pub event: HistoryEvent<AccountId>,
+}
+
+/// Something that happened to a proof.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase", tag = "event")]
+pub enum HistoryEvent<AccountId> {
+	/// The proof was stored.
+	Stored {
+		/// Issuer of the proof.
+		issuer: AccountId,
+	},
+	/// A use of the proof was consumed.
+	Consumed {
+		/// Verifier that consumed it.
+		verifier: AccountId,
+	},
+	/// A border crossing was recorded with the proof.
+	CrossingRecorded {
+		/// Verifier that recorded it.
+		verifier: AccountId,
+		/// Whether the holder entered the country, or left it.
+		entry: bool,
+	},
+	/// The holder of the proof answered a challenge.
+	Presented {
+		/// Verifier that issued the challenge.
+		verifier: AccountId,
+	},
+	/// The proof was pruned from the registry after it expired.
+	Pruned,
+	/// The entry of the proof in the status list of its issuer was set.
+	Revoked,
+	/// The entry of the proof in the status list of its issuer was cleared.
+	Reinstated,
+	/// The issuer declared its key compromised since before the proof was stored.
+	KeyCompromised,
+}
+
+impl<AccountId> From<HistoryRecord<AccountId>> for HistoryEntry<AccountId> {
+	fn from(record: HistoryRecord<AccountId>) -> Self {
+		let event = match record.event {
+			ProofEvent::Stored { issuer } => HistoryEvent::Stored { issuer },
+			ProofEvent::Consumed { verifier } => HistoryEvent::Consumed { verifier },
+			ProofEvent::CrossingRecorded { verifier, direction } => HistoryEvent::CrossingRecorded {
+				verifier,
+				entry: direction == CrossingDirection::Entry,
+			},
+			ProofEvent::Presented { verifier } => HistoryEvent::Presented { verifier },
+			ProofEvent::Pruned => HistoryEvent::Pruned,
+			ProofEvent::Revoked => HistoryEvent::Revoked,
+			ProofEvent::Reinstated => HistoryEvent::Reinstated,
+			ProofEvent::KeyCompromised => HistoryEvent::KeyCompromised,
+		};
+		HistoryEntry { block: record.block, event }
+	}
+}
+
+/// Lookups of indexed proofs.
+#[rpc(server)]
+pub trait ProofIndexApi<AccountId, ProofKey> {
+	/// Up to `limit` proofs stored by `issuer`, from the `start`th one.
+	#[method(name = "proofIndex_proofsByIssuer")]
+	fn proofs_by_issuer(
+		&self,
+		issuer: AccountId,
+		start: Option<u32>,
+		limit: Option<u32>,
+	) -> RpcResult<IssuerProofs<ProofKey>>;
+
+	/// The history of a proof, oldest first. Empty if nothing was indexed for the proof.
+	#[method(name = "proofIndex_proofHistory")]
+	fn proof_history(&self, proof_key: ProofKey) -> RpcResult<Vec<HistoryEntry<AccountId>>>;
+}
+
+/// Serves [`ProofIndexApiServer`] from the offchain database.
+pub struct ProofIndex<S> {
+	storage: S,
+}
+
+impl<S: OffchainStorage> ProofIndex<S> {
+	/// Create a new instance reading `storage`.
+	pub fn new(storage: S) -> Self {
+		Self { storage }
+	}
+
+	fn get<V: Decode>(&self, key: &[u8]) -> RpcResult<Option<V>> {
+		self.storage
+			.get(STORAGE_PREFIX, key)
+			.map(|value| V::decode(&mut &value[..]).map_err(|_| corrupt_record()))
+			.transpose()
+	}
+}
+
+fn corrupt_record() -> ErrorObjectOwned {
+	ErrorObject::owned(1, "Corrupt record in the offchain database", None::<()>)
+}
+
+impl<S, AccountId, ProofKey> ProofIndexApiServer<AccountId, ProofKey> for ProofIndex<S>
+where
+	S: OffchainStorage + 'static,
+	AccountId: Encode + Decode + Serialize + Send + Sync + 'static,
+	ProofKey: Encode + Decode + Serialize + Send + Sync + 'static,
+{
+	fn proofs_by_issuer(
+		&self,
+		issuer: AccountId,
+		start: Option<u32>,
+		limit: Option<u32>,
+	) -> RpcResult<IssuerProofs<ProofKey>> {
+		let total = self.get(&indexing::issuer_proofs_len_key(&issuer))?.unwrap_or(0);
+		let start = start.unwrap_or(0);
+		let end = start.saturating_add(limit.unwrap_or(MAX_PAGE_SIZE).min(MAX_PAGE_SIZE)).min(total);
+		let proof_keys = (start..end)
+			.map(|n| {
+				self.get(&indexing::issuer_proof_key(&issuer, n))?.ok_or_else(corrupt_record)
+			})
+			.collect::<RpcResult<_>>()?;
+		Ok(IssuerProofs { total, proof_keys })
+	}
+
+	fn proof_history(&self, proof_key: ProofKey) -> RpcResult<Vec<HistoryEntry<AccountId>>> {
+		let length = self.get(&indexing::history_len_key(&proof_key))?.unwrap_or(0);
+		(0..length)
+			.map(|n| {
+				self.get::<HistoryRecord<AccountId>>(&indexing::history_key(&proof_key, n))?
+					.map(Into::into)
+					.ok_or_else(corrupt_record)
+			})
+			.collect()
+	}
+}
+
+/// Fold the proof events indexed in the finalized blocks of `client` into the lists of `storage`,
+/// until the finality notification stream ends. The last folded block is kept in `storage`, so a
+/// restarted node carries on from there. A block that can't be folded is tried again at the next
+/// finalized block.
+pub async fn fold_finalized<S: OffchainStorage>(client: Arc<FullClient>, mut storage: S) {
+	let mut finality = client.finality_notification_stream();
+	loop {
+		if let Err(error) = fold_up_to(&client, &mut storage, client.info().finalized_number) {
+			log::warn!(target: LOG_TARGET, "Failed to fold the proof events: {error}");
+		}
+		let Some(notification) = finality.next().await else { return };
+		for head in notification.stale_heads.iter() {
+			if let Err(error) = discard_fork(&client, &mut storage, *head) {
+				log::warn!(target: LOG_TARGET, "Failed to discard the fork of {head}: {error}");
+			}
+		}
+	}
+}
+
+fn fold_up_to<S: OffchainStorage>(
+	client: &FullClient,
+	storage: &mut S,
+	finalized: u32,
+) -> Result<(), String> {
+	let folded = read::<u32>(storage, FOLDED_KEY).map_err(|e| e.to_string())?.unwrap_or(0);
+	for number in folded + 1..=finalized {
+		let hash = client
+			.hash(number)
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| format!("no finalized block #{number}"))?;
+		fold_block(storage, &header(client, hash)?)
+			.map_err(|error| format!("block #{number}: {error}"))?;
+		storage.set(STORAGE_PREFIX, FOLDED_KEY, &number.encode());
+	}
+	Ok(())
+}
+
+/// Add the proof events of the block of `header` to the lists of their issuers and proofs, and
+/// remove them. A record that can't be decoded is dropped, as it never will be.
+fn fold_block<S: OffchainStorage>(storage: &mut S, header: &Header) -> Result<(), codec::Error> {
+	let block = U256::from(header.number);
+	let block_id = indexing::block_id(&header.parent_hash, &header.digest);
+	let length_key = indexing::block_events_len_key(block, &block_id);
+	let Some(length) = read::<u32>(storage, &length_key)? else { return Ok(()) };
+	for n in 0..length {
+		let event_key = indexing::block_event_key(block, &block_id, n);
+		match read::<BlockRecord<AccountId, Hash>>(storage, &event_key) {
+			Ok(Some(BlockRecord::Proofs(events))) =>
+				for (proof_key, event) in events {
+					fold_event(storage, block, proof_key, event)?;
+				},
+			Ok(Some(BlockRecord::KeyCompromised { issuer, since, until })) =>
+				fold_compromise(storage, block, &issuer, since, until)?,
+			Ok(None) => continue,
+			Err(error) => log::warn!(
+				target: LOG_TARGET,
+				"Dropping the proof events of #{block} at {n}: {error}"
+			),
+		}
+		storage.remove(STORAGE_PREFIX, &event_key);
+	}
+	storage.remove(STORAGE_PREFIX, &length_key);
+	Ok(())
+}
+
+fn fold_event<S: OffchainStorage>(
+	storage: &mut S,
+	block: U256,
+	proof_key: Hash,
+	event: ProofEvent<AccountId>,
+) -> Result<(), codec::Error> {
+	if let ProofEvent::Stored { issuer } = &event {
+		let length_key = indexing::issuer_proofs_len_key(issuer);
+		append(storage, &length_key, |n| indexing::issuer_proof_key(issuer, n), &proof_key)?;
+		// a proof stored again after it was pruned starts a new history
+		storage.remove(STORAGE_PREFIX, &indexing::history_len_key(&proof_key));
+	}
+	let record = HistoryRecord { block, event };
+	let length_key = indexing::history_len_key(&proof_key);
+	append(storage, &length_key, |n| indexing::history_key(&proof_key, n), &record)
+}
+
+/// Record the key compromise in the history of the proofs `issuer` stored from `since` to
+/// `until`, from the block of their first record.
+fn fold_compromise<S: OffchainStorage>(
+	storage: &mut S,
+	block: U256,
+	issuer: &AccountId,
+	since: U256,
+	until: U256,
+) -> Result<(), codec::Error> {
+	let length = read::<u32>(storage, &indexing::issuer_proofs_len_key(issuer))?.unwrap_or(0);
+	let mut seen = BTreeSet::new();
+	for n in 0..length {
+		let Some(proof_key) = read::<Hash>(storage, &indexing::issuer_proof_key(issuer, n))? else {
+			continue;
+		};
+		if !seen.insert(proof_key) {
+			continue;
+		}
+		let stored =
+			read::<HistoryRecord<AccountId>>(storage, &indexing::history_key(&proof_key, 0))?;
+		if stored.is_some_and(|record| since <= record.block && record.block <= until) {
+			fold_event(storage, block, proof_key, ProofEvent::KeyCompromised)?;
+		}
+	}
+	Ok(())
+}
+
+/// Remove the proof events of the blocks from `head` back to the finalized chain.
+fn discard_fork<S: OffchainStorage>(
+	client: &FullClient,
+	storage: &mut S,
+	mut head: Hash,
+) -> Result<(), String> {
+	loop {
+		let header = header(client, head)?;
+		if client.hash(header.number).map_err(|e| e.to_string())? == Some(head) {
+			return Ok(());
+		}
+		let block = U256::from(header.number);
+		let block_id = indexing::block_id(&header.parent_hash, &header.digest);
+		let length_key = indexing::block_events_len_key(block, &block_id);
+		let length = read::<u32>(storage, &length_key).ok().flatten().unwrap_or(0);
+		for n in 0..length {
+			storage.remove(STORAGE_PREFIX, &indexing::block_event_key(block, &block_id, n));
+		}
+		storage.remove(STORAGE_PREFIX, &length_key);
+		head = header.parent_hash;
+	}
+}
+
+fn header(client: &FullClient, hash: Hash) -> Result<Header, String> {
+	client.header(hash).map_err(|e| e.to_string())?.ok_or_else(|| format!("unknown block {hash}"))
+}
+
+/// Add `item` to the end of the list whose length is under `length_key`.
+fn append<S: OffchainStorage, V: Encode>(
+	storage: &mut S,
+	length_key: &[u8],
+	item_key: impl FnOnce(u32) -> Vec<u8>,
+	item: &V,
+) -> Result<(), codec::Error> {
+	let length = read::<u32>(storage, length_key)?.unwrap_or(0);
+	storage.set(STORAGE_PREFIX, &item_key(length), &item.encode());
+	storage.set(STORAGE_PREFIX, length_key, &(length + 1).encode());
+	Ok(())
+}
+
+fn read<V: Decode>(storage: &impl OffchainStorage, key: &[u8]) -> Result<Option<V>, codec::Error> {
+	storage.get(STORAGE_PREFIX, key).map(|value| V::decode(&mut &value[..])).transpose()
+}
@@ -1,5 +1,6 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
+use crate::cli::ProofServicesParams;
 use futures::FutureExt;
 use sc_client_api::{Backend, BlockBackend};
 use sc_consensus_aura::{ImportQueueParams, SlotProportion, StartAuraParams};
@@ -16,7 +17,8 @@ pub(crate) type FullClient = sc_service::TFullClient<
 	RuntimeApi,
 	sc_executor::WasmExecutor<sp_io::SubstrateHostFunctions>,
 >;
-type FullBackend = sc_service::TFullBackend<Block>;
+pub(crate) type FullPool = sc_transaction_pool::TransactionPoolHandle<Block, FullClient>;
+pub(crate) type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
 
 /// The minimum period of blocks on which justifications will be
@@ -28,7 +30,7 @@ pub type Service = sc_service::PartialComponents<
 	FullBackend,
 	FullSelectChain,
 	sc_consensus::DefaultImportQueue<Block>,
-	sc_transaction_pool::TransactionPoolHandle<Block, FullClient>,
+	FullPool,
 	(
 		sc_consensus_grandpa::GrandpaBlockImport<FullBackend, Block, FullClient, FullSelectChain>,
 		sc_consensus_grandpa::LinkHalf<Block, FullClient, FullSelectChain>,
@@ -131,6 +133,7 @@ pub fn new_full<
 	N: sc_network::NetworkBackend<Block, <Block as sp_runtime::traits::Block>::Hash>,
 >(
 	config: Configuration,
+	proof_services: ProofServicesParams,
 ) -> Result<TaskManager, ServiceError> {
 	let sc_service::PartialComponents {
 		client,
@@ -211,12 +214,85 @@ pub fn new_full<
 	let enable_grandpa = !config.disable_grandpa;
 	let prometheus_registry = config.prometheus_registry().cloned();
 
+	let proof_metrics =
+		prometheus_registry.as_ref().map(crate::metrics::Metrics::register).transpose().map_err(
+			|e| ServiceError::Other(format!("Failed to register the proof metrics: {e}")),
+		)?;
+
+	let indexer = proof_services
+		.proof_indexer
+		.map(|path| crate::indexer::Indexer::open(&path))
+		.transpose()
+		.map_err(|e| ServiceError::Other(format!("Failed to open the proof indexer: {e}")))?;
+	if let Some(indexer) = &indexer {
+		task_manager.spawn_handle().spawn_blocking(
+			"proof-indexer",
+			None,
+			indexer.clone().run(client.clone()),
+		);
+		if let Some(port) = proof_services.graphql_port {
+			task_manager.spawn_handle().spawn(
+				"proof-graphql",
+				None,
+				crate::graphql::serve(port, indexer.clone(), client.clone(), proof_metrics.clone()),
+			);
+		}
+	}
+
+	if let Some(path) = proof_services.webhooks {
+		let notifier = crate::webhooks::Notifier::open(&path)
+			.map_err(|e| ServiceError::Other(format!("Failed to start the webhooks: {e}")))?;
+		task_manager.spawn_handle().spawn("proof-webhooks", None, notifier.run(client.clone()));
+	}
+
+	if let Some(port) = proof_services.rest_port {
+		let gateway = crate::gateway::Gateway::new(
+			client.clone(),
+			transaction_pool.clone(),
+			keystore_container.keystore(),
+			&config.data_path.join("gateway-salt-secret"),
+			proof_services.rest_signer,
+			proof_metrics.clone(),
+		)
+		.map_err(|e| ServiceError::Other(format!("Failed to start the REST gateway: {e}")))?;
+		task_manager.spawn_handle().spawn(
+			"proof-gateway",
+			None,
+			crate::gateway::serve(port, gateway),
+		);
+	}
+
+	if let Some(metrics) = &proof_metrics {
+		task_manager.spawn_handle().spawn(
+			"proof-metrics",
+			None,
+			metrics.clone().run(client.clone(), indexer.clone()),
+		);
+	}
+
+	if let (true, Some(offchain_db)) =
+		(config.offchain_worker.indexing_enabled, backend.offchain_storage())
+	{
+		task_manager.spawn_handle().spawn_blocking(
+			"proof-index",
+			None,
+			crate::rpc::proof_index::fold_finalized(client.clone(), offchain_db),
+		);
+	}
+
 	let rpc_extensions_builder = {
 		let client = client.clone();
 		let pool = transaction_pool.clone();
+		let offchain_db = backend.offchain_storage();
 
 		Box::new(move |_| {
-			let deps = crate::rpc::FullDeps { client: client.clone(), pool: pool.clone() };
+			let deps = crate::rpc::FullDeps {
+				client: client.clone(),
+				pool: pool.clone(),
+				offchain_db: offchain_db.clone(),
+				indexer: indexer.clone(),
+				metrics: proof_metrics.clone(),
+			};
 			crate::rpc::create_full(deps).map_err(Into::into)
 		})
 	};
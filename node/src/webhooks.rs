@@ -0,0 +1,463 @@
+//! Optional notifier posting the events of the issuance proof pallet in finalized blocks to HTTP
+//! endpoints, for systems that can receive callbacks but can't hold a subscription open.
+//!
+//! The targets are read from a JSON configuration file, e.g.
+//!
+//! ```json
+//! {
+//!   "queue": "webhooks.sqlite",
+//!   "maxAttempts": 10,
+//!   "targets": [{
+//!     "name": "case-management",
+//!     "url": "http://127.0.0.1:9000/probo",
+//!     "secret": "...",
+//!     "kinds": ["ProofStored", "RevocationStatusUpdated"],
+//!     "accounts": ["5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"]
+//!   }]
+//! }
+//! ```
+//!
+//! The events matching the filters of a target are queued in the SQLite database at `queue`,
+//! relative to the configuration file, in the same transaction that moves the cursor past their
+//! block, so none is lost or queued twice across restarts. Each is posted as JSON with the
+//! headers:
+//!
+//! - `X-Probo-Delivery`: `<block number>-<event index>`, to drop duplicates,
+//! - `X-Probo-Timestamp`: Unix time of the attempt, in seconds,
+//! - `X-Probo-Signature`: `sha256=` and the hex HMAC-SHA256 of `<timestamp>.<body>` keyed with the
+//!   secret of the target.
+//!
+//! Each target is delivered to on its own, in the order the events were queued. A delivery is
+//! retried with an exponential backoff until it gets a 2xx answer, and the target is not posted to
+//! until then. After `maxAttempts` failures it is logged and kept in the queue as a dead letter,
+//! with `dead = 1`, so are the deliveries of targets removed from the configuration.
+
+use crate::{
+	indexer::{self, Error},
+	service::FullClient,
+};
+use hmac::{Hmac, Mac};
+use rusqlite::{params, Connection, OptionalExtension};
+use sc_client_api::BlockchainEvents;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use solochain_template_runtime::{BlockNumber, Hash, RuntimeEvent};
+use sp_blockchain::HeaderBackend;
+use std::{
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex},
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::Notify;
+
+const LOG_TARGET: &str = "proof-webhooks";
+
+const SCHEMA: &str = "
+	CREATE TABLE IF NOT EXISTS cursor (
+		id INTEGER PRIMARY KEY CHECK (id = 0),
+		block_number INTEGER NOT NULL
+	);
+	CREATE TABLE IF NOT EXISTS deliveries (
+		id INTEGER PRIMARY KEY,
+		target TEXT NOT NULL,
+		delivery TEXT NOT NULL,
+		payload TEXT NOT NULL,
+		attempts INTEGER NOT NULL DEFAULT 0,
+		next_attempt INTEGER NOT NULL,
+		last_error TEXT,
+		dead INTEGER NOT NULL DEFAULT 0
+	);
+	DROP INDEX IF EXISTS deliveries_due;
+	CREATE INDEX IF NOT EXISTS deliveries_due_by_target ON deliveries (target, dead, next_attempt);
+";
+
+/// Deliveries of a target read from the queue at once.
+const BATCH_SIZE: u32 = 32;
+
+/// Delay before the first retry, doubled on every failure.
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Longest delay between two attempts.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(3600);
+
+/// How long a target has to answer.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn default_max_attempts() -> u32 {
+	10
+}
+
+/// Configuration file of the notifier.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Config {
+	/// Path of the queue database, relative to the configuration file.
+	pub queue: PathBuf,
+	/// Attempts of a delivery before it is given up.
+	#[serde(default = "default_max_attempts")]
+	pub max_attempts: u32,
+	pub targets: Vec<Target>,
+}
+
+/// An endpoint and the events it wants.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Target {
+	/// Name of the target in the queue and the logs.
+	pub name: String,
+	pub url: String,
+	/// Key of the HMAC signing the payloads.
+	pub secret: String,
+	/// Only events of these kinds, e.g. `ProofStored`, all of them when empty.
+	#[serde(default)]
+	pub kinds: Vec<String>,
+	/// Only events about these issuers or verifiers, all of them when empty.
+	#[serde(default)]
+	pub accounts: Vec<String>,
+	/// Only events about these proofs, all of them when empty.
+	#[serde(default)]
+	pub proof_keys: Vec<Hash>,
+}
+
+impl Target {
+	fn matches(&self, payload: &Payload) -> bool {
+		(self.kinds.is_empty() || self.kinds.iter().any(|kind| kind == payload.kind)) &&
+			(self.accounts.is_empty() ||
+				payload
+					.account
+					.as_ref()
+					.is_some_and(|account| self.accounts.contains(account))) &&
+			(self.proof_keys.is_empty() ||
+				payload.proof_key.is_some_and(|key| self.proof_keys.contains(&key)))
+	}
+}
+
+/// Body posted to the targets.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Payload {
+	block_number: BlockNumber,
+	block_hash: Hash,
+	event_index: u32,
+	kind: &'static str,
+	proof_key: Option<Hash>,
+	/// SS58 address of the issuer or verifier of the event.
+	account: Option<String>,
+	/// The other fields of the event.
+	data: serde_json::Value,
+}
+
+/// A queued delivery.
+struct Delivery {
+	id: i64,
+	target: String,
+	delivery: String,
+	payload: String,
+	attempts: u32,
+}
+
+/// Queues the events of finalized blocks and delivers them to the targets.
+pub struct Notifier {
+	db: Mutex<Connection>,
+	targets: Vec<Target>,
+	max_attempts: u32,
+	http: reqwest::Client,
+	queued: Notify,
+}
+
+impl Notifier {
+	/// Read the configuration file at `path` and open its queue, creating it if needed.
+	pub fn open(path: &Path) -> Result<Self, String> {
+		let config = std::fs::read(path).map_err(|e| format!("cannot read {path:?}: {e}"))?;
+		let config: Config =
+			serde_json::from_slice(&config).map_err(|e| format!("invalid {path:?}: {e}"))?;
+		for (i, target) in config.targets.iter().enumerate() {
+			if config.targets[..i].iter().any(|other| other.name == target.name) {
+				return Err(format!("target `{}` is configured twice", target.name));
+			}
+			reqwest::Url::parse(&target.url)
+				.map_err(|e| format!("invalid URL of target `{}`: {e}", target.name))?;
+		}
+
+		let queue = path.parent().unwrap_or(Path::new(".")).join(&config.queue);
+		let open = || -> Result<Connection, rusqlite::Error> {
+			let db = Connection::open(&queue)?;
+			db.pragma_update(None, "journal_mode", "WAL")?;
+			db.execute_batch(SCHEMA)?;
+			Ok(db)
+		};
+		let db = open().map_err(|e| format!("cannot open the queue {queue:?}: {e}"))?;
+		retire_removed_targets(&db, &config.targets)
+			.map_err(|e| format!("cannot update the queue {queue:?}: {e}"))?;
+		let http = reqwest::Client::builder()
+			.timeout(REQUEST_TIMEOUT)
+			.build()
+			.map_err(|e| e.to_string())?;
+
+		Ok(Self {
+			db: Mutex::new(db),
+			targets: config.targets,
+			max_attempts: config.max_attempts,
+			http,
+			queued: Notify::new(),
+		})
+	}
+
+	fn db(&self) -> std::sync::MutexGuard<'_, Connection> {
+		self.db.lock().expect("the database lock is never held across a panic; qed")
+	}
+
+	/// Queue the events of the blocks finalized by `client` and deliver them, until the finality
+	/// notifications end.
+	pub async fn run(self, client: Arc<FullClient>) {
+		let notifier = Arc::new(self);
+		let follow = Box::pin(notifier.clone().follow(client));
+		let targets = (0..notifier.targets.len()).map(|target| notifier.clone().deliver(target));
+		let deliver = async move {
+			futures::future::join_all(targets).await;
+			// without targets, the cursor still follows the finalized blocks
+			futures::future::pending::<()>().await
+		};
+		futures::future::select(follow, Box::pin(deliver)).await;
+	}
+
+	async fn follow(self: Arc<Self>, client: Arc<FullClient>) {
+		use futures::StreamExt;
+
+		let mut finality = client.finality_notification_stream();
+		loop {
+			let (notifier, client) = (self.clone(), client.clone());
+			match blocking(move || notifier.enqueue(&client)).await {
+				// a target busy delivering reads the queue again before it waits
+				Ok(true) => self.queued.notify_waiters(),
+				Ok(false) => {},
+				Err(error) => log::warn!(target: LOG_TARGET, "Failed to queue events: {error}"),
+			}
+			if finality.next().await.is_none() {
+				return;
+			}
+		}
+	}
+
+	/// Queue the events of the blocks finalized since the cursor. Returns whether any was queued.
+	fn enqueue(&self, client: &FullClient) -> Result<bool, Error> {
+		let finalized = client.info().finalized_number;
+		let cursor = self
+			.db()
+			.query_row("SELECT block_number FROM cursor", [], |row| row.get(0))
+			.optional()?;
+		// a new queue starts from the current finalized block, not from genesis
+		let Some(cursor) = cursor else {
+			self.db()
+				.execute("INSERT INTO cursor (id, block_number) VALUES (0, ?1)", [finalized])?;
+			return Ok(false);
+		};
+
+		let mut queued = false;
+		for number in cursor + 1..=finalized {
+			let hash = client.hash(number)?.ok_or(Error::UnknownBlock(number))?;
+			let events: Vec<frame_system::EventRecord<RuntimeEvent, Hash>> =
+				indexer::read_storage(client, hash, b"System", b"Events")?.unwrap_or_default();
+
+			let mut db = self.db();
+			let tx = db.transaction()?;
+			for (index, record) in events.into_iter().enumerate() {
+				let RuntimeEvent::Proof(event) = record.event else { continue };
+				let Some((kind, proof_key, account, data)) = indexer::describe(event) else {
+					continue
+				};
+				let payload = Payload {
+					block_number: number,
+					block_hash: hash,
+					event_index: index as u32,
+					kind,
+					proof_key,
+					account,
+					data,
+				};
+				let delivery = format!("{number}-{index}");
+				let body = serde_json::to_string(&payload).expect("payloads serialize; qed");
+				for target in self.targets.iter().filter(|target| target.matches(&payload)) {
+					tx.execute(
+						"INSERT INTO deliveries (target, delivery, payload, next_attempt)
+						VALUES (?1, ?2, ?3, 0)",
+						params![target.name, delivery, body],
+					)?;
+					queued = true;
+				}
+			}
+			tx.execute("UPDATE cursor SET block_number = ?1", [number])?;
+			tx.commit()?;
+		}
+		Ok(queued)
+	}
+
+	/// Deliver the queued events of the target at `target`, oldest first. A delivery that fails
+	/// holds up the ones after it until it is retried, but not those of the other targets.
+	async fn deliver(self: Arc<Self>, target: usize) {
+		loop {
+			let notifier = self.clone();
+			let due = match blocking(move || notifier.due(target)).await {
+				Ok(due) => due,
+				Err(error) => {
+					log::warn!(target: LOG_TARGET, "Failed to read the queue: {error}");
+					Vec::new()
+				},
+			};
+			if due.is_empty() {
+				// wait for new events, or for the next retry to be due
+				let _ = tokio::time::timeout(RETRY_DELAY, self.queued.notified()).await;
+				continue;
+			}
+			for delivery in due {
+				let result = self.post(&self.targets[target], &delivery).await;
+				let notifier = self.clone();
+				match blocking(move || notifier.settle(delivery, result)).await {
+					Ok(None) => {},
+					Ok(Some(retry)) => {
+						tokio::time::sleep(retry).await;
+						break;
+					},
+					Err(error) => {
+						log::warn!(target: LOG_TARGET, "Failed to update the queue: {error}");
+						break;
+					},
+				}
+			}
+		}
+	}
+
+	/// Deliveries to the target at `target` whose next attempt is due, oldest first.
+	fn due(&self, target: usize) -> Result<Vec<Delivery>, Error> {
+		let db = self.db();
+		let mut statement = db.prepare_cached(
+			"SELECT id, target, delivery, payload, attempts FROM deliveries
+			WHERE target = ?1 AND dead = 0 AND next_attempt <= ?2 ORDER BY id LIMIT ?3",
+		)?;
+		let due = statement
+			.query_map(
+				params![self.targets[target].name, unix_time().as_secs() as i64, BATCH_SIZE],
+				|row| {
+					Ok(Delivery {
+						id: row.get(0)?,
+						target: row.get(1)?,
+						delivery: row.get(2)?,
+						payload: row.get(3)?,
+						attempts: row.get(4)?,
+					})
+				},
+			)?
+			.collect::<Result<_, _>>()?;
+		Ok(due)
+	}
+
+	/// Post `delivery` to `target`.
+	async fn post(&self, target: &Target, delivery: &Delivery) -> Result<(), String> {
+		let timestamp = unix_time().as_secs().to_string();
+		let response = self
+			.http
+			.post(&target.url)
+			.header("Content-Type", "application/json")
+			.header("X-Probo-Delivery", &delivery.delivery)
+			.header("X-Probo-Signature", signature(&target.secret, &timestamp, &delivery.payload))
+			.header("X-Probo-Timestamp", timestamp)
+			.body(delivery.payload.clone())
+			.send()
+			.await
+			.map_err(|e| e.to_string())?;
+		if !response.status().is_success() {
+			return Err(format!("HTTP {}", response.status()));
+		}
+		Ok(())
+	}
+
+	/// Remove `delivery` from the queue if it succeeded, or schedule its next attempt. Returns
+	/// the delay before the next attempt, if there is one.
+	fn settle(
+		&self,
+		delivery: Delivery,
+		result: Result<(), String>,
+	) -> Result<Option<Duration>, Error> {
+		let db = self.db();
+		let Err(error) = result else {
+			db.execute("DELETE FROM deliveries WHERE id = ?1", [delivery.id])?;
+			return Ok(None);
+		};
+
+		let attempts = delivery.attempts + 1;
+		if attempts >= self.max_attempts {
+			log::error!(
+				target: LOG_TARGET,
+				"Gave up delivering {} to `{}` after {attempts} attempts, last error: {error}. \
+				Payload: {}",
+				delivery.delivery,
+				delivery.target,
+				delivery.payload,
+			);
+			db.execute(
+				"UPDATE deliveries SET attempts = ?2, last_error = ?3, dead = 1 WHERE id = ?1",
+				params![delivery.id, attempts, error],
+			)?;
+			Ok(None)
+		} else {
+			let delay = RETRY_DELAY.saturating_mul(1 << attempts.min(16)).min(MAX_RETRY_DELAY);
+			log::debug!(
+				target: LOG_TARGET,
+				"Delivering {} to `{}` failed: {error}, retrying in {}s",
+				delivery.delivery,
+				delivery.target,
+				delay.as_secs(),
+			);
+			db.execute(
+				"UPDATE deliveries SET attempts = ?2, last_error = ?3, next_attempt = ?4
+				WHERE id = ?1",
+				params![delivery.id, attempts, error, (unix_time() + delay).as_secs() as i64],
+			)?;
+			Ok(Some(delay))
+		}
+	}
+}
+
+/// Give up the deliveries to targets that are not configured anymore.
+fn retire_removed_targets(db: &Connection, targets: &[Target]) -> Result<(), rusqlite::Error> {
+	let queued = db
+		.prepare("SELECT DISTINCT target FROM deliveries WHERE dead = 0")?
+		.query_map([], |row| row.get::<_, String>(0))?
+		.collect::<Result<Vec<_>, _>>()?;
+	for name in queued.iter().filter(|name| !targets.iter().any(|target| &target.name == *name)) {
+		let retired = db.execute(
+			"UPDATE deliveries SET last_error = ?2, dead = 1 WHERE target = ?1 AND dead = 0",
+			params![name, "the target is not configured anymore"],
+		)?;
+		log::warn!(
+			target: LOG_TARGET,
+			"Gave up {retired} deliveries to `{name}`, which is not configured anymore",
+		);
+	}
+	Ok(())
+}
+
+/// Run `call`, which reads the queue or the client, on the blocking thread pool.
+async fn blocking<R: Send + 'static>(
+	call: impl FnOnce() -> Result<R, Error> + Send + 'static,
+) -> Result<R, Error> {
+	match tokio::task::spawn_blocking(call).await {
+		Ok(result) => result,
+		Err(error) => std::panic::resume_unwind(error.into_panic()),
+	}
+}
+
+/// Value of the `X-Probo-Signature` header of `payload` sent at `timestamp`.
+fn signature(secret: &str, timestamp: &str, payload: &str) -> String {
+	let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+		.expect("HMAC takes keys of any size; qed");
+	mac.update(timestamp.as_bytes());
+	mac.update(b".");
+	mac.update(payload.as_bytes());
+	let digest = mac.finalize().into_bytes();
+	format!("sha256={}", digest.iter().map(|byte| format!("{byte:02x}")).collect::<String>())
+}
+
+fn unix_time() -> Duration {
+	SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default()
+}
@@ -0,0 +1,62 @@
+//! Runtime API definition for the issuance proof pallet.
+//!
+//! Lets issuers and verifiers query the proof registry without decoding raw storage.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use codec::Codec;
+use sp_core::H256;
+
+pub use pallet_issuance_proof::{
+	merkle::{DisclosedAttribute, MerkleProof},
+	CrossingSummary, EntityCounts, InvalidReason, ProofPage, ProofStatus,
+	MAX_PROOFS_PER_PAGE,
+};
+
+sp_api::decl_runtime_apis! {
+	#[api_version(3)]
+	pub trait IssuanceProofApi<AccountId, ProofBytes, ProofKey, HolderSignature>
+	where
+		AccountId: Codec,
+		ProofBytes: Codec,
+		ProofKey: Codec,
+		HolderSignature: Codec,
+	{
+		/// Status of a proof. For a composite proof, the first invalid component is reported.
+		fn proof_status(proof: ProofBytes) -> ProofStatus;
+
+		/// Entry count and overstay status of a proof, `None` if the proof is unknown.
+		fn crossing_summary(proof: ProofBytes) -> Option<CrossingSummary>;
+
+		/// Whether `signature` answers the open challenge `nonce` with the holder key bound to
+		/// the challenged proof.
+		fn verify_presentation(nonce: [u8; 32], signature: HolderSignature) -> bool;
+
+		/// Whether the disclosed attributes belong to the attribute root anchored with `proof`.
+		fn verify_disclosure(proof: ProofBytes, attributes: Vec<DisclosedAttribute>) -> bool;
+
+		/// Status of a document anchored in the batch with the given `root`, given the leaf hash
+		/// of the document and its inclusion path. Revoked documents are reported as such.
+		fn batch_document_status(root: H256, leaf: H256, proof: MerkleProof) -> ProofStatus;
+
+		/// Keys of the proofs stored by `issuer`, starting after `cursor`. At most `limit` keys
+		/// and at most `MAX_PROOFS_PER_PAGE` are returned; pass the returned `next_cursor` to
+		/// get the next page.
+		fn proofs_by_issuer(
+			issuer: AccountId,
+			cursor: Option<ProofKey>,
+			limit: u32,
+		) -> ProofPage<ProofKey>;
+
+		/// Status of a proof from its key, for callers that do not hold the proof bytes.
+		#[api_version(2)]
+		fn proof_status_by_key(proof_key: ProofKey) -> ProofStatus;
+
+		/// Number of active and suspended issuers, and of verifiers.
+		#[api_version(3)]
+		fn entity_counts() -> EntityCounts;
+	}
+}
@@ -7,8 +7,9 @@ use crate::{Config, Pallet as Proof};
 use frame_benchmarking::v2::*;
 use frame_support::traits::fungible::Mutate;
 use frame_system::RawOrigin;
+use sp_core::{H256, U256};
 use frame::runtime::{prelude::*};
-use scale_info::prelude::vec::Vec;
+use scale_info::prelude::{vec, vec::Vec};
 
 
 fn create_user_account<T: Config>(seed: u32) -> T::AccountId {
@@ -17,6 +18,26 @@ fn create_user_account<T: Config>(seed: u32) -> T::AccountId {
 			.expect("infinite length input; no invalid inputs for type; qed")
 }
 
+fn create_proof<T: Config>(byte: u8, len: u32) -> ProofBytes<T> {
+	vec![byte; len as usize].try_into().expect("at most MaxProofLength; qed")
+}
+
+/// Commit `issuer` to `key` with a zero salt and move to the next block, so the commitment can
+/// be revealed.
+fn commit_to<T: Config, K: Encode>(issuer: &T::AccountId, key: &K) {
+	let _ = Proof::<T>::do_commit_proof(issuer.clone(), Proof::<T>::commitment(issuer, key, [0; 32]));
+	frame_system::Pallet::<T>::set_block_number(frame_system::Pallet::<T>::block_number() + 1u32.into());
+}
+
+/// Declare the key of `issuer` compromised from a block after its proofs were stored, so that
+/// checking a proof reads the compromise window and the issuance block without revoking it.
+fn declare_compromise_after_issuance<T: Config>(issuer: T::AccountId) {
+	let now = frame_system::Pallet::<T>::block_number();
+	frame_system::Pallet::<T>::set_block_number(now + 2u32.into());
+	let since = Proof::<T>::current_block() - U256::one();
+	let _ = Proof::<T>::do_declare_key_compromise(issuer, since);
+}
+
 #[benchmarks]
 mod benchmarks {
 	use super::*;
@@ -37,10 +58,11 @@ mod benchmarks {
 
     /// Benchmark: store_proof
     /// It always adds just one proof.
-    /// Worst case: Longest proof, 512 byte
-    /// O(1)
+    /// Worst case: with a usage limit, a bound holder key, an attribute root and a status list
+    /// entry
+    /// O(l), l = proof length, hashed into the registry key and deposited in the event
     #[benchmark]
-    fn store_proof() {
+    fn store_proof(l: Linear<1, { T::MaxProofLength::get() }>) {
         // setup issuer
         let issuer = create_user_account::<T>(0);
 
@@ -48,16 +70,379 @@ mod benchmarks {
         let _ = T::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
         let _ = Proof::<T>::register_entity(RawOrigin::Signed(issuer.clone()).into());
 
-        // generate longest proof
-        let mut proof = Vec::new();
-        for _i in 0..512 {
-            proof.push(1u8)
+        let proof = create_proof::<T>(1, l);
+        let holder = T::BenchmarkHelper::create_holder_key();
+        commit_to::<T>(&issuer, &Proof::<T>::proof_key(&proof));
+
+        #[extrinsic_call]
+        store_proof(
+            RawOrigin::Signed(issuer),
+            proof,
+            10_000.into(),
+            Some(1),
+            Some(holder),
+            Some(H256::repeat_byte(1)),
+            Some(0),
+            [0; 32],
+        );
+
+    }
+
+    /// Benchmark: authorize_verifier
+    /// It always adds just one verifier.
+    /// O(1)
+    #[benchmark]
+    fn authorize_verifier() -> Result<(), BenchmarkError> {
+        let verifier = create_user_account::<T>(1);
+        let origin =
+            T::VerifierAuthorityOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+        #[extrinsic_call]
+        authorize_verifier(origin as T::RuntimeOrigin, verifier);
+
+        Ok(())
+    }
+
+    /// Benchmark: consume_proof
+    /// Worst case: A usage limit of one, so the proof also gets exhausted, a status list entry to
+    /// check and an issuer key compromised after the proof was stored
+    /// O(l), l = proof length
+    #[benchmark]
+    fn consume_proof(l: Linear<1, { T::MaxProofLength::get() }>) {
+        // setup issuer and verifier
+        let issuer = create_user_account::<T>(0);
+        let verifier = create_user_account::<T>(1);
+        let _ = T::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        let _ = Proof::<T>::register_entity(RawOrigin::Signed(issuer.clone()).into());
+        let _ = Proof::<T>::do_authorize_verifier(verifier.clone());
+
+        let proof = create_proof::<T>(1, l);
+        let _ = Proof::<T>::do_store_proof(issuer.clone(), proof.clone(), 10_000.into(), Some(1), None, None, Some(0));
+        declare_compromise_after_issuance::<T>(issuer);
+
+        #[extrinsic_call]
+        consume_proof(RawOrigin::Signed(verifier), proof);
+    }
+
+    /// Benchmark: record_crossing
+    /// Worst case: An entry with a proof that has a status list entry, whose issuer key was
+    /// compromised after it was stored and whose crossing log is one record away from full
+    /// O(l), l = proof length, the log is bounded by MaxCrossingRecords
+    #[benchmark]
+    fn record_crossing(l: Linear<1, { T::MaxProofLength::get() }>) {
+        let issuer = create_user_account::<T>(0);
+        let verifier = create_user_account::<T>(1);
+        let _ = T::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        let _ = Proof::<T>::register_entity(RawOrigin::Signed(issuer.clone()).into());
+        let _ = Proof::<T>::do_authorize_verifier(verifier.clone());
+
+        let proof = create_proof::<T>(1, l);
+        let _ = Proof::<T>::do_store_proof(issuer.clone(), proof.clone(), 10_000.into(), None, None, None, Some(0));
+        declare_compromise_after_issuance::<T>(issuer);
+
+        let checkpoint: CheckpointCode = vec![1u8; 16].try_into().expect("checkpoint too long");
+        for _i in 1..T::MaxCrossingRecords::get() {
+            let _ = Proof::<T>::do_record_crossing(
+                verifier.clone(),
+                proof.clone(),
+                CrossingDirection::Entry,
+                checkpoint.clone(),
+            );
         }
 
-        let proof = proof.try_into().expect("proof too long");
+        #[extrinsic_call]
+        record_crossing(RawOrigin::Signed(verifier), proof, CrossingDirection::Entry, checkpoint);
+    }
+
+    /// Benchmark: issue_challenge
+    /// It always adds just one challenge.
+    /// O(l), l = proof length
+    #[benchmark]
+    fn issue_challenge(l: Linear<1, { T::MaxProofLength::get() }>) {
+        let issuer = create_user_account::<T>(0);
+        let verifier = create_user_account::<T>(1);
+        let _ = T::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        let _ = Proof::<T>::register_entity(RawOrigin::Signed(issuer.clone()).into());
+        let _ = Proof::<T>::do_authorize_verifier(verifier.clone());
+
+        let holder = T::BenchmarkHelper::create_holder_key();
+        let proof = create_proof::<T>(1, l);
+        let _ = Proof::<T>::do_store_proof(issuer, proof.clone(), 10_000.into(), None, Some(holder), None, None);
 
         #[extrinsic_call]
-        store_proof(RawOrigin::Signed(issuer), proof, 10_000.into());
+        issue_challenge(RawOrigin::Signed(verifier), proof);
+    }
+
+    /// Benchmark: complete_challenge
+    /// Dominated by verifying the holder signature.
+    /// O(1)
+    #[benchmark]
+    fn complete_challenge() {
+        let issuer = create_user_account::<T>(0);
+        let verifier = create_user_account::<T>(1);
+        let _ = T::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        let _ = Proof::<T>::register_entity(RawOrigin::Signed(issuer.clone()).into());
+        let _ = Proof::<T>::do_authorize_verifier(verifier.clone());
+
+        let holder = T::BenchmarkHelper::create_holder_key();
+        let proof = create_proof::<T>(1, T::MaxProofLength::get());
+        let _ = Proof::<T>::do_store_proof(issuer, proof.clone(), 10_000.into(), None, Some(holder.clone()), None, None);
+        let _ = Proof::<T>::do_issue_challenge(verifier.clone(), proof.clone());
+
+        let (nonce, _) = Challenges::<T>::iter().next().expect("challenge was just issued; qed");
+        let payload = Proof::<T>::presentation_payload(nonce, &Proof::<T>::proof_key(&proof));
+        let signature = T::BenchmarkHelper::sign_presentation(&holder, &payload);
+
+        #[extrinsic_call]
+        complete_challenge(RawOrigin::Signed(verifier), nonce, signature);
 
+        assert!(!Challenges::<T>::contains_key(nonce));
     }
-}
\ No newline at end of file
+
+    /// Benchmark: store_composite_proof
+    /// Every component is checked for nesting and validity.
+    /// O(c * l), c = number of components, l = length of the composite and component proofs
+    #[benchmark]
+    fn store_composite_proof(
+        c: Linear<2, { T::MaxComponents::get() }>,
+        l: Linear<1, { T::MaxProofLength::get() }>,
+    ) {
+        let issuer = create_user_account::<T>(0);
+        let _ = T::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        let _ = Proof::<T>::register_entity(RawOrigin::Signed(issuer.clone()).into());
+
+        let mut components = Vec::new();
+        for i in 0..c {
+            let component = create_proof::<T>(i as u8, l);
+            let _ = Proof::<T>::do_store_proof(issuer.clone(), component.clone(), 10_000.into(), None, None, None, None);
+            components.push(component);
+        }
+        let components: BoundedVec<_, _> = components.try_into().expect("at most MaxComponents; qed");
+        let proof = create_proof::<T>(u8::MAX, l);
+        commit_to::<T>(&issuer, &Proof::<T>::proof_key(&proof));
+
+        #[extrinsic_call]
+        store_composite_proof(RawOrigin::Signed(issuer), proof.clone(), 10_000.into(), components, [0; 32]);
+
+        assert!(CompositeComponents::<T>::contains_key(Proof::<T>::proof_key(&proof)));
+    }
+
+    /// Benchmark: rekey_proof
+    /// Moves one longest proof still keyed by its bytes to `Proofs`.
+    /// O(1)
+    #[benchmark]
+    fn rekey_proof() {
+        let issuer = create_user_account::<T>(0);
+        let proof = vec![1u8; 512];
+        migrations::v1::IssuanceProof::<T>::insert(
+            &proof,
+            migrations::v1::OldProofMeta { issuer, expiry_block: 10_000.into() },
+        );
+        RekeyOngoing::<T>::put(true);
+
+        #[block]
+        {
+            migrations::v1::migrate_proof::<T>(&proof);
+        }
+
+        assert!(Proofs::<T>::contains_key(Proof::<T>::proof_key(&proof)));
+    }
+
+    /// Benchmark: anchor_batch
+    /// It always adds just one batch root, whatever the number of documents.
+    /// O(1)
+    #[benchmark]
+    fn anchor_batch() {
+        let issuer = create_user_account::<T>(0);
+        let _ = T::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        let _ = Proof::<T>::register_entity(RawOrigin::Signed(issuer.clone()).into());
+        let root = H256::repeat_byte(1);
+        commit_to::<T>(&issuer, &root);
+
+        #[extrinsic_call]
+        anchor_batch(RawOrigin::Signed(issuer), root, 10_000.into(), [0; 32]);
+
+        assert!(Batches::<T>::contains_key(root));
+    }
+
+    /// Benchmark: revoke_batch_leaves
+    /// Worst case: none of the leaves was revoked before
+    /// O(n), n = number of leaves
+    #[benchmark]
+    fn revoke_batch_leaves(n: Linear<1, { T::MaxLeavesPerRevocation::get() }>) {
+        let issuer = create_user_account::<T>(0);
+        let _ = T::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        let _ = Proof::<T>::register_entity(RawOrigin::Signed(issuer.clone()).into());
+        let root = H256::repeat_byte(1);
+        let _ = Proof::<T>::do_anchor_batch(issuer.clone(), root, 10_000.into());
+
+        let leaves: Vec<_> = (0..n).map(|i| merkle::leaf_hash(&i.encode())).collect();
+        let leaves: BoundedVec<_, _> = leaves.try_into().expect("at most MaxLeavesPerRevocation; qed");
+        let last = *leaves.last().expect("at least one leaf; qed");
+
+        #[extrinsic_call]
+        revoke_batch_leaves(RawOrigin::Signed(issuer), root, leaves);
+
+        assert!(RevokedLeaves::<T>::contains_key(root, last));
+    }
+
+    /// Benchmark: update_revocation_status
+    /// Worst case: every entry in a different chunk
+    /// O(n), n = number of updates
+    #[benchmark]
+    fn update_revocation_status(n: Linear<1, { T::MaxStatusUpdates::get() }>) {
+        let issuer = create_user_account::<T>(0);
+        let _ = T::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        let _ = Proof::<T>::register_entity(RawOrigin::Signed(issuer.clone()).into());
+
+        let chunks = T::StatusListLength::get().div_ceil(STATUS_CHUNK_BITS);
+        let updates: Vec<_> = (0..n).map(|i| ((i % chunks) * STATUS_CHUNK_BITS + i / chunks, true)).collect();
+        let updates: BoundedVec<_, _> = updates.try_into().expect("at most MaxStatusUpdates; qed");
+
+        #[extrinsic_call]
+        update_revocation_status(RawOrigin::Signed(issuer.clone()), updates);
+
+        assert!(StatusListChunks::<T>::contains_key(&issuer, 0));
+    }
+
+    /// Benchmark: release_expired_status
+    /// Releases the status list entry of one expired proof.
+    /// O(1)
+    #[benchmark]
+    fn release_expired_status() {
+        let issuer = create_user_account::<T>(0);
+        let _ = T::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        let _ = Proof::<T>::register_entity(RawOrigin::Signed(issuer.clone()).into());
+
+        let proof = create_proof::<T>(1, T::MaxProofLength::get());
+        let _ = Proof::<T>::do_store_proof(issuer.clone(), proof.clone(), 1u32.into(), None, None, None, Some(0));
+        let _ = Proof::<T>::do_update_revocation_status(issuer.clone(), vec![(0, true)].try_into().expect("one update; qed"));
+        let proof_key = Proof::<T>::proof_key(&proof);
+        let expiry_block = Proofs::<T>::get(proof_key).expect("proof was just stored; qed").expiry_block;
+        frame_system::Pallet::<T>::set_block_number(frame_system::Pallet::<T>::block_number() + 2u32.into());
+
+        #[block]
+        {
+            Proof::<T>::release_expired_statuses(Weight::MAX);
+        }
+
+        assert!(!StatusExpiry::<T>::contains_key(expiry_block, proof_key));
+        assert!(!StatusListChunks::<T>::contains_key(&issuer, 0));
+    }
+
+    /// Benchmark: remove_stale_commitment
+    /// Removes one commitment that can no longer be revealed.
+    /// O(1)
+    #[benchmark]
+    fn remove_stale_commitment() {
+        let issuer = create_user_account::<T>(0);
+        let _ = T::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        let _ = Proof::<T>::register_entity(RawOrigin::Signed(issuer.clone()).into());
+        let commitment = H256::repeat_byte(1);
+        let _ = Proof::<T>::do_commit_proof(issuer.clone(), commitment);
+        frame_system::Pallet::<T>::set_block_number(
+            frame_system::Pallet::<T>::block_number() + T::CommitmentTtl::get() + 1u32.into(),
+        );
+
+        #[block]
+        {
+            Proof::<T>::remove_stale_commitments(Weight::MAX);
+        }
+
+        assert!(!ProofCommitments::<T>::contains_key(&issuer, commitment));
+    }
+
+    /// Benchmark: remove_expired_challenge
+    /// Removes one challenge that can no longer be answered.
+    /// O(1)
+    #[benchmark]
+    fn remove_expired_challenge() {
+        let issuer = create_user_account::<T>(0);
+        let verifier = create_user_account::<T>(1);
+        let _ = T::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        let _ = Proof::<T>::register_entity(RawOrigin::Signed(issuer.clone()).into());
+        let _ = Proof::<T>::do_authorize_verifier(verifier.clone());
+
+        let holder = T::BenchmarkHelper::create_holder_key();
+        let proof = create_proof::<T>(1, 64);
+        let _ = Proof::<T>::do_store_proof(issuer, proof.clone(), 10_000.into(), None, Some(holder), None, None);
+        let _ = Proof::<T>::do_issue_challenge(verifier, proof);
+        let (nonce, _) = Challenges::<T>::iter().next().expect("challenge was just issued; qed");
+        frame_system::Pallet::<T>::set_block_number(
+            frame_system::Pallet::<T>::block_number() + T::ChallengeTtl::get() + 1u32.into(),
+        );
+
+        #[block]
+        {
+            Proof::<T>::remove_expired_challenges(Weight::MAX);
+        }
+
+        assert!(!Challenges::<T>::contains_key(nonce));
+    }
+
+    /// Benchmark: declare_key_compromise
+    /// Worst case: the key was already declared compromised, so the windows are merged
+    /// O(1), the proofs of the key are not touched
+    #[benchmark]
+    fn declare_key_compromise() {
+        let issuer = create_user_account::<T>(0);
+        let _ = T::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        let _ = Proof::<T>::register_entity(RawOrigin::Signed(issuer.clone()).into());
+        frame_system::Pallet::<T>::set_block_number(frame_system::Pallet::<T>::block_number() + 2u32.into());
+        let now = Proof::<T>::current_block();
+        let _ = Proof::<T>::do_declare_key_compromise(issuer.clone(), now);
+
+        #[extrinsic_call]
+        declare_key_compromise(RawOrigin::Signed(issuer.clone()), issuer.clone(), now - U256::one());
+
+        assert_eq!(CompromisedKeys::<T>::get(&issuer).map(|window| window.since), Some(now - U256::one()));
+    }
+
+    /// Benchmark: commit_proof
+    /// It always adds just one commitment.
+    /// O(1)
+    #[benchmark]
+    fn commit_proof() {
+        let issuer = create_user_account::<T>(0);
+        let _ = T::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        let _ = Proof::<T>::register_entity(RawOrigin::Signed(issuer.clone()).into());
+        let commitment = H256::repeat_byte(1);
+
+        #[extrinsic_call]
+        commit_proof(RawOrigin::Signed(issuer.clone()), commitment);
+
+        assert!(ProofCommitments::<T>::contains_key(&issuer, commitment));
+    }
+
+    /// Benchmark: prune_expired
+    /// Worst case: every proof has a status list entry and a crossing log to remove
+    /// O(n), n = number of proofs pruned
+    #[benchmark]
+    fn prune_expired(n: Linear<1, { T::MaxPrunePerBlock::get() }>) {
+        let issuer = create_user_account::<T>(0);
+        let verifier = create_user_account::<T>(1);
+        let _ = T::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        let _ = Proof::<T>::register_entity(RawOrigin::Signed(issuer.clone()).into());
+        let _ = Proof::<T>::do_authorize_verifier(verifier.clone());
+
+        let checkpoint: CheckpointCode = vec![1u8; 16].try_into().expect("checkpoint too long");
+        let mut proof_keys = Vec::new();
+        for i in 0..n {
+            let mut bytes = vec![1u8; T::MaxProofLength::get() as usize];
+            bytes[..4].copy_from_slice(&i.to_le_bytes());
+            let proof: ProofBytes<T> = bytes.try_into().expect("at most MaxProofLength; qed");
+            let _ = Proof::<T>::do_store_proof(issuer.clone(), proof.clone(), 1u32.into(), None, None, None, Some(i));
+            let _ = Proof::<T>::do_record_crossing(verifier.clone(), proof.clone(), CrossingDirection::Exit, checkpoint.clone());
+            proof_keys.push(Proof::<T>::proof_key(&proof));
+        }
+        let proof_keys: BoundedVec<_, _> = proof_keys.try_into().expect("at most MaxPrunePerBlock; qed");
+        frame_system::Pallet::<T>::set_block_number(
+            frame_system::Pallet::<T>::block_number() + T::ChallengeTtl::get() + 2u32.into(),
+        );
+
+        #[extrinsic_call]
+        prune_expired(RawOrigin::None, proof_keys);
+
+        assert_eq!(ProofCountByIssuer::<T>::get(&issuer), 0);
+    }
+}
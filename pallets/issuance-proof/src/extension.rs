@@ -0,0 +1,360 @@
+//! Transaction extensions for proof issuance.
+//!
+//! [`ValidateProofIssuance`] runs the checks of issuance when the transaction pool validates the
+//! transaction, so that issuance that would fail is rejected before its sender pays a fee. It tags
+//! issuance by the proof key, batch root or commitment it claims, so that the pool keeps one
+//! pending transaction per claim, and raises the priority of valid issuance.
+//!
+//! [`ChargeIssuanceQuota`] wraps the fee payment extension. Issuance transactions of active
+//! entities draw from a quota refreshed every era instead of paying fees, so issuers do not have
+//! to keep a balance topped up. Only issuance that succeeds uses up the quota. Once the quota of
+//! the era is used up, for other calls and for everybody else, the wrapped extension charges fees
+//! as usual.
+
+use crate::{Batches, Call, Config, Pallet, ProofCommitments};
+use sp_std::vec::Vec;
+use codec::{Decode, DecodeWithMemTracking, Encode};
+use core::marker::PhantomData;
+use frame_support::{dispatch::DispatchInfo, pallet_prelude::*, traits::IsSubType};
+use sp_runtime::{
+    impl_tx_ext_default,
+    traits::{
+        AsSystemOriginSigner, DispatchInfoOf, Dispatchable, Implication, PostDispatchInfoOf,
+        TransactionExtension, TransactionExtensionMetadata, ValidateResult,
+    },
+    transaction_validity::{
+        InvalidTransaction, TransactionSource, TransactionValidity, ValidTransaction,
+    },
+    DispatchResult,
+};
+
+/// Why an issuance transaction is rejected, reported as `InvalidTransaction::Custom`.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+#[repr(u8)]
+pub enum ValidityError {
+    /// The sender is not an active whitelisted entity.
+    NotWhitelistEntity = 0,
+    /// The proof is already stored.
+    ProofAlreadyExist = 1,
+    /// The batch root is already anchored.
+    BatchAlreadyAnchored = 2,
+    /// The sender has no commitment to reveal, or it is not from an earlier block.
+    CommitmentNotFound = 3,
+    /// The sender has already made this commitment.
+    CommitmentAlreadyExist = 4,
+}
+
+impl From<ValidityError> for TransactionValidityError {
+    fn from(error: ValidityError) -> Self {
+        InvalidTransaction::Custom(error as u8).into()
+    }
+}
+
+/// Validates `store_proof`, `store_composite_proof`, `anchor_batch` and `commit_proof` in the
+/// transaction pool. Other calls pass through untouched.
+#[derive(Encode, Decode, DecodeWithMemTracking, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct ValidateProofIssuance<T>(PhantomData<T>);
+
+impl<T> ValidateProofIssuance<T> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T> Default for ValidateProofIssuance<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> core::fmt::Debug for ValidateProofIssuance<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "ValidateProofIssuance")
+    }
+}
+
+impl<T: Config> ValidateProofIssuance<T> {
+    /// Check an issuance call of `who` against the current state.
+    pub fn validate_issuance(
+        who: &T::AccountId,
+        call: &Call<T>,
+    ) -> Result<ValidTransaction, TransactionValidityError> {
+        let tag = match call {
+            Call::store_proof { proof, salt, .. }
+            | Call::store_composite_proof { proof, salt, .. } => {
+                ensure!(
+                    Pallet::<T>::get_proof(proof).is_none(),
+                    ValidityError::ProofAlreadyExist
+                );
+                let proof_key = Pallet::<T>::proof_key(proof);
+                Self::ensure_commitment(who, &proof_key, *salt)?;
+                (b"proof", proof_key).encode()
+            }
+            Call::anchor_batch { root, salt, .. } => {
+                ensure!(!Batches::<T>::contains_key(root), ValidityError::BatchAlreadyAnchored);
+                Self::ensure_commitment(who, root, *salt)?;
+                (b"batch", root).encode()
+            }
+            Call::commit_proof { commitment } => {
+                ensure!(
+                    !ProofCommitments::<T>::contains_key(who, commitment),
+                    ValidityError::CommitmentAlreadyExist
+                );
+                (b"commitment", who, commitment).encode()
+            }
+            _ => return Ok(ValidTransaction::default()),
+        };
+        Pallet::<T>::is_whitelist_entity(who).map_err(|_| ValidityError::NotWhitelistEntity)?;
+
+        ValidTransaction::with_tag_prefix("ProofIssuance")
+            .priority(T::IssuancePriority::get())
+            .and_provides(tag)
+            .build()
+    }
+
+    fn ensure_commitment<K: Encode>(
+        who: &T::AccountId,
+        key: &K,
+        salt: [u8; 32],
+    ) -> Result<(), TransactionValidityError> {
+        // the transaction pool validates against the block the transaction would be included in
+        let commitment = Pallet::<T>::commitment(who, key, salt);
+        let current_block = Pallet::<T>::current_block();
+        ProofCommitments::<T>::get(who, commitment)
+            .filter(|committed_at| {
+                *committed_at < current_block
+                    && current_block <= Pallet::<T>::commitment_expiry(*committed_at)
+            })
+            .map(|_| ())
+            .ok_or_else(|| ValidityError::CommitmentNotFound.into())
+    }
+}
+
+impl<T: Config + Send + Sync> TransactionExtension<<T as frame_system::Config>::RuntimeCall>
+    for ValidateProofIssuance<T>
+where
+    <T as frame_system::Config>::RuntimeCall:
+        Dispatchable<Info = DispatchInfo> + IsSubType<Call<T>>,
+    <<T as frame_system::Config>::RuntimeCall as Dispatchable>::RuntimeOrigin:
+        AsSystemOriginSigner<T::AccountId> + Clone,
+{
+    const IDENTIFIER: &'static str = "ValidateProofIssuance";
+    type Implicit = ();
+    type Val = ();
+    type Pre = ();
+
+    fn weight(&self, call: &<T as frame_system::Config>::RuntimeCall) -> Weight {
+        match call.is_sub_type() {
+            // the entity, the registry entry and the commitment
+            Some(Call::store_proof { .. })
+            | Some(Call::store_composite_proof { .. })
+            | Some(Call::anchor_batch { .. }) => T::DbWeight::get().reads(4),
+            Some(Call::commit_proof { .. }) => T::DbWeight::get().reads(2),
+            _ => Weight::zero(),
+        }
+    }
+
+    fn validate(
+        &self,
+        origin: <<T as frame_system::Config>::RuntimeCall as Dispatchable>::RuntimeOrigin,
+        call: &<T as frame_system::Config>::RuntimeCall,
+        _info: &DispatchInfoOf<<T as frame_system::Config>::RuntimeCall>,
+        _len: usize,
+        _self_implicit: Self::Implicit,
+        _inherited_implication: &impl Implication,
+        _source: TransactionSource,
+    ) -> ValidateResult<Self::Val, <T as frame_system::Config>::RuntimeCall> {
+        let (Some(who), Some(call)) = (origin.as_system_origin_signer(), call.is_sub_type()) else {
+            return Ok((ValidTransaction::default(), (), origin));
+        };
+        let validity = Self::validate_issuance(who, call)?;
+        Ok((validity, (), origin))
+    }
+
+    impl_tx_ext_default!(<T as frame_system::Config>::RuntimeCall; prepare);
+}
+
+/// Charges fees with `S`, unless an active entity makes an issuance call within its quota.
+///
+/// It encodes like `S` and takes over its identifier, so it can replace `S` in the runtime
+/// without changing what transactions sign.
+#[derive(Encode, Decode, DecodeWithMemTracking, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct ChargeIssuanceQuota<T, S>(pub S, PhantomData<T>);
+
+impl<T, S> ChargeIssuanceQuota<T, S> {
+    pub fn new(inner: S) -> Self {
+        Self(inner, PhantomData)
+    }
+}
+
+impl<T, S> From<S> for ChargeIssuanceQuota<T, S> {
+    fn from(inner: S) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<T, S: core::fmt::Debug> core::fmt::Debug for ChargeIssuanceQuota<T, S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "ChargeIssuanceQuota<{:?}>", self.0)
+    }
+}
+
+/// Whether a transaction is charged by the wrapped extension or drawn from the quota of an
+/// entity.
+pub enum QuotaOrCharge<Inner, AccountId> {
+    Charge(Inner),
+    Quota(AccountId),
+}
+
+impl<T: Config, S> ChargeIssuanceQuota<T, S>
+where
+    <T as frame_system::Config>::RuntimeCall: Dispatchable + IsSubType<Call<T>>,
+    <<T as frame_system::Config>::RuntimeCall as Dispatchable>::RuntimeOrigin:
+        AsSystemOriginSigner<T::AccountId>,
+{
+    /// Whether `call` issues proofs or batches, or updates their status.
+    fn is_issuance(call: &<T as frame_system::Config>::RuntimeCall) -> bool {
+        matches!(
+            call.is_sub_type(),
+            Some(Call::store_proof { .. })
+                | Some(Call::store_composite_proof { .. })
+                | Some(Call::commit_proof { .. })
+                | Some(Call::anchor_batch { .. })
+                | Some(Call::update_revocation_status { .. })
+        )
+    }
+
+    /// The entity whose quota pays for `call`, if any.
+    fn quota_payer(
+        origin: &<<T as frame_system::Config>::RuntimeCall as Dispatchable>::RuntimeOrigin,
+        call: &<T as frame_system::Config>::RuntimeCall,
+    ) -> Option<T::AccountId> {
+        if !Self::is_issuance(call) {
+            return None;
+        }
+        origin
+            .as_system_origin_signer()
+            .filter(|who| Pallet::<T>::remaining_quota(who) > 0)
+            .cloned()
+    }
+}
+
+impl<T: Config + Send + Sync, S> TransactionExtension<<T as frame_system::Config>::RuntimeCall>
+    for ChargeIssuanceQuota<T, S>
+where
+    S: TransactionExtension<<T as frame_system::Config>::RuntimeCall>,
+    <T as frame_system::Config>::RuntimeCall:
+        Dispatchable<Info = DispatchInfo> + IsSubType<Call<T>>,
+    <<T as frame_system::Config>::RuntimeCall as Dispatchable>::RuntimeOrigin:
+        AsSystemOriginSigner<T::AccountId> + Clone,
+{
+    const IDENTIFIER: &'static str = S::IDENTIFIER;
+    type Implicit = S::Implicit;
+    type Val = QuotaOrCharge<S::Val, T::AccountId>;
+    type Pre = QuotaOrCharge<S::Pre, T::AccountId>;
+
+    fn implicit(&self) -> Result<Self::Implicit, TransactionValidityError> {
+        self.0.implicit()
+    }
+
+    fn metadata() -> Vec<TransactionExtensionMetadata> {
+        S::metadata()
+    }
+
+    fn weight(&self, call: &<T as frame_system::Config>::RuntimeCall) -> Weight {
+        let weight = self.0.weight(call);
+        if Self::is_issuance(call) {
+            // the entity and its quota
+            weight.saturating_add(T::DbWeight::get().reads_writes(2, 1))
+        } else {
+            weight
+        }
+    }
+
+    fn validate(
+        &self,
+        origin: <<T as frame_system::Config>::RuntimeCall as Dispatchable>::RuntimeOrigin,
+        call: &<T as frame_system::Config>::RuntimeCall,
+        info: &DispatchInfoOf<<T as frame_system::Config>::RuntimeCall>,
+        len: usize,
+        self_implicit: Self::Implicit,
+        inherited_implication: &impl Implication,
+        source: TransactionSource,
+    ) -> ValidateResult<Self::Val, <T as frame_system::Config>::RuntimeCall> {
+        if let Some(who) = Self::quota_payer(&origin, call) {
+            return Ok((ValidTransaction::default(), QuotaOrCharge::Quota(who), origin));
+        }
+        let (validity, val, origin) = self.0.validate(
+            origin,
+            call,
+            info,
+            len,
+            self_implicit,
+            inherited_implication,
+            source,
+        )?;
+        Ok((validity, QuotaOrCharge::Charge(val), origin))
+    }
+
+    fn prepare(
+        self,
+        val: Self::Val,
+        origin: &<<T as frame_system::Config>::RuntimeCall as Dispatchable>::RuntimeOrigin,
+        call: &<T as frame_system::Config>::RuntimeCall,
+        info: &DispatchInfoOf<<T as frame_system::Config>::RuntimeCall>,
+        len: usize,
+    ) -> Result<Self::Pre, TransactionValidityError> {
+        match val {
+            QuotaOrCharge::Charge(val) => {
+                self.0.prepare(val, origin, call, info, len).map(QuotaOrCharge::Charge)
+            }
+            // the quota is only used once the call succeeded
+            QuotaOrCharge::Quota(who) => Ok(QuotaOrCharge::Quota(who)),
+        }
+    }
+
+    fn post_dispatch_details(
+        pre: Self::Pre,
+        info: &DispatchInfoOf<<T as frame_system::Config>::RuntimeCall>,
+        post_info: &PostDispatchInfoOf<<T as frame_system::Config>::RuntimeCall>,
+        len: usize,
+        result: &DispatchResult,
+    ) -> Result<Weight, TransactionValidityError> {
+        match pre {
+            QuotaOrCharge::Charge(pre) => S::post_dispatch_details(pre, info, post_info, len, result),
+            QuotaOrCharge::Quota(who) => {
+                if result.is_ok() {
+                    Pallet::<T>::use_quota(&who);
+                }
+                Ok(Weight::zero())
+            }
+        }
+    }
+
+    fn bare_validate(
+        call: &<T as frame_system::Config>::RuntimeCall,
+        info: &DispatchInfoOf<<T as frame_system::Config>::RuntimeCall>,
+        len: usize,
+    ) -> TransactionValidity {
+        S::bare_validate(call, info, len)
+    }
+
+    fn bare_validate_and_prepare(
+        call: &<T as frame_system::Config>::RuntimeCall,
+        info: &DispatchInfoOf<<T as frame_system::Config>::RuntimeCall>,
+        len: usize,
+    ) -> Result<(), TransactionValidityError> {
+        S::bare_validate_and_prepare(call, info, len)
+    }
+
+    fn bare_post_dispatch(
+        info: &DispatchInfoOf<<T as frame_system::Config>::RuntimeCall>,
+        post_info: &mut PostDispatchInfoOf<<T as frame_system::Config>::RuntimeCall>,
+        len: usize,
+        result: &DispatchResult,
+    ) -> Result<(), TransactionValidityError> {
+        S::bare_post_dispatch(info, post_info, len, result)
+    }
+}
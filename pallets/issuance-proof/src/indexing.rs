@@ -0,0 +1,107 @@
+//! Records written to the offchain database of the node with offchain indexing, so that the
+//! proofs of an issuer and the history of a proof can be looked up without replaying blocks or
+//! reading chain state. They are only written by nodes run with `--enable-offchain-indexing`.
+//!
+//! Offchain indexing can't read what it wrote, and counting the records on chain would grow the
+//! state with every proof. So the pallet only writes what happened to proofs under the block and
+//! the index of the event it deposited, with the number of events of the block, and the node
+//! folds the records of finalized blocks into the lists of [`issuer_proof_key`] and
+//! [`history_key`]. A proof stored again after it was pruned starts a new history over the old
+//! one.
+//!
+//! Records are written as blocks are imported, including blocks that end up off the finalized
+//! chain. The hash of a block isn't known while it executes, so they are written under its
+//! [`block_id`] instead, which the node derives from the header of the finalized block.
+//!
+//! Proofs stored before the indexing was added are not listed.
+
+use crate::CrossingDirection;
+use codec::{Decode, Encode};
+use frame_support::RuntimeDebug;
+use scale_info::TypeInfo;
+use sp_core::{hashing::blake2_256, U256};
+use sp_runtime::generic::{Digest, DigestItem};
+use sp_std::vec::Vec;
+
+/// Prefix of the keys of the proofs indexed under their issuer.
+pub const ISSUER_PROOFS_PREFIX: &[u8] = b"probo::issuer-proofs";
+
+/// Prefix of the keys of the proof history records.
+pub const PROOF_HISTORY_PREFIX: &[u8] = b"probo::proof-history";
+
+/// Prefix of the keys of the proof events written by the pallet.
+pub const BLOCK_EVENTS_PREFIX: &[u8] = b"probo::block-events";
+
+/// Something that happened to a proof.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, TypeInfo, RuntimeDebug)]
+pub enum ProofEvent<AccountId> {
+    Stored { issuer: AccountId },
+    Consumed { verifier: AccountId },
+    CrossingRecorded { verifier: AccountId, direction: CrossingDirection },
+    Presented { verifier: AccountId },
+    Pruned,
+    /// The entry of the proof in the status list of its issuer was set.
+    Revoked,
+    /// The entry of the proof in the status list of its issuer was cleared.
+    Reinstated,
+    /// The proof was stored in the window of a key compromise of its issuer.
+    KeyCompromised,
+}
+
+/// What the pallet writes for an event it deposited.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, TypeInfo, RuntimeDebug)]
+pub enum BlockRecord<AccountId, ProofKey> {
+    /// Things that happened to proofs.
+    Proofs(Vec<(ProofKey, ProofEvent<AccountId>)>),
+    /// The key of `issuer` was declared compromised. Which proofs it stored from block `since`
+    /// to `until` is only known to the node, which records [`ProofEvent::KeyCompromised`] for
+    /// them.
+    KeyCompromised { issuer: AccountId, since: U256, until: U256 },
+}
+
+/// An entry of the history of a proof.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, TypeInfo, RuntimeDebug)]
+pub struct HistoryRecord<AccountId> {
+    pub block: U256,
+    pub event: ProofEvent<AccountId>,
+}
+
+/// Key of the number of proofs indexed under `issuer`.
+pub fn issuer_proofs_len_key<AccountId: Encode>(issuer: &AccountId) -> Vec<u8> {
+    (ISSUER_PROOFS_PREFIX, issuer).encode()
+}
+
+/// Key of the `n`th proof indexed under `issuer`, in the order they were stored.
+pub fn issuer_proof_key<AccountId: Encode>(issuer: &AccountId, n: u32) -> Vec<u8> {
+    (ISSUER_PROOFS_PREFIX, issuer, n).encode()
+}
+
+/// Key of the number of records in the history of a proof.
+pub fn history_len_key<ProofKey: Encode>(proof_key: &ProofKey) -> Vec<u8> {
+    (PROOF_HISTORY_PREFIX, proof_key).encode()
+}
+
+/// Key of the `n`th record in the history of a proof, oldest first.
+pub fn history_key<ProofKey: Encode>(proof_key: &ProofKey, n: u32) -> Vec<u8> {
+    (PROOF_HISTORY_PREFIX, proof_key, n).encode()
+}
+
+/// Identifies a block before its hash is known, from its parent and its pre-runtime digests,
+/// e.g. the Aura slot. Blocks built on the same parent only share it if the same author built
+/// them in the same slot.
+pub fn block_id<Hash: Encode>(parent_hash: &Hash, digest: &Digest) -> [u8; 32] {
+    let pre_runtime: Vec<&DigestItem> =
+        digest.logs.iter().filter(|item| matches!(item, DigestItem::PreRuntime(..))).collect();
+    (parent_hash, pre_runtime).using_encoded(blake2_256)
+}
+
+/// Key of the number of events of a block, an upper bound of the indexes of its proof events.
+pub fn block_events_len_key(block: U256, block_id: &[u8; 32]) -> Vec<u8> {
+    (BLOCK_EVENTS_PREFIX, block, block_id).encode()
+}
+
+/// Key of the [`BlockRecord`] of the `n`th event of a block. Only events about proofs have an
+/// entry.
+pub fn block_event_key(block: U256, block_id: &[u8; 32], n: u32) -> Vec<u8> {
+    (BLOCK_EVENTS_PREFIX, block, block_id, n).encode()
+}
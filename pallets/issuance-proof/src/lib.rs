@@ -14,28 +14,43 @@ mod benchmarking;
 pub mod weights;
 pub use weights::*;
 
+pub mod extension;
+pub mod indexing;
+pub mod merkle;
+pub mod migrations;
+
 #[frame_support::pallet]
 pub mod pallet {
 
     use super::*;
-    use codec::MaxEncodedLen;
-    use frame_support::traits::fungible;
-    use frame_support::traits::fungible::{Inspect, MutateHold};
+    use crate::indexing::{BlockRecord, ProofEvent};
+    use codec::{DecodeWithMemTracking, MaxEncodedLen};
+    use frame_support::traits::{fungible, UnixTime};
+    use frame_support::traits::fungible::{Inspect, InspectHold, MutateHold};
     use frame_support::traits::tokens::{Fortitude, Preservation};
     use frame_support::{pallet_prelude::*, dispatch::GetDispatchInfo};
+    use frame_system::offchain::{CreateInherent, SubmitTransaction};
     use frame_system::pallet_prelude::*;
-    use sp_core::U256;
-    use sp_runtime::traits::{Dispatchable};
+    use sp_core::{H256, U256};
+    use sp_core::hashing::blake2_256;
+    use sp_runtime::traits::{Dispatchable, Hash as HashT, IdentifyAccount, Saturating, Verify};
+    use sp_runtime::offchain::storage::StorageValueRef;
+    use sp_runtime::SaturatedConversion;
+    use sp_std::vec::Vec;
 
     pub type BalanceOf<T> = <<T as Config>::NativeBalance as fungible::Inspect<
         <T as frame_system::Config>::AccountId,
     >>::Balance;
 
+    /// The in-code storage version.
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(4);
+
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
     #[pallet::config]
-    pub trait Config: frame_system::Config {
+    pub trait Config: frame_system::Config + CreateInherent<Call<Self>> {
         /// The overarching runtime event type.
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
         // A type representing the weights required by the dispatchables of this pallet.
@@ -54,6 +69,96 @@ pub mod pallet {
 
         /// Overarching hold reason.
         type RuntimeHoldReason: From<HoldReason>;
+
+        /// Origin allowed to authorize verifier entities.
+        type VerifierAuthorityOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// Origin allowed to declare any issuer key compromised, besides the key itself.
+        type KeyCompromiseOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// Source of the timestamp recorded with border crossings.
+        type UnixTime: UnixTime;
+
+        /// Maximum number of border crossings kept in the log of a single proof.
+        #[pallet::constant]
+        type MaxCrossingRecords: Get<u32>;
+
+        /// Public key of a document holder that an issuer can bind to a proof.
+        type HolderKey: Parameter + MaxEncodedLen;
+
+        /// Signature a holder produces over a presentation challenge.
+        type HolderSignature: Verify<Signer: IdentifyAccount<AccountId = Self::HolderKey>> + Parameter;
+
+        /// Number of blocks a presentation challenge stays open.
+        #[pallet::constant]
+        type ChallengeTtl: Get<BlockNumberFor<Self>>;
+
+        /// Number of blocks after the block of a commitment it can be revealed in. Stale
+        /// commitments are removed in `on_idle`.
+        #[pallet::constant]
+        type CommitmentTtl: Get<BlockNumberFor<Self>>;
+
+        /// Maximum number of component proofs a composite proof can reference.
+        #[pallet::constant]
+        type MaxComponents: Get<u32>;
+
+        /// Maximum length of the signed proof bytes, e.g. 64 for ed25519 or 512 for RSA-4096
+        /// signatures.
+        #[pallet::constant]
+        type MaxProofLength: Get<u32>;
+
+        /// Key of a proof in the registry. Stored with the `Identity` hasher, so it has to be the
+        /// output of a cryptographic hash.
+        type ProofKey: Parameter + MaxEncodedLen + Copy;
+
+        /// Hashing of the proof bytes into their registry key.
+        type ProofHashing: HashT<Output = Self::ProofKey>;
+
+        /// Maximum number of batch leaves that can be revoked in one call.
+        #[pallet::constant]
+        type MaxLeavesPerRevocation: Get<u32>;
+
+        /// Number of entries of the revocation status list of each issuer.
+        #[pallet::constant]
+        type StatusListLength: Get<u32>;
+
+        /// Maximum number of status list entries that can be updated in one call.
+        #[pallet::constant]
+        type MaxStatusUpdates: Get<u32>;
+
+        /// Priority added to valid issuance transactions by
+        /// [`ValidateProofIssuance`](crate::extension::ValidateProofIssuance).
+        #[pallet::constant]
+        type IssuancePriority: Get<TransactionPriority>;
+
+        /// Number of fee-less transactions an active entity can submit to this pallet per era,
+        /// see [`ChargeIssuanceQuota`](crate::extension::ChargeIssuanceQuota).
+        #[pallet::constant]
+        type IssuanceQuota: Get<u32>;
+
+        /// Number of blocks of a quota era.
+        #[pallet::constant]
+        type QuotaEraLength: Get<BlockNumberFor<Self>>;
+
+        /// Maximum number of expired proofs pruned in one block.
+        #[pallet::constant]
+        type MaxPrunePerBlock: Get<u32>;
+
+        /// Priority of the unsigned transactions pruning expired proofs.
+        #[pallet::constant]
+        type PruneUnsignedPriority: Get<TransactionPriority>;
+
+        /// Creates holder keys and signatures for benchmarking.
+        #[cfg(feature = "runtime-benchmarks")]
+        type BenchmarkHelper: BenchmarkHelper<Self::HolderKey, Self::HolderSignature>;
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    pub trait BenchmarkHelper<HolderKey, HolderSignature> {
+        /// Create a holder key that `sign_presentation` can sign with.
+        fn create_holder_key() -> HolderKey;
+        /// Sign `message` with the key returned by `create_holder_key`.
+        fn sign_presentation(holder: &HolderKey, message: &[u8]) -> HolderSignature;
     }
 
     #[pallet::composite_enum]
@@ -63,34 +168,396 @@ pub mod pallet {
         WhitelistEntity,
     }
 
+    /// Standing of a whitelisted entity.
+    #[derive(
+        Encode, Decode, Clone, Copy, PartialEq, Eq, TypeInfo, MaxEncodedLen, RuntimeDebug,
+    )]
+    pub enum EntityState {
+        /// Can store proofs.
+        Active,
+        /// Cannot store new proofs. The proofs it already stored stay valid, unless its key was
+        /// declared compromised when they were stored.
+        Suspended,
+    }
+
+    /// Registration record of a whitelisted entity.
+    #[derive(Encode, Decode, Clone, PartialEq, Eq, TypeInfo, MaxEncodedLen, RuntimeDebug)]
+    pub struct EntityStatus<Balance> {
+        pub state: EntityState,
+        /// Amount held under `HoldReason::WhitelistEntity` for the registration.
+        pub held: Balance,
+        /// Block of the registration. `None` for entities registered before it was recorded.
+        pub registered_at: Option<U256>,
+    }
+
+    /// Number of status list entries stored together in one chunk.
+    pub const STATUS_CHUNK_BITS: u32 = 256;
+
+    /// A chunk of a revocation status list, one bit per entry, set when revoked.
+    pub type StatusChunk = [u8; 32];
+
+    /// Number of registry entries the offchain worker looks at per block for expired proofs.
+    pub const PRUNE_SCAN_LIMIT: u32 = 1_000;
+
+    /// Offchain storage key of the registry entry the offchain worker continues scanning from.
+    const PRUNE_CURSOR_KEY: &[u8] = b"probo::prune-cursor";
+
+    /// Upper bound of the page size of [`Pallet::proofs_by_issuer`].
+    pub const MAX_PROOFS_PER_PAGE: u32 = 1_000;
+
+    /// The signed proof bytes.
+    pub type ProofBytes<T> = BoundedVec<u8, <T as Config>::MaxProofLength>;
+
     #[derive(Encode, Decode, TypeInfo, MaxEncodedLen, RuntimeDebug)]
     #[scale_info(skip_type_params(T))]
     pub struct ProofMeta<T: Config> {
         pub issuer: T::AccountId,
         pub expiry_block: U256,
+        /// Remaining number of times the proof can be consumed by a verifier.
+        /// `None` means the proof can be used without limit.
+        pub remaining_uses: Option<u32>,
+        /// Key of the holder the proof was issued to. When set, the holder has to answer a
+        /// challenge with this key to present the proof.
+        pub holder: Option<T::HolderKey>,
+        /// Merkle root over the individually salted attributes of the document, allowing the
+        /// holder to disclose only some of them. See [`merkle`](crate::merkle).
+        pub attribute_root: Option<H256>,
+    }
+
+    /// A nonce a verifier has asked the holder of a proof to sign.
+    #[derive(Encode, Decode, TypeInfo, MaxEncodedLen, RuntimeDebug)]
+    #[scale_info(skip_type_params(T))]
+    pub struct PresentationChallenge<T: Config> {
+        pub proof_key: T::ProofKey,
+        pub verifier: T::AccountId,
+        pub expiry_block: U256,
+    }
+
+    /// Identifier of the checkpoint where a border crossing was recorded.
+    pub type CheckpointCode = BoundedVec<u8, ConstU32<16>>;
+
+    #[derive(
+        Encode, Decode, DecodeWithMemTracking, Clone, Copy, PartialEq, Eq, TypeInfo, MaxEncodedLen,
+        RuntimeDebug,
+    )]
+    pub enum CrossingDirection {
+        Entry,
+        Exit,
+    }
+
+    /// A single border crossing made with a proof. It does not hold any data about the holder.
+    #[derive(Encode, Decode, Clone, PartialEq, Eq, TypeInfo, MaxEncodedLen, RuntimeDebug)]
+    pub struct CrossingRecord {
+        pub direction: CrossingDirection,
+        pub checkpoint: CheckpointCode,
+        /// Milliseconds since the unix epoch.
+        pub timestamp: u64,
+    }
+
+    /// Summary of the crossing log of a proof, as returned by the runtime API.
+    #[derive(Encode, Decode, Clone, PartialEq, Eq, TypeInfo, RuntimeDebug)]
+    pub struct CrossingSummary {
+        pub entries: u32,
+        pub exits: u32,
+        pub last_crossing: Option<CrossingRecord>,
+        /// The holder has entered and not left again although the proof has expired.
+        pub overstayed: bool,
+    }
+
+    /// A page of the proofs stored by an issuer, as returned by the runtime API.
+    #[derive(Encode, Decode, Clone, PartialEq, Eq, TypeInfo, RuntimeDebug)]
+    pub struct ProofPage<ProofKey> {
+        /// Keys of the proofs, in storage order.
+        pub proofs: Vec<ProofKey>,
+        /// Cursor to pass to get the next page, `None` on the last page.
+        pub next_cursor: Option<ProofKey>,
+        /// Number of proofs the issuer has stored in total.
+        pub total: u32,
+    }
+
+    /// Number of registered entities, as stored and returned by the runtime API.
+    #[derive(
+        Encode, Decode, Clone, Copy, Default, PartialEq, Eq, TypeInfo, MaxEncodedLen, RuntimeDebug,
+    )]
+    pub struct EntityCounts {
+        /// Whitelisted entities that can store proofs.
+        pub active_issuers: u32,
+        /// Whitelisted entities whose key was declared compromised.
+        pub suspended_issuers: u32,
+        /// Entities authorized to consume proofs.
+        pub verifiers: u32,
+    }
+
+    /// Why a proof cannot be relied on.
+    #[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, TypeInfo, RuntimeDebug)]
+    pub enum InvalidReason {
+        NotFound,
+        Expired,
+        Exhausted,
+        Revoked,
+        /// The proof was stored while the issuer's key was declared compromised.
+        KeyCompromised,
+    }
+
+    /// Status of a proof, as returned by the runtime API.
+    #[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, TypeInfo, RuntimeDebug)]
+    pub enum ProofStatus {
+        Valid,
+        Invalid(InvalidReason),
+        /// The composite proof itself is valid, but the component at `index` is not.
+        InvalidComponent { index: u32, reason: InvalidReason },
+    }
+
+    /// A batch of documents anchored under a single Merkle root.
+    #[derive(Encode, Decode, TypeInfo, MaxEncodedLen, RuntimeDebug)]
+    #[scale_info(skip_type_params(T))]
+    pub struct BatchMeta<T: Config> {
+        pub issuer: T::AccountId,
+        pub expiry_block: U256,
+        /// Block of the anchoring, checked against the compromise window of the issuer key.
+        pub anchored_at: U256,
+    }
+
+    /// Blocks in which the proofs of a compromised key can no longer be trusted, bounds included.
+    #[derive(
+        Encode, Decode, Clone, Copy, PartialEq, Eq, TypeInfo, MaxEncodedLen, RuntimeDebug,
+    )]
+    pub struct CompromiseWindow {
+        /// Block from which the key is suspected to be compromised.
+        pub since: U256,
+        /// Block of the declaration, the key cannot store proofs after it.
+        pub until: U256,
+    }
+
+    impl CompromiseWindow {
+        /// Whether something signed with the key in `block` can no longer be trusted.
+        pub fn contains(&self, block: U256) -> bool {
+            self.since <= block && block <= self.until
+        }
+    }
+
+    /// Fee-less transactions an entity has submitted in an era.
+    #[derive(
+        Encode, Decode, Clone, Copy, PartialEq, Eq, Default, TypeInfo, MaxEncodedLen, RuntimeDebug,
+    )]
+    pub struct QuotaUsage {
+        pub era: u32,
+        pub used: u32,
     }
 
+    /// Proofs by the hash of their bytes. The bytes themselves are only kept off-chain, in the
+    /// `ProofStored` event.
+    #[pallet::storage]
+    pub type Proofs<T: Config> = StorageMap<_, Identity, T::ProofKey, ProofMeta<T>>;
+
+    /// Whether proofs keyed by their bytes are still being moved to `Proofs`.
+    /// See [`migrations::v1`](crate::migrations::v1).
+    #[pallet::storage]
+    pub type RekeyOngoing<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+    #[pallet::storage]
+    pub type WhitelistEntity<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, EntityStatus<BalanceOf<T>>>;
+
+    /// Entities authorized to consume proofs, e.g. border checkpoints or exam boards.
+    #[pallet::storage]
+    pub type VerifierEntity<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, bool>;
+
+    /// Number of issuers by state and of verifiers, kept up to date so they are not counted by
+    /// reading every registration.
+    #[pallet::storage]
+    pub type EntityCount<T: Config> = StorageValue<_, EntityCounts, ValueQuery>;
+
+    /// Open presentation challenges by nonce. A challenge is removed once answered, so a holder
+    /// signature cannot be replayed, or once it has expired unanswered.
+    #[pallet::storage]
+    pub type Challenges<T: Config> = StorageMap<_, Identity, [u8; 32], PresentationChallenge<T>>;
+
+    /// Number of challenges issued so far, used to derive unique nonces.
+    #[pallet::storage]
+    pub type ChallengeCount<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+    /// Open challenges by the last block they can be answered in, so expired ones can be removed.
+    #[pallet::storage]
+    pub type ChallengeExpiry<T: Config> =
+        StorageDoubleMap<_, Twox64Concat, U256, Identity, [u8; 32], ()>;
+
+    /// Next expiry block whose challenges have to be removed.
+    #[pallet::storage]
+    pub type ChallengeGcCursor<T: Config> = StorageValue<_, U256, ValueQuery>;
+
+    /// Component proofs referenced by a composite proof, e.g. the passport and the visa whose
+    /// combination the composite attests. The composite itself is stored in `Proofs`.
+    #[pallet::storage]
+    pub type CompositeComponents<T: Config> =
+        StorageMap<_, Identity, T::ProofKey, BoundedVec<T::ProofKey, T::MaxComponents>>;
+
+    /// Anchored batches by Merkle root. The number of documents in a batch is not recorded, so
+    /// the issuance volume cannot be read from the chain.
+    #[pallet::storage]
+    pub type Batches<T: Config> = StorageMap<_, Blake2_128Concat, H256, BatchMeta<T>>;
+
+    /// Revoked documents of anchored batches, by batch root and document leaf.
+    #[pallet::storage]
+    pub type RevokedLeaves<T: Config> =
+        StorageDoubleMap<_, Blake2_128Concat, H256, Blake2_128Concat, H256, ()>;
+
+    /// Revocation status lists, one per issuer, in the spirit of the W3C Bitstring Status List.
+    /// Each chunk holds `STATUS_CHUNK_BITS` entries, chunks without revoked entries are not
+    /// stored.
+    #[pallet::storage]
+    pub type StatusListChunks<T: Config> =
+        StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Twox64Concat, u32, StatusChunk, ValueQuery>;
+
+    /// Entry of a proof in the status list of its issuer.
+    #[pallet::storage]
+    pub type StatusIndexOf<T: Config> = StorageMap<_, Identity, T::ProofKey, u32>;
+
+    /// Proof holding each entry of the status lists, so that revoking an entry revokes a single
+    /// proof. The entry is released when the proof expires or is pruned.
     #[pallet::storage]
-    pub type IssuanceProof<T: Config> =
-        StorageMap<_, Blake2_128Concat, BoundedVec<u8, ConstU32<512>>, ProofMeta<T>>;
+    pub type StatusIndexOwner<T: Config> =
+        StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Twox64Concat, u32, T::ProofKey>;
 
+    /// Proofs with a status list entry by expiry block, so the entry can be released once the
+    /// proof has expired.
     #[pallet::storage]
-    pub type WhitelistEntity<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, bool>;
+    pub type StatusExpiry<T: Config> =
+        StorageDoubleMap<_, Twox64Concat, U256, Identity, T::ProofKey, ()>;
+
+    /// Next expiry block whose status list entries have to be released.
+    #[pallet::storage]
+    pub type StatusGcCursor<T: Config> = StorageValue<_, U256, ValueQuery>;
+
+    /// Block in which each proof was stored. Proofs stored before it was recorded have no entry
+    /// and count as stored at block zero.
+    #[pallet::storage]
+    pub type ProofIssuedAt<T: Config> = StorageMap<_, Identity, T::ProofKey, U256>;
+
+    /// Issuer keys declared compromised. The proofs they stored within the window are evaluated
+    /// as revoked when they are read, so the declaration does not touch them.
+    #[pallet::storage]
+    pub type CompromisedKeys<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, CompromiseWindow>;
+
+    /// Salted commitments to proofs and batch roots, with the block they were made in. A proof or
+    /// batch can only be stored by revealing a commitment made in an earlier block, so seeing it
+    /// in the transaction pool is too late to claim it first.
+    #[pallet::storage]
+    pub type ProofCommitments<T: Config> =
+        StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Identity, H256, U256>;
+
+    /// Commitments by the last block they can be revealed in, so stale ones can be removed.
+    #[pallet::storage]
+    pub type CommitmentExpiry<T: Config> =
+        StorageDoubleMap<_, Twox64Concat, U256, Blake2_128Concat, (T::AccountId, H256), ()>;
+
+    /// Next expiry block whose commitments have to be removed.
+    #[pallet::storage]
+    pub type CommitmentGcCursor<T: Config> = StorageValue<_, U256, ValueQuery>;
+
+    /// Quota used by each entity. An entry from a past era counts as unused, so the quota is
+    /// refreshed without touching every entity at the start of an era.
+    #[pallet::storage]
+    pub type QuotaUsed<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, QuotaUsage>;
+
+    /// Last block expired proofs were pruned in, at most one pruning transaction is accepted per
+    /// block.
+    #[pallet::storage]
+    pub type LastPruneBlock<T: Config> = StorageValue<_, U256>;
+
+    /// Keys of the proofs stored by each issuer. Proofs not rekeyed yet are only listed once
+    /// they have been moved to `Proofs`.
+    #[pallet::storage]
+    pub type ProofsByIssuer<T: Config> =
+        StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Identity, T::ProofKey, ()>;
+
+    /// Number of entries of each issuer in `ProofsByIssuer`.
+    #[pallet::storage]
+    pub type ProofCountByIssuer<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+    /// Border entries and exits made with a proof, oldest first.
+    #[pallet::storage]
+    pub type CrossingLog<T: Config> = StorageMap<
+        _,
+        Identity,
+        T::ProofKey,
+        BoundedVec<CrossingRecord, T::MaxCrossingRecords>,
+        ValueQuery,
+    >;
 
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event<T: Config> {
         /// A user has successfully set a new value.
         ProofStored {
-            /// The new value set.
-            proof: BoundedVec<u8, ConstU32<512>>,
+            /// Key of the proof in the registry
+            proof_key: T::ProofKey,
+            /// The new value set. Only kept in this event, not in storage.
+            proof: ProofBytes<T>,
             /// The document issuer's public address
             issuer: T::AccountId,
             /// Expiry Block Number
             expiry_block: U256,
+            /// Number of times the proof can be used, `None` for unlimited
+            max_uses: Option<u32>,
+            /// Entry of the proof in the status list of the issuer, if any
+            status_index: Option<u32>,
         },
         /// When register as whitelist entity is successful
         WhitelistEntityRegistered { entity: T::AccountId, hold_balance: BalanceOf<T> },
+        /// An entity has been authorized to consume proofs
+        VerifierAuthorized { verifier: T::AccountId },
+        /// A verifier has used a proof
+        ProofConsumed {
+            proof_key: T::ProofKey,
+            /// The verifier that consumed the proof
+            verifier: T::AccountId,
+            /// Uses left after this one, `None` for unlimited proofs
+            remaining_uses: Option<u32>,
+        },
+        /// A usage-limited proof has no uses left
+        ProofExhausted { proof_key: T::ProofKey, verifier: T::AccountId },
+        /// A border crossing has been recorded against a proof
+        CrossingRecorded {
+            proof_key: T::ProofKey,
+            direction: CrossingDirection,
+            checkpoint: CheckpointCode,
+        },
+        /// A proof attesting the combination of other proofs has been stored
+        CompositeProofStored { proof_key: T::ProofKey, issuer: T::AccountId, components: u32 },
+        /// A verifier has asked the holder of a proof to sign a nonce
+        ChallengeIssued { nonce: [u8; 32], verifier: T::AccountId },
+        /// The holder has answered a challenge with the key bound to the proof
+        PresentationVerified { nonce: [u8; 32], verifier: T::AccountId },
+        /// All proofs keyed by their bytes have been moved to `Proofs`
+        ProofsRekeyed,
+        /// An issuer has anchored a batch of documents under a Merkle root
+        BatchAnchored { root: H256, issuer: T::AccountId, expiry_block: U256 },
+        /// Documents of a batch have been revoked
+        BatchLeavesRevoked {
+            root: H256,
+            /// Number of documents newly revoked
+            revoked: u32,
+        },
+        /// An issuer has updated entries of its revocation status list
+        RevocationStatusUpdated {
+            issuer: T::AccountId,
+            /// Number of entries set
+            revoked: u32,
+            /// Number of entries cleared
+            reinstated: u32,
+            /// The entries set or cleared, in the order of the update
+            indexes: BoundedVec<u32, T::MaxStatusUpdates>,
+        },
+        /// An issuer has committed to a proof or batch it will store in a later block
+        ProofCommitted { issuer: T::AccountId, commitment: H256 },
+        /// Expired proofs have been removed from the registry
+        ProofsPruned { proof_keys: BoundedVec<T::ProofKey, T::MaxPrunePerBlock> },
+        /// An issuer key has been declared compromised. Every proof it stored from `since` to
+        /// `until` is revoked and the key is suspended.
+        KeyCompromiseDeclared { key: T::AccountId, since: U256, until: U256 },
     }
 
     #[pallet::error]
@@ -103,6 +570,95 @@ pub mod pallet {
         NotWhitelistEntity,
         /// Already registered as whitelisted entity
         IsAlreadyWhitelisted,
+        /// Proof is not stored on chain
+        ProofNotFound,
+        /// Proof has passed its expiry block
+        ProofExpired,
+        /// Proof has no uses left
+        ProofExhausted,
+        /// Usage limit must be at least one
+        InvalidUsageLimit,
+        /// Not authorized verifier entity
+        NotVerifierEntity,
+        /// Already authorized as verifier entity
+        IsAlreadyVerifier,
+        /// The crossing log of the proof has reached `MaxCrossingRecords`
+        CrossingLogFull,
+        /// No holder key is bound to the proof
+        NoHolderKeyBound,
+        /// Challenge does not exist or has already been answered
+        ChallengeNotFound,
+        /// Challenge has passed its expiry block
+        ChallengeExpired,
+        /// Challenge was issued by another verifier
+        NotChallengeVerifier,
+        /// Signature does not match the holder key bound to the proof
+        InvalidHolderSignature,
+        /// A composite proof needs at least two distinct components
+        TooFewComponents,
+        /// A component proof is listed more than once
+        DuplicateComponent,
+        /// A component proof is not valid
+        InvalidComponent,
+        /// A composite proof cannot be a component of another composite proof
+        NestedComposite,
+        /// A batch with this root has already been anchored
+        BatchAlreadyAnchored,
+        /// No batch has been anchored with this root
+        BatchNotFound,
+        /// Only the issuer of a batch can revoke its documents
+        NotBatchIssuer,
+        /// The index is beyond the length of the status list
+        StatusIndexOutOfRange,
+        /// The proof has been revoked by its issuer
+        ProofRevoked,
+        /// The suspected start of a key compromise lies in the future
+        CompromiseInFuture,
+        /// The issuer has already made this commitment
+        CommitmentAlreadyExist,
+        /// The issuer has not committed to the proof or batch with this salt
+        CommitmentNotFound,
+        /// The commitment was made in the current block, it can only be revealed in a later one
+        CommitmentNotMatured,
+        /// The expiry block would be beyond the largest block number
+        InvalidExpiration,
+        /// The status list entry belongs to another proof that has not expired, or is revoked
+        StatusIndexTaken,
+        /// The commitment is older than the commitment TTL and can no longer be revealed
+        CommitmentExpired,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_idle(_n: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+            let mut used = migrations::v1::step::<T>(remaining_weight);
+            used.saturating_accrue(Self::release_expired_statuses(
+                remaining_weight.saturating_sub(used),
+            ));
+            used.saturating_accrue(Self::remove_stale_commitments(
+                remaining_weight.saturating_sub(used),
+            ));
+            used.saturating_add(Self::remove_expired_challenges(
+                remaining_weight.saturating_sub(used),
+            ))
+        }
+
+        /// Look for expired proofs and submit an unsigned transaction pruning them.
+        fn offchain_worker(_n: BlockNumberFor<T>) {
+            let proof_keys = Self::scan_expired_proofs();
+            if !proof_keys.is_empty() {
+                let call = Call::prune_expired { proof_keys };
+                // another node may already have submitted the same pruning
+                let _ = SubmitTransaction::<T, Call<T>>::submit_transaction(
+                    T::create_inherent(call.into()),
+                );
+            }
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn try_state(_n: BlockNumberFor<T>) -> Result<(), sp_runtime::TryRuntimeError> {
+            Self::do_try_state()
+        }
     }
 
     #[pallet::call]
@@ -120,19 +676,217 @@ pub mod pallet {
 
         /// Store proof on chain
         #[pallet::call_index(0)]
-        #[pallet::weight(T::WeightInfo::store_proof())]
+        #[pallet::weight(T::WeightInfo::store_proof(proof.len() as u32))]
         pub fn store_proof(
             origin: OriginFor<T>,
-            proof: BoundedVec<u8, ConstU32<512>>,
+            proof: ProofBytes<T>,
             expiration: U256,
+            max_uses: Option<u32>,
+            holder: Option<T::HolderKey>,
+            attribute_root: Option<H256>,
+            status_index: Option<u32>,
+            salt: [u8; 32],
         ) -> DispatchResult {
             // Check that the extrinsic was signed and get the signer.
             let who = ensure_signed(origin)?;
-            Self::do_store_proof(who, proof, expiration)?;
+            Self::reveal_commitment(&who, &Self::proof_key(&proof), salt)?;
+            Self::do_store_proof(who, proof, expiration, max_uses, holder, attribute_root, status_index)?;
             // Return a successful `DispatchResult`
             Ok(())
         }
 
+        /// Authorize an entity to consume proofs
+        #[pallet::call_index(2)]
+        #[pallet::weight(T::WeightInfo::authorize_verifier())]
+        pub fn authorize_verifier(origin: OriginFor<T>, verifier: T::AccountId) -> DispatchResult {
+            T::VerifierAuthorityOrigin::ensure_origin(origin)?;
+            Self::do_authorize_verifier(verifier)?;
+
+            Ok(())
+        }
+
+        /// Use a proof once, e.g. when a single-entry visa is presented at the border
+        #[pallet::call_index(3)]
+        #[pallet::weight(T::WeightInfo::consume_proof(proof.len() as u32))]
+        pub fn consume_proof(origin: OriginFor<T>, proof: ProofBytes<T>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::do_consume_proof(who, proof)?;
+
+            Ok(())
+        }
+
+        /// Record a border entry or exit made with a proof
+        #[pallet::call_index(4)]
+        #[pallet::weight(T::WeightInfo::record_crossing(proof.len() as u32))]
+        pub fn record_crossing(
+            origin: OriginFor<T>,
+            proof: ProofBytes<T>,
+            direction: CrossingDirection,
+            checkpoint: CheckpointCode,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::do_record_crossing(who, proof, direction, checkpoint)?;
+
+            Ok(())
+        }
+
+        /// Ask the holder of a proof to sign a fresh nonce
+        #[pallet::call_index(5)]
+        #[pallet::weight(T::WeightInfo::issue_challenge(proof.len() as u32))]
+        pub fn issue_challenge(origin: OriginFor<T>, proof: ProofBytes<T>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::do_issue_challenge(who, proof)?;
+
+            Ok(())
+        }
+
+        /// Check the holder's answer to a challenge and close the challenge
+        #[pallet::call_index(6)]
+        #[pallet::weight(T::WeightInfo::complete_challenge())]
+        pub fn complete_challenge(
+            origin: OriginFor<T>,
+            nonce: [u8; 32],
+            signature: T::HolderSignature,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::do_complete_challenge(who, nonce, signature)?;
+
+            Ok(())
+        }
+
+        /// Store a proof that is only valid while all the referenced component proofs are valid
+        #[pallet::call_index(7)]
+        #[pallet::weight(T::WeightInfo::store_composite_proof(
+            components.len() as u32,
+            components.iter().map(|component| component.len()).fold(proof.len(), core::cmp::max) as u32,
+        ))]
+        pub fn store_composite_proof(
+            origin: OriginFor<T>,
+            proof: ProofBytes<T>,
+            expiration: U256,
+            components: BoundedVec<ProofBytes<T>, T::MaxComponents>,
+            salt: [u8; 32],
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::reveal_commitment(&who, &Self::proof_key(&proof), salt)?;
+            Self::do_store_composite_proof(who, proof, expiration, components)?;
+
+            Ok(())
+        }
+
+        /// Anchor a batch of documents by the root of the Merkle tree over their leaves
+        #[pallet::call_index(8)]
+        #[pallet::weight(T::WeightInfo::anchor_batch())]
+        pub fn anchor_batch(
+            origin: OriginFor<T>,
+            root: H256,
+            expiration: U256,
+            salt: [u8; 32],
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::reveal_commitment(&who, &root, salt)?;
+            Self::do_anchor_batch(who, root, expiration)?;
+
+            Ok(())
+        }
+
+        /// Revoke or reinstate entries of the caller's revocation status list
+        #[pallet::call_index(10)]
+        #[pallet::weight(T::WeightInfo::update_revocation_status(updates.len() as u32))]
+        pub fn update_revocation_status(
+            origin: OriginFor<T>,
+            updates: BoundedVec<(u32, bool), T::MaxStatusUpdates>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::do_update_revocation_status(who, updates)?;
+
+            Ok(())
+        }
+
+        /// Commit to a proof or batch root before storing it. `commitment` is
+        /// [`Pallet::commitment`] of the caller, the proof key or batch root and a random salt.
+        #[pallet::call_index(12)]
+        #[pallet::weight(T::WeightInfo::commit_proof())]
+        pub fn commit_proof(origin: OriginFor<T>, commitment: H256) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::do_commit_proof(who, commitment)?;
+
+            Ok(())
+        }
+
+        /// Remove expired proofs from the registry. Submitted unsigned by the offchain worker.
+        #[pallet::call_index(13)]
+        #[pallet::weight(T::WeightInfo::prune_expired(proof_keys.len() as u32))]
+        pub fn prune_expired(
+            origin: OriginFor<T>,
+            proof_keys: BoundedVec<T::ProofKey, T::MaxPrunePerBlock>,
+        ) -> DispatchResult {
+            ensure_none(origin)?;
+            Self::do_prune_expired(proof_keys)?;
+
+            Ok(())
+        }
+
+        /// Declare an issuer key compromised since the given block, revoking every proof it
+        /// stored since then. Can be called by the key itself or by `KeyCompromiseOrigin`.
+        #[pallet::call_index(11)]
+        #[pallet::weight(T::WeightInfo::declare_key_compromise())]
+        pub fn declare_key_compromise(
+            origin: OriginFor<T>,
+            key: T::AccountId,
+            since: U256,
+        ) -> DispatchResult {
+            if let Err(origin) = T::KeyCompromiseOrigin::try_origin(origin) {
+                ensure!(ensure_signed(origin)? == key, DispatchError::BadOrigin);
+            }
+            Self::do_declare_key_compromise(key, since)?;
+
+            Ok(())
+        }
+
+        /// Revoke individual documents of a batch by their leaf hash
+        #[pallet::call_index(9)]
+        #[pallet::weight(T::WeightInfo::revoke_batch_leaves(leaves.len() as u32))]
+        pub fn revoke_batch_leaves(
+            origin: OriginFor<T>,
+            root: H256,
+            leaves: BoundedVec<H256, T::MaxLeavesPerRevocation>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::do_revoke_batch_leaves(who, root, leaves)?;
+
+            Ok(())
+        }
+
+    }
+
+    #[pallet::validate_unsigned]
+    impl<T: Config> ValidateUnsigned for Pallet<T> {
+        type Call = Call<T>;
+
+        /// Only pruning transactions are accepted unsigned, and only when every proof they name
+        /// can be pruned, so they can't be used to flood the pool.
+        fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+            let Call::prune_expired { proof_keys } = call else {
+                return InvalidTransaction::Call.into();
+            };
+            ensure!(!proof_keys.is_empty(), InvalidTransaction::Call);
+
+            let now = Self::current_block();
+            ensure!(LastPruneBlock::<T>::get() != Some(now), InvalidTransaction::Stale);
+            for proof_key in proof_keys {
+                let prunable = Proofs::<T>::get(proof_key)
+                    .is_some_and(|meta| Self::is_prunable(proof_key, &meta));
+                ensure!(prunable, InvalidTransaction::Stale);
+            }
+
+            ValidTransaction::with_tag_prefix("ProofPruning")
+                .priority(T::PruneUnsignedPriority::get())
+                .and_provides(now)
+                .longevity(5)
+                .propagate(true)
+                .build()
+        }
     }
 
     impl<T: Config> Pallet<T> {
@@ -154,7 +908,15 @@ pub mod pallet {
             )?;
 
             // register whitelist entity
-            WhitelistEntity::<T>::insert(&issuer, true);
+            WhitelistEntity::<T>::insert(
+                &issuer,
+                EntityStatus {
+                    state: EntityState::Active,
+                    held: hold_balance.into(),
+                    registered_at: Some(Self::current_block()),
+                },
+            );
+            EntityCount::<T>::mutate(|counts| counts.active_issuers.saturating_inc());
 
             // Emit an event.
             Self::deposit_event(Event::WhitelistEntityRegistered { entity: issuer, hold_balance: hold_balance.into() });
@@ -172,47 +934,1084 @@ pub mod pallet {
 
         pub fn do_store_proof(
             issuer: T::AccountId,
-            proof: BoundedVec<u8, ConstU32<512>>,
+            proof: ProofBytes<T>,
             expiration: U256,
+            max_uses: Option<u32>,
+            holder: Option<T::HolderKey>,
+            attribute_root: Option<H256>,
+            status_index: Option<u32>,
         ) -> DispatchResult {
             // check proof already exists
             ensure!(
-                !IssuanceProof::<T>::contains_key(&proof),
+                Self::get_proof(&proof).is_none(),
                 Error::<T>::ProofAlreadyExist
             );
             // check is whitelisted entity
             Self::is_whitelist_entity(&issuer)?;
+            // a proof that can never be used makes no sense
+            ensure!(max_uses != Some(0), Error::<T>::InvalidUsageLimit);
+            if let Some(index) = status_index {
+                ensure!(index < T::StatusListLength::get(), Error::<T>::StatusIndexOutOfRange);
+                // a revoked entry without owner would revoke the proof as soon as it is stored
+                ensure!(
+                    !StatusIndexOwner::<T>::contains_key(&issuer, index)
+                        && !Self::status(&issuer, index),
+                    Error::<T>::StatusIndexTaken
+                );
+            }
 
-            let current_block = frame_system::Pallet::<T>::block_number().into();
-            let expiry_block = current_block + expiration;
+            let current_block = Self::current_block();
+            let expiry_block =
+                current_block.checked_add(expiration).ok_or(Error::<T>::InvalidExpiration)?;
             let proof_meta = ProofMeta {
                 issuer: issuer.clone(),
                 expiry_block,
+                remaining_uses: max_uses,
+                holder,
+                attribute_root,
             };
-            IssuanceProof::<T>::insert(&proof, proof_meta);
+            let proof_key = Self::proof_key(&proof);
+            Self::insert_proof(proof_key, proof_meta);
+            ProofIssuedAt::<T>::insert(proof_key, current_block);
+            if let Some(index) = status_index {
+                // nothing expires before this proof, the collection can start from here
+                if StatusExpiry::<T>::iter_keys().next().is_none() {
+                    StatusGcCursor::<T>::put(current_block);
+                }
+                StatusIndexOf::<T>::insert(proof_key, index);
+                StatusIndexOwner::<T>::insert(&issuer, index, proof_key);
+                StatusExpiry::<T>::insert(expiry_block, proof_key, ());
+            }
 
             // Emit an event.
             Self::deposit_event(Event::ProofStored {
+                proof_key,
                 proof,
-                issuer,
+                issuer: issuer.clone(),
                 expiry_block,
+                max_uses,
+                status_index,
             });
+            Self::index_events(&[(proof_key, ProofEvent::Stored { issuer })]);
             Ok(())
         }
 
-        fn is_whitelist_entity(issuer: &T::AccountId) -> DispatchResult {
+        pub fn do_store_composite_proof(
+            issuer: T::AccountId,
+            proof: ProofBytes<T>,
+            expiration: U256,
+            components: BoundedVec<ProofBytes<T>, T::MaxComponents>,
+        ) -> DispatchResult {
+            ensure!(components.len() >= 2, Error::<T>::TooFewComponents);
+            let mut component_keys = BoundedVec::<T::ProofKey, T::MaxComponents>::new();
+            for component in components.iter() {
+                let component_key = Self::ensure_rekeyed(component);
+                ensure!(!component_keys.contains(&component_key), Error::<T>::DuplicateComponent);
+                ensure!(
+                    !CompositeComponents::<T>::contains_key(component_key),
+                    Error::<T>::NestedComposite
+                );
+                ensure!(
+                    Self::proof_status(component) == ProofStatus::Valid,
+                    Error::<T>::InvalidComponent
+                );
+                component_keys
+                    .try_push(component_key)
+                    .map_err(|_| Error::<T>::TooFewComponents)?;
+            }
+
+            let proof_key = Self::proof_key(&proof);
+            Self::do_store_proof(issuer.clone(), proof, expiration, None, None, None, None)?;
+            let count = component_keys.len() as u32;
+            CompositeComponents::<T>::insert(proof_key, component_keys);
+
+            Self::deposit_event(Event::CompositeProofStored { proof_key, issuer, components: count });
+            Ok(())
+        }
+
+        pub fn do_anchor_batch(issuer: T::AccountId, root: H256, expiration: U256) -> DispatchResult {
+            ensure!(!Batches::<T>::contains_key(root), Error::<T>::BatchAlreadyAnchored);
+            // check is whitelisted entity
+            Self::is_whitelist_entity(&issuer)?;
+
+            let anchored_at = Self::current_block();
+            let expiry_block =
+                anchored_at.checked_add(expiration).ok_or(Error::<T>::InvalidExpiration)?;
+            Batches::<T>::insert(root, BatchMeta { issuer: issuer.clone(), expiry_block, anchored_at });
+
+            Self::deposit_event(Event::BatchAnchored { root, issuer, expiry_block });
+            Ok(())
+        }
+
+        pub fn do_update_revocation_status(
+            issuer: T::AccountId,
+            updates: BoundedVec<(u32, bool), T::MaxStatusUpdates>,
+        ) -> DispatchResult {
+            // a suspended issuer can still revoke what it has issued
+            ensure!(WhitelistEntity::<T>::contains_key(&issuer), Error::<T>::NotWhitelistEntity);
+
+            let (mut revoked, mut reinstated) = (0u32, 0u32);
+            let mut changed = Vec::new();
+            let indexes = BoundedVec::truncate_from(updates.iter().map(|(index, _)| *index).collect());
+            for (index, revoke) in updates {
+                ensure!(index < T::StatusListLength::get(), Error::<T>::StatusIndexOutOfRange);
+                Self::set_status(&issuer, index, revoke);
+                let event = if revoke {
+                    revoked += 1;
+                    ProofEvent::Revoked
+                } else {
+                    reinstated += 1;
+                    ProofEvent::Reinstated
+                };
+                if let Some(proof_key) = StatusIndexOwner::<T>::get(&issuer, index) {
+                    changed.push((proof_key, event));
+                }
+            }
+
+            Self::deposit_event(Event::RevocationStatusUpdated { issuer, revoked, reinstated, indexes });
+            if !changed.is_empty() {
+                Self::index_events(&changed);
+            }
+            Ok(())
+        }
+
+        pub fn do_commit_proof(issuer: T::AccountId, commitment: H256) -> DispatchResult {
+            // check is whitelisted entity
+            Self::is_whitelist_entity(&issuer)?;
             ensure!(
-                WhitelistEntity::<T>::contains_key(issuer),
-                Error::<T>::NotWhitelistEntity
+                !ProofCommitments::<T>::contains_key(&issuer, commitment),
+                Error::<T>::CommitmentAlreadyExist
             );
-            if let Some(still_whitelisted) = WhitelistEntity::<T>::get(issuer) {
-                if !still_whitelisted {
-                    return Err(Error::<T>::NotWhitelistEntity.into());
+
+            let current_block = Self::current_block();
+            if CommitmentExpiry::<T>::iter_keys().next().is_none() {
+                CommitmentGcCursor::<T>::put(current_block);
+            }
+            ProofCommitments::<T>::insert(&issuer, commitment, current_block);
+            CommitmentExpiry::<T>::insert(
+                Self::commitment_expiry(current_block),
+                (issuer.clone(), commitment),
+                (),
+            );
+
+            Self::deposit_event(Event::ProofCommitted { issuer, commitment });
+            Ok(())
+        }
+
+        /// Commitment of `issuer` to a proof key or batch root. Binding the issuer means a
+        /// commitment copied from the chain cannot be revealed by anyone else.
+        pub fn commitment<K: Encode>(issuer: &T::AccountId, key: &K, salt: [u8; 32]) -> H256 {
+            (b"probo/commitment", issuer, key, salt).using_encoded(blake2_256).into()
+        }
+
+        /// Last block in which a commitment made in `committed_at` can be revealed.
+        pub fn commitment_expiry(committed_at: U256) -> U256 {
+            committed_at.saturating_add(T::CommitmentTtl::get().into())
+        }
+
+        /// Consume the commitment of `issuer` to `key`, which must be from an earlier block and
+        /// not older than the commitment TTL.
+        pub fn reveal_commitment<K: Encode>(
+            issuer: &T::AccountId,
+            key: &K,
+            salt: [u8; 32],
+        ) -> DispatchResult {
+            let commitment = Self::commitment(issuer, key, salt);
+            let committed_at = ProofCommitments::<T>::get(issuer, commitment)
+                .ok_or(Error::<T>::CommitmentNotFound)?;
+            let current_block = Self::current_block();
+            ensure!(committed_at < current_block, Error::<T>::CommitmentNotMatured);
+            let expiry_block = Self::commitment_expiry(committed_at);
+            ensure!(current_block <= expiry_block, Error::<T>::CommitmentExpired);
+
+            ProofCommitments::<T>::remove(issuer, commitment);
+            CommitmentExpiry::<T>::remove(expiry_block, (issuer.clone(), commitment));
+            Ok(())
+        }
+
+        /// Index of the current quota era.
+        pub fn current_era() -> u32 {
+            (frame_system::Pallet::<T>::block_number() / T::QuotaEraLength::get()).saturated_into()
+        }
+
+        /// Number of fee-less transactions `entity` can still submit in the current era. Zero for
+        /// accounts that are not active entities.
+        pub fn remaining_quota(entity: &T::AccountId) -> u32 {
+            if Self::is_whitelist_entity(entity).is_err() {
+                return 0;
+            }
+            let era = Self::current_era();
+            let used = QuotaUsed::<T>::get(entity)
+                .filter(|usage| usage.era == era)
+                .map_or(0, |usage| usage.used);
+            T::IssuanceQuota::get().saturating_sub(used)
+        }
+
+        /// Use one transaction of the quota of `entity` for the current era.
+        pub(crate) fn use_quota(entity: &T::AccountId) {
+            let era = Self::current_era();
+            QuotaUsed::<T>::mutate(entity, |usage| {
+                let usage = usage.get_or_insert_with(QuotaUsage::default);
+                if usage.era != era {
+                    *usage = QuotaUsage { era, used: 0 };
+                }
+                usage.used.saturating_inc();
+            });
+        }
+
+        pub fn do_prune_expired(
+            proof_keys: BoundedVec<T::ProofKey, T::MaxPrunePerBlock>,
+        ) -> DispatchResult {
+            LastPruneBlock::<T>::put(Self::current_block());
+
+            let mut pruned = Vec::new();
+            for proof_key in proof_keys {
+                let Some(meta) = Proofs::<T>::get(proof_key) else { continue };
+                if Self::is_prunable(&proof_key, &meta) {
+                    Self::remove_proof(proof_key, meta);
+                    pruned.push((proof_key, ProofEvent::Pruned));
+                }
+            }
+
+            let proof_keys =
+                BoundedVec::truncate_from(pruned.iter().map(|(proof_key, _)| *proof_key).collect());
+            Self::deposit_event(Event::ProofsPruned { proof_keys });
+            if !pruned.is_empty() {
+                Self::index_events(&pruned);
+            }
+            Ok(())
+        }
+
+        /// Whether a proof can be removed from the registry. It must have expired longer ago than
+        /// a presentation challenge stays open, and its holder must not still be in the country,
+        /// as leaving has to be recorded against it.
+        pub fn is_prunable(proof_key: &T::ProofKey, meta: &ProofMeta<T>) -> bool {
+            let open_until = meta.expiry_block.saturating_add(T::ChallengeTtl::get().into());
+            let inside = CrossingLog::<T>::get(proof_key)
+                .last()
+                .is_some_and(|record| record.direction == CrossingDirection::Entry);
+            Self::current_block() > open_until && !inside
+        }
+
+        /// Remove a proof and everything stored about it.
+        fn remove_proof(proof_key: T::ProofKey, meta: ProofMeta<T>) {
+            Proofs::<T>::remove(proof_key);
+            if ProofsByIssuer::<T>::take(&meta.issuer, proof_key).is_some() {
+                ProofCountByIssuer::<T>::mutate(&meta.issuer, |count| *count = count.saturating_sub(1));
+            }
+            ProofIssuedAt::<T>::remove(proof_key);
+            if let Some(index) = StatusIndexOf::<T>::take(proof_key) {
+                StatusExpiry::<T>::remove(meta.expiry_block, proof_key);
+                Self::release_status(&meta.issuer, index);
+            }
+            CompositeComponents::<T>::remove(proof_key);
+            CrossingLog::<T>::remove(proof_key);
+        }
+
+        /// Write what happened to proofs in the event just deposited to the offchain index.
+        fn index_events(events: &[(T::ProofKey, ProofEvent<T::AccountId>)]) {
+            Self::index_record(BlockRecord::Proofs(events.to_vec()));
+        }
+
+        /// Write the record of the event just deposited to the offchain index, under the block
+        /// and the index of the event.
+        fn index_record(record: BlockRecord<T::AccountId, T::ProofKey>) {
+            let block = Self::current_block();
+            let parent_hash = frame_system::Pallet::<T>::parent_hash();
+            let block_id = indexing::block_id(&parent_hash, &frame_system::Pallet::<T>::digest());
+            let n = frame_system::Pallet::<T>::event_count().saturating_sub(1);
+            sp_io::offchain_index::set(
+                &indexing::block_event_key(block, &block_id, n),
+                &record.encode(),
+            );
+            // events are counted up within a block, the last write bounds them all
+            sp_io::offchain_index::set(
+                &indexing::block_events_len_key(block, &block_id),
+                &(n + 1).encode(),
+            );
+        }
+
+        /// Expired proofs found by scanning the registry from where the previous run stopped, at
+        /// most `MaxPrunePerBlock` of them. The position is kept in offchain storage, the scan
+        /// starts over once it reaches the end of the registry.
+        fn scan_expired_proofs() -> BoundedVec<T::ProofKey, T::MaxPrunePerBlock> {
+            let cursor_ref = StorageValueRef::persistent(PRUNE_CURSOR_KEY);
+            let cursor = cursor_ref.get::<T::ProofKey>().ok().flatten();
+            let mut entries = match cursor {
+                Some(cursor) => Proofs::<T>::iter_from(Proofs::<T>::hashed_key_for(cursor)),
+                None => Proofs::<T>::iter(),
+            };
+
+            let mut proof_keys = BoundedVec::new();
+            let mut last = None;
+            let mut full = false;
+            for (proof_key, meta) in entries.by_ref().take(PRUNE_SCAN_LIMIT as usize) {
+                if Self::is_prunable(&proof_key, &meta) && proof_keys.try_push(proof_key).is_err() {
+                    full = true;
+                    break;
+                }
+                last = Some(proof_key);
+            }
+
+            match last {
+                Some(proof_key) if full || entries.next().is_some() => cursor_ref.set(&proof_key),
+                _ => cursor_ref.clear(),
+            }
+            proof_keys
+        }
+
+        pub fn do_declare_key_compromise(key: T::AccountId, since: U256) -> DispatchResult {
+            let now = Self::current_block();
+            ensure!(since <= now, Error::<T>::CompromiseInFuture);
+            // stop the key from storing more proofs, the issuer has to register a new one
+            WhitelistEntity::<T>::try_mutate(&key, |maybe_status| -> DispatchResult {
+                let status = maybe_status.as_mut().ok_or(Error::<T>::NotWhitelistEntity)?;
+                if status.state == EntityState::Active {
+                    EntityCount::<T>::mutate(|counts| {
+                        counts.active_issuers.saturating_dec();
+                        counts.suspended_issuers.saturating_inc();
+                    });
+                }
+                status.state = EntityState::Suspended;
+                Ok(())
+            })?;
+
+            // a later declaration can only widen the window
+            let since = CompromisedKeys::<T>::get(&key).map_or(since, |window| window.since.min(since));
+            CompromisedKeys::<T>::insert(&key, CompromiseWindow { since, until: now });
+
+            Self::deposit_event(Event::KeyCompromiseDeclared { key: key.clone(), since, until: now });
+            Self::index_record(BlockRecord::KeyCompromised { issuer: key, since, until: now });
+            Ok(())
+        }
+
+        pub fn do_revoke_batch_leaves(
+            issuer: T::AccountId,
+            root: H256,
+            leaves: BoundedVec<H256, T::MaxLeavesPerRevocation>,
+        ) -> DispatchResult {
+            let batch = Batches::<T>::get(root).ok_or(Error::<T>::BatchNotFound)?;
+            // a suspended issuer can still revoke what it has issued
+            ensure!(batch.issuer == issuer, Error::<T>::NotBatchIssuer);
+
+            let mut revoked = 0u32;
+            for leaf in leaves {
+                if !RevokedLeaves::<T>::contains_key(root, leaf) {
+                    RevokedLeaves::<T>::insert(root, leaf, ());
+                    revoked += 1;
                 }
+            }
+
+            Self::deposit_event(Event::BatchLeavesRevoked { root, revoked });
+            Ok(())
+        }
+
+        pub fn do_authorize_verifier(verifier: T::AccountId) -> DispatchResult {
+            ensure!(
+                !VerifierEntity::<T>::contains_key(&verifier),
+                Error::<T>::IsAlreadyVerifier
+            );
+            VerifierEntity::<T>::insert(&verifier, true);
+            EntityCount::<T>::mutate(|counts| counts.verifiers.saturating_inc());
+
+            Self::deposit_event(Event::VerifierAuthorized { verifier });
+            Ok(())
+        }
+
+        pub fn do_consume_proof(verifier: T::AccountId, proof: ProofBytes<T>) -> DispatchResult {
+            // check is authorized verifier
+            Self::is_verifier_entity(&verifier)?;
+
+            let proof_key = Self::ensure_rekeyed(&proof);
+            let remaining_uses = Proofs::<T>::try_mutate(proof_key, |maybe_meta| {
+                let meta = maybe_meta.as_mut().ok_or(Error::<T>::ProofNotFound)?;
+                ensure!(Self::current_block() <= meta.expiry_block, Error::<T>::ProofExpired);
+                ensure!(!Self::is_revoked(&proof_key, &meta.issuer), Error::<T>::ProofRevoked);
+                ensure!(!Self::is_compromised(&proof_key, &meta.issuer), Error::<T>::ProofRevoked);
+                if let Some(uses) = meta.remaining_uses.as_mut() {
+                    ensure!(*uses > 0, Error::<T>::ProofExhausted);
+                    *uses -= 1;
+                }
+                Ok::<_, DispatchError>(meta.remaining_uses)
+            })?;
+
+            Self::deposit_event(Event::ProofConsumed {
+                proof_key,
+                verifier: verifier.clone(),
+                remaining_uses,
+            });
+            Self::index_events(&[(proof_key, ProofEvent::Consumed { verifier: verifier.clone() })]);
+            if remaining_uses == Some(0) {
+                Self::deposit_event(Event::ProofExhausted { proof_key, verifier });
+            }
+            Ok(())
+        }
+
+        pub fn do_record_crossing(
+            verifier: T::AccountId,
+            proof: ProofBytes<T>,
+            direction: CrossingDirection,
+            checkpoint: CheckpointCode,
+        ) -> DispatchResult {
+            // check is authorized verifier
+            Self::is_verifier_entity(&verifier)?;
+
+            let (proof_key, meta) = Self::get_proof(&proof).ok_or(Error::<T>::ProofNotFound)?;
+            // an expired or revoked proof no longer grants entry, but the holder must still be able
+            // to leave
+            if direction == CrossingDirection::Entry {
+                ensure!(Self::current_block() <= meta.expiry_block, Error::<T>::ProofExpired);
+                ensure!(!Self::is_revoked(&proof_key, &meta.issuer), Error::<T>::ProofRevoked);
+                ensure!(!Self::is_compromised(&proof_key, &meta.issuer), Error::<T>::ProofRevoked);
+            }
+
+            let record = CrossingRecord {
+                direction,
+                checkpoint: checkpoint.clone(),
+                timestamp: T::UnixTime::now().as_millis() as u64,
+            };
+            CrossingLog::<T>::try_mutate(proof_key, |log| {
+                log.try_push(record).map_err(|_| Error::<T>::CrossingLogFull)
+            })?;
+
+            Self::deposit_event(Event::CrossingRecorded { proof_key, direction, checkpoint });
+            Self::index_events(&[(proof_key, ProofEvent::CrossingRecorded { verifier, direction })]);
+            Ok(())
+        }
+
+        pub fn do_issue_challenge(verifier: T::AccountId, proof: ProofBytes<T>) -> DispatchResult {
+            // check is authorized verifier
+            Self::is_verifier_entity(&verifier)?;
+
+            let proof_key = Self::ensure_rekeyed(&proof);
+            let meta = Proofs::<T>::get(proof_key).ok_or(Error::<T>::ProofNotFound)?;
+            ensure!(meta.holder.is_some(), Error::<T>::NoHolderKeyBound);
+            ensure!(Self::current_block() <= meta.expiry_block, Error::<T>::ProofExpired);
+
+            let count = ChallengeCount::<T>::get();
+            let nonce = (b"probo/challenge", count, &verifier, proof_key).using_encoded(blake2_256);
+            let current_block = Self::current_block();
+            let expiry_block = current_block + T::ChallengeTtl::get().into();
+            if ChallengeExpiry::<T>::iter_keys().next().is_none() {
+                ChallengeGcCursor::<T>::put(current_block);
+            }
+            Challenges::<T>::insert(
+                nonce,
+                PresentationChallenge { proof_key, verifier: verifier.clone(), expiry_block },
+            );
+            ChallengeExpiry::<T>::insert(expiry_block, nonce, ());
+            ChallengeCount::<T>::put(count.wrapping_add(1));
+
+            Self::deposit_event(Event::ChallengeIssued { nonce, verifier });
+            Ok(())
+        }
+
+        pub fn do_complete_challenge(
+            verifier: T::AccountId,
+            nonce: [u8; 32],
+            signature: T::HolderSignature,
+        ) -> DispatchResult {
+            let challenge = Challenges::<T>::get(nonce).ok_or(Error::<T>::ChallengeNotFound)?;
+            ensure!(challenge.verifier == verifier, Error::<T>::NotChallengeVerifier);
+            Self::check_presentation(nonce, &signature)?;
+
+            // the nonce can only be answered once
+            Challenges::<T>::remove(nonce);
+            ChallengeExpiry::<T>::remove(challenge.expiry_block, nonce);
+
+            Self::deposit_event(Event::PresentationVerified { nonce, verifier: verifier.clone() });
+            Self::index_events(&[(challenge.proof_key, ProofEvent::Presented { verifier })]);
+            Ok(())
+        }
+
+        /// Message the holder signs to answer a challenge.
+        pub fn presentation_payload(nonce: [u8; 32], proof_key: &T::ProofKey) -> Vec<u8> {
+            (b"probo/presentation", nonce, proof_key).encode()
+        }
+
+        /// Check a holder signature against an open challenge and the key bound to its proof.
+        pub fn check_presentation(
+            nonce: [u8; 32],
+            signature: &T::HolderSignature,
+        ) -> DispatchResult {
+            let challenge = Challenges::<T>::get(nonce).ok_or(Error::<T>::ChallengeNotFound)?;
+            ensure!(Self::current_block() <= challenge.expiry_block, Error::<T>::ChallengeExpired);
+
+            let meta = Proofs::<T>::get(challenge.proof_key).ok_or(Error::<T>::ProofNotFound)?;
+            let holder = meta.holder.ok_or(Error::<T>::NoHolderKeyBound)?;
+            let payload = Self::presentation_payload(nonce, &challenge.proof_key);
+            ensure!(signature.verify(&payload[..], &holder), Error::<T>::InvalidHolderSignature);
+
+            Ok(())
+        }
+
+        /// Status of a proof. A composite proof is only valid while all its components are, the
+        /// first component that is not is reported.
+        pub fn proof_status(proof: &ProofBytes<T>) -> ProofStatus {
+            match Self::get_proof(proof) {
+                Some((proof_key, meta)) => Self::status_of(&proof_key, &meta),
+                None => ProofStatus::Invalid(InvalidReason::NotFound),
+            }
+        }
+
+        /// Status of a proof from its key, see [`Self::proof_status`]. Proofs that have not been
+        /// rekeyed yet are not found.
+        pub fn proof_status_by_key(proof_key: &T::ProofKey) -> ProofStatus {
+            match Proofs::<T>::get(proof_key) {
+                Some(meta) => Self::status_of(proof_key, &meta),
+                None => ProofStatus::Invalid(InvalidReason::NotFound),
+            }
+        }
+
+        fn status_of(proof_key: &T::ProofKey, meta: &ProofMeta<T>) -> ProofStatus {
+            if let Err(reason) = Self::check_validity(proof_key, meta) {
+                return ProofStatus::Invalid(reason);
+            }
+
+            let components = CompositeComponents::<T>::get(proof_key).unwrap_or_default();
+            for (index, component_key) in components.iter().enumerate() {
+                let validity = Proofs::<T>::get(component_key)
+                    .ok_or(InvalidReason::NotFound)
+                    .and_then(|component| Self::check_validity(component_key, &component));
+                if let Err(reason) = validity {
+                    return ProofStatus::InvalidComponent { index: index as u32, reason };
+                }
+            }
+            ProofStatus::Valid
+        }
+
+        /// Validity of a single proof, ignoring its components.
+        fn check_validity(proof_key: &T::ProofKey, meta: &ProofMeta<T>) -> Result<(), InvalidReason> {
+            if Self::current_block() > meta.expiry_block {
+                return Err(InvalidReason::Expired);
+            }
+            if Self::is_revoked(proof_key, &meta.issuer) {
+                return Err(InvalidReason::Revoked);
+            }
+            if Self::is_compromised(proof_key, &meta.issuer) {
+                return Err(InvalidReason::KeyCompromised);
+            }
+            if meta.remaining_uses == Some(0) {
+                return Err(InvalidReason::Exhausted);
+            }
+            Ok(())
+        }
+
+        /// Whether the issuer has revoked the proof in its status list.
+        fn is_revoked(proof_key: &T::ProofKey, issuer: &T::AccountId) -> bool {
+            StatusIndexOf::<T>::get(proof_key).is_some_and(|index| Self::status(issuer, index))
+        }
+
+        /// Whether entry `index` of the status list of `issuer` is set.
+        pub fn status(issuer: &T::AccountId, index: u32) -> bool {
+            let chunk = StatusListChunks::<T>::get(issuer, index / STATUS_CHUNK_BITS);
+            let bit = index % STATUS_CHUNK_BITS;
+            chunk[(bit / 8) as usize] & (1 << (bit % 8)) != 0
+        }
+
+        /// Whether the proof was stored while the key of its issuer was compromised. The issuance
+        /// block is only read for compromised keys.
+        fn is_compromised(proof_key: &T::ProofKey, issuer: &T::AccountId) -> bool {
+            CompromisedKeys::<T>::get(issuer).is_some_and(|window| {
+                window.contains(ProofIssuedAt::<T>::get(proof_key).unwrap_or_default())
+            })
+        }
+
+        /// Set or clear an entry of the status list of `issuer`.
+        fn set_status(issuer: &T::AccountId, index: u32, revoked: bool) {
+            let chunk_index = index / STATUS_CHUNK_BITS;
+            let bit = index % STATUS_CHUNK_BITS;
+            let mut chunk = StatusListChunks::<T>::get(issuer, chunk_index);
+            if revoked {
+                chunk[(bit / 8) as usize] |= 1 << (bit % 8);
+            } else {
+                chunk[(bit / 8) as usize] &= !(1 << (bit % 8));
+            }
+
+            if chunk == StatusChunk::default() {
+                StatusListChunks::<T>::remove(issuer, chunk_index);
             } else {
-                return Err(Error::<T>::NotWhitelistEntity.into());
+                StatusListChunks::<T>::insert(issuer, chunk_index, chunk);
+            }
+        }
+
+        /// Clear an entry of the status list of `issuer` and let another proof take it.
+        fn release_status(issuer: &T::AccountId, index: u32) {
+            StatusIndexOwner::<T>::remove(issuer, index);
+            Self::set_status(issuer, index, false);
+        }
+
+        /// Release the status list entries of expired proofs, oldest expiry first, as far as
+        /// `limit` allows. Returns the weight used.
+        pub(crate) fn release_expired_statuses(limit: Weight) -> Weight {
+            let mut used = T::DbWeight::get().reads(2);
+            if used.any_gt(limit) {
+                return Weight::zero();
+            }
+
+            if StatusExpiry::<T>::iter_keys().next().is_none() {
+                return used;
+            }
+
+            let now = Self::current_block();
+            let mut cursor = StatusGcCursor::<T>::get();
+            let per_block = T::DbWeight::get().reads(1);
+            let per_proof = T::WeightInfo::release_expired_status();
+            // proofs expiring at `cursor` are valid until the end of that block
+            while cursor < now {
+                if used.saturating_add(per_block).any_gt(limit) {
+                    break;
+                }
+                used.saturating_accrue(per_block);
+                let Some(proof_key) = StatusExpiry::<T>::iter_key_prefix(cursor).next() else {
+                    cursor += U256::one();
+                    continue;
+                };
+
+                if used.saturating_add(per_proof).any_gt(limit) {
+                    break;
+                }
+                used.saturating_accrue(per_proof);
+                StatusExpiry::<T>::remove(cursor, proof_key);
+                if let Some(index) = StatusIndexOf::<T>::take(proof_key) {
+                    if let Some(meta) = Proofs::<T>::get(proof_key) {
+                        Self::release_status(&meta.issuer, index);
+                    }
+                }
+            }
+
+            StatusGcCursor::<T>::put(cursor);
+            used.saturating_add(T::DbWeight::get().writes(1))
+        }
+
+        /// Remove the commitments that can no longer be revealed, oldest first, as far as `limit`
+        /// allows. Returns the weight used.
+        pub(crate) fn remove_stale_commitments(limit: Weight) -> Weight {
+            let mut used = T::DbWeight::get().reads(2);
+            if used.any_gt(limit) {
+                return Weight::zero();
+            }
+
+            if CommitmentExpiry::<T>::iter_keys().next().is_none() {
+                return used;
+            }
+
+            let now = Self::current_block();
+            let mut cursor = CommitmentGcCursor::<T>::get();
+            let per_block = T::DbWeight::get().reads(1);
+            let per_commitment = T::WeightInfo::remove_stale_commitment();
+            // commitments expiring at `cursor` can be revealed until the end of that block
+            while cursor < now {
+                if used.saturating_add(per_block).any_gt(limit) {
+                    break;
+                }
+                used.saturating_accrue(per_block);
+                let Some((issuer, commitment)) =
+                    CommitmentExpiry::<T>::iter_key_prefix(cursor).next()
+                else {
+                    cursor += U256::one();
+                    continue;
+                };
+
+                if used.saturating_add(per_commitment).any_gt(limit) {
+                    break;
+                }
+                used.saturating_accrue(per_commitment);
+                CommitmentExpiry::<T>::remove(cursor, (issuer.clone(), commitment));
+                ProofCommitments::<T>::remove(&issuer, commitment);
+            }
+
+            CommitmentGcCursor::<T>::put(cursor);
+            used.saturating_add(T::DbWeight::get().writes(1))
+        }
+
+        /// Remove the challenges that can no longer be answered, oldest first, as far as `limit`
+        /// allows. Returns the weight used.
+        pub(crate) fn remove_expired_challenges(limit: Weight) -> Weight {
+            let mut used = T::DbWeight::get().reads(2);
+            if used.any_gt(limit) {
+                return Weight::zero();
+            }
+
+            if ChallengeExpiry::<T>::iter_keys().next().is_none() {
+                return used;
+            }
+
+            let now = Self::current_block();
+            let mut cursor = ChallengeGcCursor::<T>::get();
+            let per_block = T::DbWeight::get().reads(1);
+            let per_challenge = T::WeightInfo::remove_expired_challenge();
+            // challenges expiring at `cursor` can be answered until the end of that block
+            while cursor < now {
+                if used.saturating_add(per_block).any_gt(limit) {
+                    break;
+                }
+                used.saturating_accrue(per_block);
+                let Some(nonce) = ChallengeExpiry::<T>::iter_key_prefix(cursor).next() else {
+                    cursor += U256::one();
+                    continue;
+                };
+
+                if used.saturating_add(per_challenge).any_gt(limit) {
+                    break;
+                }
+                used.saturating_accrue(per_challenge);
+                ChallengeExpiry::<T>::remove(cursor, nonce);
+                Challenges::<T>::remove(nonce);
+            }
+
+            ChallengeGcCursor::<T>::put(cursor);
+            used.saturating_add(T::DbWeight::get().writes(1))
+        }
+
+        /// Whether the disclosed attributes belong to the attribute root anchored with the proof.
+        /// Returns `false` if the proof is unknown or was stored without an attribute root.
+        pub fn verify_disclosure(
+            proof: &ProofBytes<T>,
+            attributes: &[merkle::DisclosedAttribute],
+        ) -> bool {
+            Self::get_proof(proof)
+                .and_then(|(_, meta)| meta.attribute_root)
+                .is_some_and(|root| merkle::verify_disclosure(&root, attributes))
+        }
+
+        /// Status of a document anchored in a batch, given its leaf and inclusion path. A document
+        /// that is not included under `root` is reported as not found.
+        pub fn batch_document_status(
+            root: H256,
+            leaf: H256,
+            proof: &merkle::MerkleProof,
+        ) -> ProofStatus {
+            let Some(batch) = Batches::<T>::get(root) else {
+                return ProofStatus::Invalid(InvalidReason::NotFound);
+            };
+            if !merkle::verify_proof(&root, leaf, proof) {
+                return ProofStatus::Invalid(InvalidReason::NotFound);
+            }
+            if RevokedLeaves::<T>::contains_key(root, leaf) {
+                return ProofStatus::Invalid(InvalidReason::Revoked);
             }
+            if Self::current_block() > batch.expiry_block {
+                return ProofStatus::Invalid(InvalidReason::Expired);
+            }
+            if CompromisedKeys::<T>::get(&batch.issuer)
+                .is_some_and(|window| window.contains(batch.anchored_at))
+            {
+                return ProofStatus::Invalid(InvalidReason::KeyCompromised);
+            }
+            ProofStatus::Valid
+        }
+
+        /// Entry count and overstay status of a proof, `None` if the proof is unknown.
+        pub fn crossing_summary(proof: ProofBytes<T>) -> Option<CrossingSummary> {
+            let (proof_key, meta) = Self::get_proof(&proof)?;
+            let log = CrossingLog::<T>::get(proof_key);
+
+            let entries =
+                log.iter().filter(|record| record.direction == CrossingDirection::Entry).count() as u32;
+            let exits = log.len() as u32 - entries;
+            let last_crossing = log.last().cloned();
+            let is_inside = last_crossing
+                .as_ref()
+                .is_some_and(|record| record.direction == CrossingDirection::Entry);
+            let overstayed = is_inside && Self::current_block() > meta.expiry_block;
+
+            Some(CrossingSummary { entries, exits, last_crossing, overstayed })
+        }
+
+        /// Add a proof to the registry and to the index of its issuer.
+        pub(crate) fn insert_proof(proof_key: T::ProofKey, meta: ProofMeta<T>) {
+            ProofsByIssuer::<T>::insert(&meta.issuer, proof_key, ());
+            ProofCountByIssuer::<T>::mutate(&meta.issuer, |count| *count = count.saturating_add(1));
+            Proofs::<T>::insert(proof_key, meta);
+        }
+
+        /// Keys of the proofs stored by `issuer`, at most `limit` of them and at most
+        /// `MAX_PROOFS_PER_PAGE`, starting after `cursor`.
+        pub fn proofs_by_issuer(
+            issuer: T::AccountId,
+            cursor: Option<T::ProofKey>,
+            limit: u32,
+        ) -> ProofPage<T::ProofKey> {
+            let limit = limit.min(MAX_PROOFS_PER_PAGE) as usize;
+            let mut keys = match cursor {
+                Some(cursor) => ProofsByIssuer::<T>::iter_key_prefix_from(
+                    &issuer,
+                    ProofsByIssuer::<T>::hashed_key_for(&issuer, cursor),
+                ),
+                None => ProofsByIssuer::<T>::iter_key_prefix(&issuer),
+            };
 
+            let proofs: Vec<_> = keys.by_ref().take(limit).collect();
+            let next_cursor = match keys.next() {
+                Some(_) => proofs.last().copied(),
+                None => None,
+            };
+            ProofPage { proofs, next_cursor, total: ProofCountByIssuer::<T>::get(&issuer) }
+        }
+
+        /// Number of issuers by state, and of verifiers.
+        pub fn entity_counts() -> EntityCounts {
+            EntityCount::<T>::get()
+        }
+
+        /// Count the entities by reading every registration, to fill and check [`EntityCount`].
+        pub(crate) fn count_entities() -> EntityCounts {
+            let mut counts = EntityCounts {
+                verifiers: VerifierEntity::<T>::iter_values().filter(|verifier| *verifier).count()
+                    as u32,
+                ..Default::default()
+            };
+            for status in WhitelistEntity::<T>::iter_values() {
+                match status.state {
+                    EntityState::Active => counts.active_issuers += 1,
+                    EntityState::Suspended => counts.suspended_issuers += 1,
+                }
+            }
+            counts
+        }
+
+        /// Key of a proof in the registry.
+        pub fn proof_key(proof: &[u8]) -> T::ProofKey {
+            T::ProofHashing::hash(proof)
+        }
+
+        /// Look up a proof by its bytes. While the registry is being rekeyed, proofs that have not
+        /// been moved yet are read from the old map.
+        pub fn get_proof(proof: &ProofBytes<T>) -> Option<(T::ProofKey, ProofMeta<T>)> {
+            let proof_key = Self::proof_key(proof);
+            Proofs::<T>::get(proof_key)
+                .or_else(|| migrations::v1::old_proof::<T>(proof))
+                .map(|meta| (proof_key, meta))
+        }
+
+        /// Move a proof to `Proofs` ahead of the rekeying migration, so it can be written to and
+        /// referenced by its key.
+        fn ensure_rekeyed(proof: &ProofBytes<T>) -> T::ProofKey {
+            migrations::v1::migrate_proof::<T>(proof);
+            Self::proof_key(proof)
+        }
+
+        pub fn current_block() -> U256 {
+            frame_system::Pallet::<T>::block_number().into()
+        }
+
+        pub(crate) fn is_whitelist_entity(issuer: &T::AccountId) -> DispatchResult {
+            ensure!(
+                WhitelistEntity::<T>::get(issuer)
+                    .is_some_and(|status| status.state == EntityState::Active),
+                Error::<T>::NotWhitelistEntity
+            );
+            Ok(())
+        }
+
+        /// Invariants of the registry:
+        /// - every whitelisted entity has exactly its recorded amount held,
+        /// - every proof, including those not rekeyed yet, was stored by a whitelisted entity,
+        /// - `ProofsByIssuer` lists exactly the rekeyed proofs under their issuer, and
+        ///   `ProofCountByIssuer` counts them,
+        /// - every batch was anchored by a whitelisted entity and revoked leaves belong to a batch,
+        /// - status lists belong to whitelisted entities and have no empty chunk, status list
+        ///   entries belong to stored proofs, one proof per entry, and are indexed by expiry until
+        ///   released,
+        /// - commitments were made by whitelisted entities in past blocks and are indexed by expiry
+        ///   until removed,
+        /// - quota is only used by whitelisted entities, at most `IssuanceQuota` per era, and not
+        ///   in a future era,
+        /// - issuance blocks belong to stored proofs, compromised keys are suspended entities and
+        ///   their window is not empty,
+        /// - composite proofs, crossing logs and open challenges only refer to stored proofs, and
+        ///   composites have at least two components, none of them composite,
+        /// - challenges are indexed by expiry until removed,
+        /// - proofs are only left under their bytes while the rekeying is ongoing.
+        #[cfg(any(feature = "try-runtime", test))]
+        pub fn do_try_state() -> Result<(), sp_runtime::TryRuntimeError> {
+            for (entity, status) in WhitelistEntity::<T>::iter() {
+                let held = T::NativeBalance::balance_on_hold(
+                    &HoldReason::WhitelistEntity.into(),
+                    &entity,
+                );
+                ensure!(held == status.held, "whitelisted entity must have its registration held");
+            }
+            ensure!(
+                EntityCount::<T>::get() == Self::count_entities(),
+                "entity counts must match the registrations"
+            );
+
+            for (proof_key, meta) in Proofs::<T>::iter() {
+                ensure!(
+                    WhitelistEntity::<T>::contains_key(&meta.issuer),
+                    "proof must be stored by a whitelisted entity"
+                );
+                ensure!(
+                    ProofsByIssuer::<T>::contains_key(&meta.issuer, proof_key),
+                    "proof must be indexed under its issuer"
+                );
+            }
+            for (issuer, proof_key, ()) in ProofsByIssuer::<T>::iter() {
+                ensure!(
+                    Proofs::<T>::get(proof_key).is_some_and(|meta| meta.issuer == issuer),
+                    "issuer index must only refer to proofs of that issuer"
+                );
+            }
+            for (issuer, count) in ProofCountByIssuer::<T>::iter() {
+                ensure!(
+                    ProofsByIssuer::<T>::iter_key_prefix(&issuer).count() as u32 == count,
+                    "proof count must match the issuer index"
+                );
+            }
+            for (_, old) in migrations::v1::IssuanceProof::<T>::iter() {
+                ensure!(
+                    WhitelistEntity::<T>::contains_key(&old.issuer),
+                    "proof must be stored by a whitelisted entity"
+                );
+            }
+            ensure!(
+                RekeyOngoing::<T>::get() || migrations::v1::IssuanceProof::<T>::iter_keys().next().is_none(),
+                "proofs keyed by their bytes must be moved while the rekeying is ongoing"
+            );
+
+            for (proof_key, components) in CompositeComponents::<T>::iter() {
+                ensure!(Proofs::<T>::contains_key(proof_key), "composite proof must be stored");
+                ensure!(components.len() >= 2, "composite proof must have two components");
+                // components may have expired and been pruned before the composite
+                for component_key in components.iter() {
+                    ensure!(
+                        !CompositeComponents::<T>::contains_key(component_key),
+                        "component proof must not be composite"
+                    );
+                }
+            }
+
+            for (_, batch) in Batches::<T>::iter() {
+                ensure!(
+                    WhitelistEntity::<T>::contains_key(&batch.issuer),
+                    "batch must be anchored by a whitelisted entity"
+                );
+            }
+            for (root, _, ()) in RevokedLeaves::<T>::iter() {
+                ensure!(Batches::<T>::contains_key(root), "revoked leaf must belong to a batch");
+            }
+
+            let status_list_chunks = T::StatusListLength::get().div_ceil(STATUS_CHUNK_BITS);
+            for (issuer, chunk_index, chunk) in StatusListChunks::<T>::iter() {
+                ensure!(
+                    WhitelistEntity::<T>::contains_key(&issuer),
+                    "status list must belong to a whitelisted entity"
+                );
+                ensure!(chunk_index < status_list_chunks, "status list chunk must be in range");
+                ensure!(chunk != StatusChunk::default(), "empty status list chunk must be removed");
+            }
+            for (proof_key, index) in StatusIndexOf::<T>::iter() {
+                let meta = Proofs::<T>::get(proof_key)
+                    .ok_or("status list entry must belong to a stored proof")?;
+                ensure!(index < T::StatusListLength::get(), "status list entry must be in range");
+                ensure!(
+                    StatusExpiry::<T>::contains_key(meta.expiry_block, proof_key),
+                    "status list entry must be indexed by expiry"
+                );
+                ensure!(
+                    StatusIndexOwner::<T>::get(&meta.issuer, index) == Some(proof_key),
+                    "status list entry must be owned by its proof"
+                );
+            }
+            for (issuer, index, proof_key) in StatusIndexOwner::<T>::iter() {
+                ensure!(
+                    StatusIndexOf::<T>::get(proof_key) == Some(index)
+                        && Proofs::<T>::get(proof_key).is_some_and(|meta| meta.issuer == issuer),
+                    "status list entry must be owned by a proof of its issuer"
+                );
+            }
+            let gc_cursor = StatusGcCursor::<T>::get();
+            for (expiry_block, proof_key, ()) in StatusExpiry::<T>::iter() {
+                ensure!(
+                    StatusIndexOf::<T>::contains_key(proof_key),
+                    "expiry index must only refer to proofs with a status list entry"
+                );
+                ensure!(expiry_block >= gc_cursor, "expired status list entries must be released");
+            }
+
+            let now = Self::current_block();
+            for (issuer, commitment, committed_at) in ProofCommitments::<T>::iter() {
+                ensure!(
+                    WhitelistEntity::<T>::contains_key(&issuer),
+                    "commitment must be made by a whitelisted entity"
+                );
+                ensure!(committed_at <= now, "commitment must not be made in the future");
+                ensure!(
+                    CommitmentExpiry::<T>::contains_key(
+                        Self::commitment_expiry(committed_at),
+                        (issuer, commitment)
+                    ),
+                    "commitment must be indexed by its expiry block"
+                );
+            }
+            let gc_cursor = CommitmentGcCursor::<T>::get();
+            for (expiry_block, (issuer, commitment), ()) in CommitmentExpiry::<T>::iter() {
+                ensure!(
+                    ProofCommitments::<T>::contains_key(&issuer, commitment),
+                    "expiry index must only refer to open commitments"
+                );
+                ensure!(expiry_block >= gc_cursor, "stale commitments must be removed");
+            }
+            let era = Self::current_era();
+            for (entity, usage) in QuotaUsed::<T>::iter() {
+                ensure!(
+                    WhitelistEntity::<T>::contains_key(&entity),
+                    "quota must be used by a whitelisted entity"
+                );
+                ensure!(usage.used <= T::IssuanceQuota::get(), "quota must not be overused");
+                ensure!(usage.era <= era, "quota must not be used in a future era");
+            }
+            for (proof_key, issued_at) in ProofIssuedAt::<T>::iter() {
+                let meta = Proofs::<T>::get(proof_key)
+                    .ok_or("issuance block must belong to a stored proof")?;
+                ensure!(issued_at <= meta.expiry_block, "proof must not expire before it is stored");
+            }
+            for (key, window) in CompromisedKeys::<T>::iter() {
+                ensure!(
+                    WhitelistEntity::<T>::get(&key)
+                        .is_some_and(|status| status.state == EntityState::Suspended),
+                    "compromised key must be a suspended entity"
+                );
+                ensure!(window.since <= window.until, "compromise window must not be empty");
+            }
+
+            for proof_key in CrossingLog::<T>::iter_keys() {
+                ensure!(Proofs::<T>::contains_key(proof_key), "crossing log must belong to a stored proof");
+            }
+
+            // expired proofs are only pruned once the challenges issued for them have closed
+            for (nonce, challenge) in Challenges::<T>::iter() {
+                ensure!(
+                    ChallengeExpiry::<T>::contains_key(challenge.expiry_block, nonce),
+                    "challenge must be indexed by its expiry block"
+                );
+                if challenge.expiry_block < Self::current_block() {
+                    continue;
+                }
+                let meta = Proofs::<T>::get(challenge.proof_key)
+                    .ok_or("open challenge must be issued for a stored proof")?;
+                ensure!(meta.holder.is_some(), "challenged proof must have a holder key");
+            }
+            let gc_cursor = ChallengeGcCursor::<T>::get();
+            for (expiry_block, nonce, ()) in ChallengeExpiry::<T>::iter() {
+                ensure!(
+                    Challenges::<T>::get(nonce)
+                        .is_some_and(|challenge| challenge.expiry_block == expiry_block),
+                    "expiry index must only refer to open challenges"
+                );
+                ensure!(expiry_block >= gc_cursor, "expired challenges must be removed");
+            }
+
+            Ok(())
+        }
+
+        fn is_verifier_entity(verifier: &T::AccountId) -> DispatchResult {
+            ensure!(
+                VerifierEntity::<T>::get(verifier).unwrap_or(false),
+                Error::<T>::NotVerifierEntity
+            );
             Ok(())
         }
     }
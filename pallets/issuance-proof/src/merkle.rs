@@ -0,0 +1,116 @@
+//! Binary Merkle trees over blake2-256 hashes.
+//!
+//! Used to anchor many values under a single root, e.g. the individually salted attributes of a
+//! document for selective disclosure, or a whole batch of documents whose leaves are the
+//! [`leaf_hash`] of each document's proof bytes. Issuers build the tree off-chain with
+//! [`merkle_root`] and [`merkle_proof`], verifiers check inclusion with [`verify_proof`] either in
+//! the runtime or in their own tooling, as this module has no dependency on the pallet storage.
+//!
+//! Leaves and inner nodes are hashed with different prefixes so that an inner node can never be
+//! presented as a leaf. When a level has an odd number of nodes, the last node is paired with
+//! itself.
+
+use codec::{Decode, DecodeWithMemTracking, Encode};
+use scale_info::TypeInfo;
+use sp_core::{hashing::blake2_256, H256, RuntimeDebug};
+use sp_std::vec::Vec;
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+/// Path from a leaf to the root of a tree.
+#[derive(Encode, Decode, DecodeWithMemTracking, Clone, PartialEq, Eq, TypeInfo, RuntimeDebug)]
+pub struct MerkleProof {
+    /// Position of the leaf in the tree. Its bits tell on which side each sibling sits.
+    pub leaf_index: u32,
+    /// Sibling hashes, from the leaf level up to just below the root.
+    pub siblings: Vec<H256>,
+}
+
+/// An attribute of a document revealed to a verifier, together with its inclusion path.
+#[derive(Encode, Decode, DecodeWithMemTracking, Clone, PartialEq, Eq, TypeInfo, RuntimeDebug)]
+pub struct DisclosedAttribute {
+    /// Random salt so that low-entropy values, e.g. a birth year, cannot be guessed from the leaf.
+    pub salt: [u8; 32],
+    pub name: Vec<u8>,
+    pub value: Vec<u8>,
+    pub proof: MerkleProof,
+}
+
+impl DisclosedAttribute {
+    /// Leaf hash of the attribute in the attribute tree.
+    pub fn leaf(&self) -> H256 {
+        attribute_leaf(&self.salt, &self.name, &self.value)
+    }
+}
+
+/// Leaf hash of a salted attribute.
+pub fn attribute_leaf(salt: &[u8; 32], name: &[u8], value: &[u8]) -> H256 {
+    leaf_hash(&(salt, name, value).encode())
+}
+
+/// Hash arbitrary data into a leaf.
+pub fn leaf_hash(data: &[u8]) -> H256 {
+    (LEAF_PREFIX, data).using_encoded(blake2_256).into()
+}
+
+fn node_hash(left: &H256, right: &H256) -> H256 {
+    (NODE_PREFIX, left, right).using_encoded(blake2_256).into()
+}
+
+fn next_level(nodes: &[H256]) -> Vec<H256> {
+    nodes
+        .chunks(2)
+        .map(|pair| node_hash(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+        .collect()
+}
+
+/// Root of the tree over `leaves`, `None` if there are no leaves.
+pub fn merkle_root(leaves: &[H256]) -> Option<H256> {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level.first().copied()
+}
+
+/// Inclusion path of the leaf at `leaf_index`, `None` if the index is out of range.
+pub fn merkle_proof(leaves: &[H256], leaf_index: u32) -> Option<MerkleProof> {
+    let mut index = leaf_index as usize;
+    if index >= leaves.len() {
+        return None;
+    }
+
+    let mut siblings = Vec::new();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        let sibling = level.get(index ^ 1).unwrap_or(&level[index]);
+        siblings.push(*sibling);
+        level = next_level(&level);
+        index /= 2;
+    }
+    Some(MerkleProof { leaf_index, siblings })
+}
+
+/// Whether `leaf` is included in the tree with the given `root`.
+pub fn verify_proof(root: &H256, leaf: H256, proof: &MerkleProof) -> bool {
+    if proof.siblings.len() >= 32 {
+        return false;
+    }
+
+    let mut index = proof.leaf_index;
+    let mut node = leaf;
+    for sibling in &proof.siblings {
+        node = if index & 1 == 0 { node_hash(&node, sibling) } else { node_hash(sibling, &node) };
+        index >>= 1;
+    }
+    // a leaf index beyond the tree depth would let one path stand for several positions
+    index == 0 && &node == root
+}
+
+/// Whether every disclosed attribute is included in the attribute tree with the given `root`.
+pub fn verify_disclosure(root: &H256, attributes: &[DisclosedAttribute]) -> bool {
+    attributes
+        .iter()
+        .all(|attribute| verify_proof(root, attribute.leaf(), &attribute.proof))
+}
@@ -0,0 +1,336 @@
+//! Storage migrations of the issuance proof pallet.
+//!
+//! Each migration is wrapped in a [`VersionedMigration`](frame_support::migrations::VersionedMigration),
+//! so it only runs once, from the storage version it was written for. The runtime lists them in
+//! order in its `Migrations`.
+
+pub mod v1 {
+    //! Rekeys the proof registry from the proof bytes to their hash under `Config::ProofHashing`.
+    //!
+    //! Proofs used to be stored under their full bytes, up to 512 bytes per key. They are moved
+    //! to [`Proofs`](crate::Proofs) over several blocks: the runtime upgrade only flags the
+    //! migration and `on_idle` moves as many entries as the block has room for. Until it is done, lookups fall
+    //! back to the old map and a proof is moved as soon as it is written to, so the pallet keeps
+    //! serving transactions during the migration. `pallet-migrations` is not used because it
+    //! suspends all transactions until a multi-block migration has finished.
+
+    use crate::{Config, Event, Pallet, ProofMeta, RekeyOngoing, WeightInfo};
+    use codec::{Decode, Encode};
+    use frame_support::{
+        migrations::VersionedMigration, pallet_prelude::*, traits::UncheckedOnRuntimeUpgrade,
+    };
+    use sp_core::U256;
+    use sp_std::vec::Vec;
+
+    #[cfg(feature = "try-runtime")]
+    use sp_runtime::TryRuntimeError;
+
+    /// Proof metadata as stored before the rekeying.
+    #[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, TypeInfo)]
+    pub struct OldProofMeta<AccountId> {
+        pub issuer: AccountId,
+        pub expiry_block: U256,
+    }
+
+    fn upgrade<T: Config>(old: OldProofMeta<T::AccountId>) -> ProofMeta<T> {
+        ProofMeta {
+            issuer: old.issuer,
+            expiry_block: old.expiry_block,
+            remaining_uses: None,
+            holder: None,
+            attribute_root: None,
+        }
+    }
+
+    /// The registry keyed by proof bytes. The keys were bounded to 512 bytes, they encode the
+    /// same as a `Vec<u8>` and a proof of any length can be looked up.
+    #[frame_support::storage_alias]
+    pub type IssuanceProof<T: Config> = StorageMap<
+        Pallet<T>,
+        Blake2_128Concat,
+        Vec<u8>,
+        OldProofMeta<<T as frame_system::Config>::AccountId>,
+    >;
+
+    /// Flags the rekeying if there is anything to move, `on_idle` does the rest.
+    pub struct InnerMigrateV0ToV1<T>(PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateV0ToV1<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if IssuanceProof::<T>::iter_keys().next().is_some() {
+                RekeyOngoing::<T>::put(true);
+            }
+            T::DbWeight::get().reads_writes(1, 1)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+            Ok((IssuanceProof::<T>::iter_keys().count() as u32).encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+            let old_proofs = u32::decode(&mut &state[..])
+                .map_err(|_| "v1: cannot decode the number of old proofs")?;
+            ensure!(
+                IssuanceProof::<T>::iter_keys().count() as u32 == old_proofs,
+                "v1: old proofs must only be moved by on_idle"
+            );
+            ensure!(
+                RekeyOngoing::<T>::get() == (old_proofs > 0),
+                "v1: rekeying must be flagged if and only if there are old proofs"
+            );
+            Ok(())
+        }
+    }
+
+    /// [`InnerMigrateV0ToV1`] run only from storage version 0.
+    pub type MigrateV0ToV1<T> = VersionedMigration<
+        0,
+        1,
+        InnerMigrateV0ToV1<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+
+    /// Move entries to [`Proofs`](crate::Proofs) until `limit` is used up.
+    pub fn step<T: Config>(limit: Weight) -> Weight {
+        let mut used = T::DbWeight::get().reads(1);
+        if !RekeyOngoing::<T>::get() || used.any_gt(limit) {
+            return used;
+        }
+
+        let per_proof = T::WeightInfo::rekey_proof();
+        let mut old_proofs = IssuanceProof::<T>::drain();
+        loop {
+            if used.saturating_add(per_proof).any_gt(limit) {
+                break;
+            }
+            used.saturating_accrue(per_proof);
+            match old_proofs.next() {
+                Some((proof, old)) => {
+                    Pallet::<T>::insert_proof(Pallet::<T>::proof_key(&proof), upgrade::<T>(old));
+                }
+                None => {
+                    RekeyOngoing::<T>::kill();
+                    Pallet::<T>::deposit_event(Event::ProofsRekeyed);
+                    break;
+                }
+            }
+        }
+        used
+    }
+
+    /// Move a single proof, returns whether it was still keyed by its bytes.
+    pub fn migrate_proof<T: Config>(proof: &[u8]) -> bool {
+        if !RekeyOngoing::<T>::get() {
+            return false;
+        }
+        match IssuanceProof::<T>::take(proof) {
+            Some(old) => {
+                Pallet::<T>::insert_proof(Pallet::<T>::proof_key(proof), upgrade::<T>(old));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Read a proof that has not been moved yet.
+    pub fn old_proof<T: Config>(proof: &[u8]) -> Option<ProofMeta<T>> {
+        if !RekeyOngoing::<T>::get() {
+            return None;
+        }
+        IssuanceProof::<T>::get(proof).map(upgrade::<T>)
+    }
+}
+
+pub mod v2 {
+    //! Turns the `bool` whitelist into an [`EntityStatus`] record.
+    //!
+    //! The whitelist only holds staked entities, so it is small enough to migrate in one block.
+    //! Entities flagged `false` are kept as suspended, the amount held for them is read from the
+    //! balances pallet and they get no registration block.
+
+    use crate::{BalanceOf, Config, EntityState, EntityStatus, HoldReason, Pallet};
+    use frame_support::{
+        migrations::VersionedMigration,
+        pallet_prelude::*,
+        traits::{fungible::InspectHold, UncheckedOnRuntimeUpgrade},
+    };
+
+    #[cfg(feature = "try-runtime")]
+    use codec::{Decode, Encode};
+    #[cfg(feature = "try-runtime")]
+    use sp_runtime::TryRuntimeError;
+    #[cfg(feature = "try-runtime")]
+    use sp_std::vec::Vec;
+
+    /// The whitelist before the migration.
+    #[frame_support::storage_alias]
+    pub type WhitelistEntity<T: Config> = StorageMap<
+        Pallet<T>,
+        Blake2_128Concat,
+        <T as frame_system::Config>::AccountId,
+        bool,
+    >;
+
+    pub struct InnerMigrateV1ToV2<T>(PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateV1ToV2<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let mut entities = 0u64;
+            crate::WhitelistEntity::<T>::translate::<bool, _>(|entity, active| {
+                entities += 1;
+                let held: BalanceOf<T> =
+                    T::NativeBalance::balance_on_hold(&HoldReason::WhitelistEntity.into(), &entity);
+                let state = if active { EntityState::Active } else { EntityState::Suspended };
+                Some(EntityStatus { state, held, registered_at: None })
+            });
+            T::DbWeight::get().reads_writes(entities.saturating_mul(2), entities)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+            let entities: Vec<(T::AccountId, bool)> = WhitelistEntity::<T>::iter().collect();
+            Ok(entities.encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+            let entities = Vec::<(T::AccountId, bool)>::decode(&mut &state[..])
+                .map_err(|_| "v2: cannot decode the old whitelist")?;
+            ensure!(
+                crate::WhitelistEntity::<T>::iter().count() == entities.len(),
+                "v2: whitelist entries must be kept"
+            );
+            for (entity, active) in entities {
+                let status = crate::WhitelistEntity::<T>::get(&entity)
+                    .ok_or("v2: whitelisted entity is missing")?;
+                ensure!(
+                    (status.state == EntityState::Active) == active,
+                    "v2: entity state must match the old flag"
+                );
+            }
+            Ok(())
+        }
+    }
+
+    /// [`InnerMigrateV1ToV2`] run only from storage version 1.
+    pub type MigrateV1ToV2<T> = VersionedMigration<
+        1,
+        2,
+        InnerMigrateV1ToV2<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
+
+pub mod v3 {
+    //! Fills [`EntityCount`](crate::EntityCount) from the registrations made before it was kept.
+    //!
+    //! Like the whitelist, the verifiers are few enough to be counted in one block.
+
+    use crate::{Config, EntityCount, Pallet};
+    use frame_support::{
+        migrations::VersionedMigration, pallet_prelude::*, traits::UncheckedOnRuntimeUpgrade,
+    };
+
+    #[cfg(feature = "try-runtime")]
+    use sp_runtime::TryRuntimeError;
+    #[cfg(feature = "try-runtime")]
+    use sp_std::vec::Vec;
+
+    pub struct InnerMigrateV2ToV3<T>(PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateV2ToV3<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let counts = Pallet::<T>::count_entities();
+            EntityCount::<T>::put(counts);
+            let entities = u64::from(counts.active_issuers) +
+                u64::from(counts.suspended_issuers) +
+                u64::from(counts.verifiers);
+            T::DbWeight::get().reads_writes(entities, 1)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(_state: Vec<u8>) -> Result<(), TryRuntimeError> {
+            ensure!(
+                EntityCount::<T>::get() == Pallet::<T>::count_entities(),
+                "v3: entity counts must match the registrations"
+            );
+            Ok(())
+        }
+    }
+
+    /// [`InnerMigrateV2ToV3`] run only from storage version 2.
+    pub type MigrateV2ToV3<T> = VersionedMigration<
+        2,
+        3,
+        InnerMigrateV2ToV3<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
+
+pub mod v4 {
+    //! Indexes the open challenges by expiry block, so `on_idle` can remove them once they have
+    //! expired, and removes the challenges that expired before they were indexed.
+    //!
+    //! A challenge is open for `ChallengeTtl` blocks, so few enough are stored to be moved in
+    //! one block.
+
+    use crate::{ChallengeExpiry, ChallengeGcCursor, Challenges, Config, Pallet};
+    use frame_support::{
+        migrations::VersionedMigration, pallet_prelude::*, traits::UncheckedOnRuntimeUpgrade,
+    };
+    use sp_std::vec::Vec;
+
+    #[cfg(feature = "try-runtime")]
+    use sp_runtime::TryRuntimeError;
+
+    pub struct InnerMigrateV3ToV4<T>(PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateV3ToV4<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let now = Pallet::<T>::current_block();
+            let challenges: Vec<_> = Challenges::<T>::iter()
+                .map(|(nonce, challenge)| (nonce, challenge.expiry_block))
+                .collect();
+            let count = challenges.len() as u64;
+            for (nonce, expiry_block) in challenges {
+                if expiry_block < now {
+                    Challenges::<T>::remove(nonce);
+                } else {
+                    ChallengeExpiry::<T>::insert(expiry_block, nonce, ());
+                }
+            }
+            ChallengeGcCursor::<T>::put(now);
+            T::DbWeight::get().reads_writes(count, count.saturating_add(1))
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(_state: Vec<u8>) -> Result<(), TryRuntimeError> {
+            let now = Pallet::<T>::current_block();
+            for (nonce, challenge) in Challenges::<T>::iter() {
+                ensure!(
+                    challenge.expiry_block >= now,
+                    "v4: expired challenges must be removed"
+                );
+                ensure!(
+                    ChallengeExpiry::<T>::contains_key(challenge.expiry_block, nonce),
+                    "v4: open challenges must be indexed by their expiry block"
+                );
+            }
+            Ok(())
+        }
+    }
+
+    /// [`InnerMigrateV3ToV4`] run only from storage version 3.
+    pub type MigrateV3ToV4<T> = VersionedMigration<
+        3,
+        4,
+        InnerMigrateV3ToV4<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
@@ -5,6 +5,7 @@ use frame_support::{
 	traits::{ConstU128, ConstU16, ConstU32, ConstU64}};
 use sp_core::H256;
 use sp_runtime::{
+	testing::TestSignature,
 	traits::{BlakeTwo256, IdentityLookup},
 	BuildStorage,
 };
@@ -41,6 +42,9 @@ mod runtime {
 
 	#[runtime::pallet_index(2)]
 	pub type Proof = pallet_issuance_proof::Pallet<Runtime>;
+
+	#[runtime::pallet_index(3)]
+	pub type Timestamp = pallet_timestamp::Pallet<Runtime>;
 }
 
 #[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
@@ -86,6 +90,12 @@ impl pallet_balances::Config for Test {
 	type MaxFreezes = ConstU32<10>;
 }
 
+impl pallet_timestamp::Config for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = ConstU64<5>;
+	type WeightInfo = ();
+}
 
 impl pallet_issuance_proof::Config for Test {
     type RuntimeEvent = RuntimeEvent;
@@ -93,6 +103,59 @@ impl pallet_issuance_proof::Config for Test {
 	type RuntimeCall = RuntimeCall;
     type RuntimeHoldReason = RuntimeHoldReason;
     type WeightInfo = IssuanceProofWeightInfo<Test>;
+	type VerifierAuthorityOrigin = frame_system::EnsureRoot<Self::AccountId>;
+	type KeyCompromiseOrigin = frame_system::EnsureRoot<Self::AccountId>;
+	type UnixTime = Timestamp;
+	type MaxCrossingRecords = ConstU32<4>;
+	type HolderKey = u64;
+	type HolderSignature = TestSignature;
+	type ChallengeTtl = ConstU64<10>;
+	type CommitmentTtl = ConstU64<20>;
+	type MaxComponents = ConstU32<4>;
+	type MaxProofLength = ConstU32<512>;
+	type ProofKey = H256;
+	type ProofHashing = BlakeTwo256;
+	type MaxLeavesPerRevocation = ConstU32<4>;
+	type StatusListLength = ConstU32<1024>;
+	type MaxStatusUpdates = ConstU32<4>;
+	type IssuancePriority = ConstU64<100>;
+	type IssuanceQuota = ConstU32<2>;
+	type QuotaEraLength = ConstU64<10>;
+	type MaxPrunePerBlock = ConstU32<4>;
+	type PruneUnsignedPriority = ConstU64<10>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ProofBenchmarkHelper;
+}
+
+type Extrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+
+impl<C> frame_system::offchain::CreateTransactionBase<C> for Test
+where
+	RuntimeCall: From<C>,
+{
+	type Extrinsic = Extrinsic;
+	type RuntimeCall = RuntimeCall;
+}
+
+impl<C> frame_system::offchain::CreateInherent<C> for Test
+where
+	RuntimeCall: From<C>,
+{
+	fn create_inherent(call: RuntimeCall) -> Extrinsic {
+		Extrinsic::new_bare(call)
+	}
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+pub struct ProofBenchmarkHelper;
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_issuance_proof::BenchmarkHelper<u64, TestSignature> for ProofBenchmarkHelper {
+	fn create_holder_key() -> u64 {
+		1
+	}
+	fn sign_presentation(holder: &u64, message: &[u8]) -> TestSignature {
+		TestSignature(*holder, message.to_vec())
+	}
 }
 
 impl Test {
@@ -106,3 +169,11 @@ impl Test {
 pub fn new_test_ext() -> sp_io::TestExternalities {
 	frame_system::GenesisConfig::<Test>::default().build_storage().unwrap().into()
 }
+
+/// Run `test` and check the pallet invariants afterwards.
+pub fn build_and_execute(test: impl FnOnce()) {
+	new_test_ext().execute_with(|| {
+		test();
+		Proof::do_try_state().expect("pallet invariants must hold");
+	});
+}
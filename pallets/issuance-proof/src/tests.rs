@@ -1,13 +1,22 @@
 use crate::{mock::*, *};
-use frame_support::{assert_noop, assert_ok};
-use frame_support::traits::fungible::Mutate;
+use crate::indexing::{self, BlockRecord, ProofEvent};
+use frame_support::{assert_noop, assert_ok, bounded_vec};
+use frame_support::traits::fungible::{Mutate, MutateHold};
+use frame_support::traits::OnRuntimeUpgrade;
 use frame_support::pallet_prelude::*;
+use sp_runtime::{testing::TestSignature, traits::{BlakeTwo256, Hash}};
+use sp_runtime::transaction_validity::{TransactionSource, TransactionValidity, ValidTransaction};
+use sp_core::{H256, U256};
+use sp_core::offchain::{
+    testing::{TestOffchainExt, TestTransactionPoolExt},
+    OffchainDbExt, OffchainWorkerExt, TransactionPoolExt,
+};
 
 
 
 #[test]
 fn test_register_entity_not_enough_fund(){
-    new_test_ext().execute_with(|| {
+    build_and_execute(|| {
         let issuer = Test::create_user_account(0);
         // test fail registration when fund is not enough
         assert_noop!(Proof::do_register_entity(issuer), Error::<Test>::NotEnoughFund);
@@ -16,7 +25,7 @@ fn test_register_entity_not_enough_fund(){
 
 #[test]
 fn test_success_register_entity(){
-    new_test_ext().execute_with(|| {
+    build_and_execute(|| {
         let issuer = Test::create_user_account(0);
 
         // mint token before register
@@ -30,7 +39,7 @@ fn test_success_register_entity(){
 
 #[test]
 fn test_register_entity_already_whitelisted(){
-    new_test_ext().execute_with(|| {
+    build_and_execute(|| {
         let issuer = Test::create_user_account(0);
 
         // mint token before register
@@ -45,7 +54,7 @@ fn test_register_entity_already_whitelisted(){
 }
 #[test]
 fn test_store_proof_not_whitelist_entity(){
-        new_test_ext().execute_with(|| {
+        build_and_execute(|| {
         let issuer = Test::create_user_account(0);
         // mint token before register
         let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
@@ -53,7 +62,7 @@ fn test_store_proof_not_whitelist_entity(){
         let proof = vec![1u8; 512];
         let proof: BoundedVec<u8, ConstU32<512>> = proof.try_into().expect("proof too long");
         // store proof when not whitelisted; should fail
-        assert_noop!(Proof::do_store_proof(issuer, proof, 1000.into()), Error::<Test>::NotWhitelistEntity);
+        assert_noop!(Proof::do_store_proof(issuer, proof, 1000.into(), None, None, None, None), Error::<Test>::NotWhitelistEntity);
 
     });
 }
@@ -61,7 +70,7 @@ fn test_store_proof_not_whitelist_entity(){
 
 #[test]
 fn test_success_store_proof(){
-        new_test_ext().execute_with(|| {
+        build_and_execute(|| {
         let issuer = Test::create_user_account(0);
         // mint token before register
         let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
@@ -70,13 +79,13 @@ fn test_success_store_proof(){
         let proof = vec![1u8; 512];
         let proof: BoundedVec<u8, ConstU32<512>> = proof.try_into().expect("proof too long");
         // store proof
-        assert_ok!(Proof::do_store_proof(issuer, proof, 1000.into()));
+        assert_ok!(Proof::do_store_proof(issuer, proof, 1000.into(), None, None, None, None));
     });
 }
 
 #[test]
 fn test_store_proof_already_exist(){
-        new_test_ext().execute_with(|| {
+        build_and_execute(|| {
         let issuer = Test::create_user_account(0);
         // mint token before register
         let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
@@ -85,10 +94,1612 @@ fn test_store_proof_already_exist(){
         let proof = vec![1u8; 512];
         let proof: BoundedVec<u8, ConstU32<512>> = proof.try_into().expect("proof too long");
         // store proof
-        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 1000.into()));
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 1000.into(), None, None, None, None));
 
         // store existing proof; should fail
-        assert_noop!(Proof::do_store_proof(issuer, proof.clone(), 1000.into()), Error::<Test>::ProofAlreadyExist);
+        assert_noop!(Proof::do_store_proof(issuer, proof.clone(), 1000.into(), None, None, None, None), Error::<Test>::ProofAlreadyExist);
+
+    });
+}
+#[test]
+fn test_store_proof_zero_usage_limit(){
+        build_and_execute(|| {
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let proof: ProofBytes<Test> = vec![1u8; 512].try_into().expect("proof too long");
+        // a proof that can never be used; should fail
+        assert_noop!(Proof::do_store_proof(issuer, proof, 1000.into(), Some(0), None, None, None), Error::<Test>::InvalidUsageLimit);
+    });
+}
+
+#[test]
+fn test_authorize_verifier(){
+        build_and_execute(|| {
+        let verifier = Test::create_user_account(1);
+        // only the authority origin can authorize verifiers
+        assert_noop!(Proof::authorize_verifier(RuntimeOrigin::signed(verifier), verifier), DispatchError::BadOrigin);
+        assert_ok!(Proof::authorize_verifier(RuntimeOrigin::root(), verifier));
+
+        // authorize again; should fail
+        assert_noop!(Proof::authorize_verifier(RuntimeOrigin::root(), verifier), Error::<Test>::IsAlreadyVerifier);
+    });
+}
+
+#[test]
+fn test_consume_proof_not_verifier_entity(){
+        build_and_execute(|| {
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let proof: ProofBytes<Test> = vec![1u8; 512].try_into().expect("proof too long");
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 1000.into(), Some(1), None, None, None));
+
+        // the issuer is not a verifier; should fail
+        assert_noop!(Proof::do_consume_proof(issuer, proof), Error::<Test>::NotVerifierEntity);
+    });
+}
+
+#[test]
+fn test_consume_proof_until_exhausted(){
+        build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let verifier = Test::create_user_account(1);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        assert_ok!(Proof::do_authorize_verifier(verifier));
+        let proof: ProofBytes<Test> = vec![1u8; 512].try_into().expect("proof too long");
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 1000.into(), Some(2), None, None, None));
+
+        assert_ok!(Proof::do_consume_proof(verifier, proof.clone()));
+        assert_eq!(Proofs::<Test>::get(Proof::proof_key(&proof)).unwrap().remaining_uses, Some(1));
+
+        assert_ok!(Proof::do_consume_proof(verifier, proof.clone()));
+        assert_eq!(Proofs::<Test>::get(Proof::proof_key(&proof)).unwrap().remaining_uses, Some(0));
+        System::assert_last_event(Event::ProofExhausted { proof_key: Proof::proof_key(&proof), verifier }.into());
+
+        // no uses left; should fail
+        assert_noop!(Proof::do_consume_proof(verifier, proof), Error::<Test>::ProofExhausted);
+    });
+}
+
+#[test]
+fn test_consume_unlimited_and_expired_proof(){
+        build_and_execute(|| {
+        let issuer = Test::create_user_account(0);
+        let verifier = Test::create_user_account(1);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        assert_ok!(Proof::do_authorize_verifier(verifier));
+        let proof: ProofBytes<Test> = vec![1u8; 512].try_into().expect("proof too long");
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 1000.into(), None, None, None, None));
+
+        // unlimited proofs can be used any number of times
+        assert_ok!(Proof::do_consume_proof(verifier, proof.clone()));
+        assert_ok!(Proof::do_consume_proof(verifier, proof.clone()));
+        assert_eq!(Proofs::<Test>::get(Proof::proof_key(&proof)).unwrap().remaining_uses, None);
 
+        // past the expiry block; should fail
+        System::set_block_number(1001);
+        assert_noop!(Proof::do_consume_proof(verifier, proof), Error::<Test>::ProofExpired);
     });
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_record_crossing_not_verifier_entity(){
+        build_and_execute(|| {
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let proof: ProofBytes<Test> = vec![1u8; 512].try_into().expect("proof too long");
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 1000.into(), None, None, None, None));
+        let checkpoint: CheckpointCode = b"NRT-T1".to_vec().try_into().unwrap();
+
+        // the issuer is not a verifier; should fail
+        assert_noop!(
+            Proof::do_record_crossing(issuer, proof, CrossingDirection::Entry, checkpoint),
+            Error::<Test>::NotVerifierEntity
+        );
+    });
+}
+
+#[test]
+fn test_record_crossing_summary(){
+        build_and_execute(|| {
+        let issuer = Test::create_user_account(0);
+        let verifier = Test::create_user_account(1);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        assert_ok!(Proof::do_authorize_verifier(verifier));
+        let proof: ProofBytes<Test> = vec![1u8; 512].try_into().expect("proof too long");
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 1000.into(), None, None, None, None));
+        let checkpoint: CheckpointCode = b"NRT-T1".to_vec().try_into().unwrap();
+
+        Timestamp::set_timestamp(1_000);
+        assert_ok!(Proof::do_record_crossing(verifier, proof.clone(), CrossingDirection::Entry, checkpoint.clone()));
+        Timestamp::set_timestamp(2_000);
+        assert_ok!(Proof::do_record_crossing(verifier, proof.clone(), CrossingDirection::Exit, checkpoint.clone()));
+        assert_ok!(Proof::do_record_crossing(verifier, proof.clone(), CrossingDirection::Entry, checkpoint.clone()));
+
+        let summary = Proof::crossing_summary(proof.clone()).unwrap();
+        assert_eq!(summary.entries, 2);
+        assert_eq!(summary.exits, 1);
+        assert_eq!(summary.last_crossing.unwrap().timestamp, 2_000);
+        assert!(!summary.overstayed);
+
+        // still inside after the proof has expired
+        System::set_block_number(1001);
+        assert!(Proof::crossing_summary(proof.clone()).unwrap().overstayed);
+
+        // entering with an expired proof; should fail
+        assert_noop!(
+            Proof::do_record_crossing(verifier, proof.clone(), CrossingDirection::Entry, checkpoint.clone()),
+            Error::<Test>::ProofExpired
+        );
+        // leaving is still recorded
+        assert_ok!(Proof::do_record_crossing(verifier, proof.clone(), CrossingDirection::Exit, checkpoint.clone()));
+        assert!(!Proof::crossing_summary(proof.clone()).unwrap().overstayed);
+
+        // the log is bounded by MaxCrossingRecords
+        assert_noop!(
+            Proof::do_record_crossing(verifier, proof, CrossingDirection::Exit, checkpoint),
+            Error::<Test>::CrossingLogFull
+        );
+    });
+}
+
+#[test]
+fn test_issue_challenge_no_holder_key(){
+        build_and_execute(|| {
+        let issuer = Test::create_user_account(0);
+        let verifier = Test::create_user_account(1);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        assert_ok!(Proof::do_authorize_verifier(verifier));
+        let proof: ProofBytes<Test> = vec![1u8; 512].try_into().expect("proof too long");
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 1000.into(), None, None, None, None));
+
+        // nothing to challenge the holder with; should fail
+        assert_noop!(Proof::do_issue_challenge(verifier, proof), Error::<Test>::NoHolderKeyBound);
+    });
+}
+
+#[test]
+fn test_challenge_response_presentation(){
+        build_and_execute(|| {
+        let issuer = Test::create_user_account(0);
+        let verifier = Test::create_user_account(1);
+        let other_verifier = Test::create_user_account(2);
+        let holder = 7u64;
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        assert_ok!(Proof::do_authorize_verifier(verifier));
+        assert_ok!(Proof::do_authorize_verifier(other_verifier));
+        let proof: ProofBytes<Test> = vec![1u8; 512].try_into().expect("proof too long");
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 1000.into(), None, Some(holder), None, None));
+
+        assert_ok!(Proof::do_issue_challenge(verifier, proof.clone()));
+        let (nonce, _) = Challenges::<Test>::iter().next().unwrap();
+        let payload = Proof::presentation_payload(nonce, &Proof::proof_key(&proof));
+
+        // someone who copied the proof bytes cannot answer the challenge
+        assert_noop!(
+            Proof::check_presentation(nonce, &TestSignature(8, payload.clone())),
+            Error::<Test>::InvalidHolderSignature
+        );
+        let signature = TestSignature(holder, payload);
+        assert_ok!(Proof::check_presentation(nonce, &signature));
+
+        // only the verifier that issued the challenge can close it
+        assert_noop!(
+            Proof::do_complete_challenge(other_verifier, nonce, signature.clone()),
+            Error::<Test>::NotChallengeVerifier
+        );
+        assert_ok!(Proof::do_complete_challenge(verifier, nonce, signature.clone()));
+
+        // replaying the answer; should fail
+        assert_noop!(
+            Proof::do_complete_challenge(verifier, nonce, signature),
+            Error::<Test>::ChallengeNotFound
+        );
+    });
+}
+
+#[test]
+fn test_challenge_expired(){
+        build_and_execute(|| {
+        let issuer = Test::create_user_account(0);
+        let verifier = Test::create_user_account(1);
+        let holder = 7u64;
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        assert_ok!(Proof::do_authorize_verifier(verifier));
+        let proof: ProofBytes<Test> = vec![1u8; 512].try_into().expect("proof too long");
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 1000.into(), None, Some(holder), None, None));
+
+        assert_ok!(Proof::do_issue_challenge(verifier, proof.clone()));
+        let (nonce, _) = Challenges::<Test>::iter().next().unwrap();
+        let signature = TestSignature(holder, Proof::presentation_payload(nonce, &Proof::proof_key(&proof)));
+
+        System::set_block_number(5);
+        assert_ok!(Proof::do_issue_challenge(verifier, proof.clone()));
+        let open = Challenges::<Test>::iter_keys().find(|key| *key != nonce).unwrap();
+
+        // past ChallengeTtl; should fail
+        System::set_block_number(11);
+        assert_noop!(
+            Proof::do_complete_challenge(verifier, nonce, signature),
+            Error::<Test>::ChallengeExpired
+        );
+
+        // and it is removed once the chain is idle, the open one is kept
+        Proof::on_idle(11, Weight::MAX);
+        assert!(!Challenges::<Test>::contains_key(nonce));
+        assert!(!ChallengeExpiry::<Test>::contains_key(U256::from(10), nonce));
+        assert!(Challenges::<Test>::contains_key(open));
+        assert!(ChallengeExpiry::<Test>::contains_key(U256::from(15), open));
+        assert_eq!(ChallengeGcCursor::<Test>::get(), 11.into());
+    });
+}
+
+#[test]
+fn test_challenge_expiry_migration(){
+    build_and_execute(|| {
+        let issuer = Test::create_user_account(0);
+        let verifier = Test::create_user_account(1);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        assert_ok!(Proof::do_authorize_verifier(verifier));
+        let proof: ProofBytes<Test> = vec![1u8; 64].try_into().expect("proof too long");
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 1000.into(), None, Some(7), None, None));
+        assert_ok!(Proof::do_issue_challenge(verifier, proof.clone()));
+        let (expired, _) = Challenges::<Test>::iter().next().unwrap();
+        System::set_block_number(5);
+        assert_ok!(Proof::do_issue_challenge(verifier, proof));
+        let open = Challenges::<Test>::iter_keys().find(|key| *key != expired).unwrap();
+
+        // challenges issued before they were indexed by expiry
+        let _ = ChallengeExpiry::<Test>::clear(u32::MAX, None);
+        ChallengeGcCursor::<Test>::kill();
+        StorageVersion::new(3).put::<Proof>();
+
+        System::set_block_number(12);
+        migrations::v4::MigrateV3ToV4::<Test>::on_runtime_upgrade();
+        assert_eq!(Proof::on_chain_storage_version(), 4);
+        assert!(!Challenges::<Test>::contains_key(expired));
+        assert!(ChallengeExpiry::<Test>::contains_key(U256::from(15), open));
+        assert_eq!(ChallengeGcCursor::<Test>::get(), 12.into());
+
+        // the open challenge is removed by `on_idle` once it has expired
+        System::set_block_number(16);
+        Proof::on_idle(16, Weight::MAX);
+        assert!(!Challenges::<Test>::contains_key(open));
+        assert_eq!(ChallengeExpiry::<Test>::iter().count(), 0);
+    });
+}
+
+fn attribute_leaves() -> Vec<(merkle::DisclosedAttribute, sp_core::H256)> {
+    let attributes: Vec<(&[u8], &[u8])> = vec![
+        (b"passport_number", b"X1234567"),
+        (b"nationality", b"TWN"),
+        (b"over_18", b"true"),
+        (b"visa_type", b"multiple-entry"),
+        (b"expiry_date", b"2030-01-01"),
+    ];
+    attributes
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, value))| {
+            let attribute = merkle::DisclosedAttribute {
+                salt: [i as u8; 32],
+                name: name.to_vec(),
+                value: value.to_vec(),
+                proof: merkle::MerkleProof { leaf_index: i as u32, siblings: vec![] },
+            };
+            let leaf = attribute.leaf();
+            (attribute, leaf)
+        })
+        .collect()
+}
+
+#[test]
+fn test_merkle_proof_every_leaf(){
+    let attributes = attribute_leaves();
+    for count in 1..=attributes.len() {
+        let leaves: Vec<_> = attributes[..count].iter().map(|(_, leaf)| *leaf).collect();
+        let root = merkle::merkle_root(&leaves).unwrap();
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = merkle::merkle_proof(&leaves, index as u32).unwrap();
+            assert!(merkle::verify_proof(&root, *leaf, &proof));
+        }
+        assert!(merkle::merkle_proof(&leaves, count as u32).is_none());
+    }
+    assert!(merkle::merkle_root(&[]).is_none());
+}
+
+#[test]
+fn test_merkle_proof_rejects_tampering(){
+    let leaves: Vec<_> = attribute_leaves().into_iter().map(|(_, leaf)| leaf).collect();
+    let root = merkle::merkle_root(&leaves).unwrap();
+    let proof = merkle::merkle_proof(&leaves, 2).unwrap();
+
+    // another leaf on the same path
+    assert!(!merkle::verify_proof(&root, leaves[3], &proof));
+    // a root of another tree
+    let other_root = merkle::merkle_root(&leaves[..4]).unwrap();
+    assert!(!merkle::verify_proof(&other_root, leaves[2], &proof));
+    // an index beyond the tree depth
+    let mut shifted = proof.clone();
+    shifted.leaf_index += 1 << proof.siblings.len();
+    assert!(!merkle::verify_proof(&root, leaves[2], &shifted));
+}
+
+#[test]
+fn test_selective_disclosure(){
+        build_and_execute(|| {
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+
+        let attributes = attribute_leaves();
+        let leaves: Vec<_> = attributes.iter().map(|(_, leaf)| *leaf).collect();
+        let root = merkle::merkle_root(&leaves).unwrap();
+        let proof: ProofBytes<Test> = vec![1u8; 512].try_into().expect("proof too long");
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 1000.into(), None, None, Some(root), None));
+
+        // the bartender only sees "over 18"
+        let mut over_18 = attributes[2].0.clone();
+        over_18.proof = merkle::merkle_proof(&leaves, 2).unwrap();
+        assert!(Proof::verify_disclosure(&proof, &[over_18.clone()]));
+
+        // a forged value does not match the anchored root
+        let mut forged = over_18.clone();
+        forged.value = b"false".to_vec();
+        assert!(!Proof::verify_disclosure(&proof, &[over_18, forged]));
+
+        // proofs stored without an attribute root cannot disclose anything
+        let plain_proof: ProofBytes<Test> = vec![2u8; 512].try_into().expect("proof too long");
+        assert_ok!(Proof::do_store_proof(issuer, plain_proof.clone(), 1000.into(), None, None, None, None));
+        assert!(!Proof::verify_disclosure(&plain_proof, &[]));
+    });
+}
+
+#[test]
+fn test_store_composite_proof_invalid_components(){
+        build_and_execute(|| {
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let passport: ProofBytes<Test> = vec![1u8; 64].try_into().expect("proof too long");
+        let visa: ProofBytes<Test> = vec![2u8; 64].try_into().expect("proof too long");
+        let unknown: ProofBytes<Test> = vec![3u8; 64].try_into().expect("proof too long");
+        let composite: ProofBytes<Test> = vec![4u8; 64].try_into().expect("proof too long");
+        assert_ok!(Proof::do_store_proof(issuer, passport.clone(), 1000.into(), None, None, None, None));
+        assert_ok!(Proof::do_store_proof(issuer, visa.clone(), 1000.into(), None, None, None, None));
+
+        assert_noop!(
+            Proof::do_store_composite_proof(issuer, composite.clone(), 1000.into(), bounded_vec![passport.clone()]),
+            Error::<Test>::TooFewComponents
+        );
+        assert_noop!(
+            Proof::do_store_composite_proof(issuer, composite.clone(), 1000.into(), bounded_vec![passport.clone(), passport.clone()]),
+            Error::<Test>::DuplicateComponent
+        );
+        assert_noop!(
+            Proof::do_store_composite_proof(issuer, composite.clone(), 1000.into(), bounded_vec![passport.clone(), unknown]),
+            Error::<Test>::InvalidComponent
+        );
+
+        assert_ok!(Proof::do_store_composite_proof(issuer, composite.clone(), 1000.into(), bounded_vec![passport.clone(), visa]));
+        let nested: ProofBytes<Test> = vec![5u8; 64].try_into().expect("proof too long");
+        assert_noop!(
+            Proof::do_store_composite_proof(issuer, nested, 1000.into(), bounded_vec![passport, composite]),
+            Error::<Test>::NestedComposite
+        );
+    });
+}
+
+#[test]
+fn test_composite_proof_status(){
+        build_and_execute(|| {
+        let passport_office = Test::create_user_account(0);
+        let embassy = Test::create_user_account(1);
+        let verifier = Test::create_user_account(2);
+        for issuer in [passport_office, embassy] {
+            let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+            assert_ok!(Proof::do_register_entity(issuer));
+        }
+        assert_ok!(Proof::do_authorize_verifier(verifier));
+        let passport: ProofBytes<Test> = vec![1u8; 64].try_into().expect("proof too long");
+        let visa: ProofBytes<Test> = vec![2u8; 64].try_into().expect("proof too long");
+        let combination: ProofBytes<Test> = vec![3u8; 64].try_into().expect("proof too long");
+        assert_ok!(Proof::do_store_proof(passport_office, passport.clone(), 2000.into(), None, None, None, None));
+        assert_ok!(Proof::do_store_proof(embassy, visa.clone(), 500.into(), Some(1), None, None, None));
+        assert_ok!(Proof::do_store_composite_proof(embassy, combination.clone(), 1000.into(), bounded_vec![passport, visa.clone()]));
+        assert_eq!(Proof::proof_status(&combination), ProofStatus::Valid);
+
+        // the single-entry visa has been used
+        assert_ok!(Proof::do_consume_proof(verifier, visa));
+        assert_eq!(
+            Proof::proof_status(&combination),
+            ProofStatus::InvalidComponent { index: 1, reason: InvalidReason::Exhausted }
+        );
+        // the same from the key of the proof
+        assert_eq!(
+            Proof::proof_status_by_key(&Proof::proof_key(&combination)),
+            ProofStatus::InvalidComponent { index: 1, reason: InvalidReason::Exhausted }
+        );
+        assert_eq!(Proof::proof_status_by_key(&H256::zero()), ProofStatus::Invalid(InvalidReason::NotFound));
+
+        // the composite itself has expired
+        System::set_block_number(1001);
+        assert_eq!(Proof::proof_status(&combination), ProofStatus::Invalid(InvalidReason::Expired));
+    });
+}
+
+#[test]
+fn test_rekey_migration(){
+        build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let verifier = Test::create_user_account(1);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        assert_ok!(Proof::do_authorize_verifier(verifier));
+        let proofs: Vec<ProofBytes<Test>> =
+            (1..=3u8).map(|i| vec![i; 64].try_into().expect("proof too long")).collect();
+        for proof in &proofs {
+            migrations::v1::IssuanceProof::<Test>::insert(
+                proof,
+                migrations::v1::OldProofMeta { issuer, expiry_block: 1000.into() },
+            );
+        }
+        StorageVersion::new(0).put::<Proof>();
+        migrations::v1::MigrateV0ToV1::<Test>::on_runtime_upgrade();
+        assert_eq!(Proof::on_chain_storage_version(), 1);
+        assert!(RekeyOngoing::<Test>::get());
+
+        // proofs that have not been moved yet are still served
+        assert_eq!(Proof::proof_status(&proofs[0]), ProofStatus::Valid);
+        assert_noop!(
+            Proof::do_store_proof(issuer, proofs[0].clone(), 1000.into(), None, None, None, None),
+            Error::<Test>::ProofAlreadyExist
+        );
+        // and moved as soon as they are written to
+        assert_ok!(Proof::do_consume_proof(verifier, proofs[0].clone()));
+        assert!(!migrations::v1::IssuanceProof::<Test>::contains_key(&proofs[0]));
+
+        // the rest is moved with the weight left in each block
+        let per_proof = <Test as pallet::Config>::WeightInfo::rekey_proof();
+        Proof::on_idle(1, per_proof);
+        assert_eq!(migrations::v1::IssuanceProof::<Test>::iter().count(), 1);
+        Proof::on_idle(2, per_proof.saturating_mul(2));
+        assert!(!RekeyOngoing::<Test>::get());
+        System::assert_last_event(Event::ProofsRekeyed.into());
+
+        for proof in &proofs {
+            assert!(Proofs::<Test>::contains_key(Proof::proof_key(proof)));
+        }
+        // rekeyed proofs are listed under their issuer
+        assert_eq!(Proof::proofs_by_issuer(issuer, None, 10).proofs.len(), 3);
+    });
+}
+
+#[test]
+fn test_store_short_proof(){
+        build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        // an ed25519 signature
+        let proof: ProofBytes<Test> = vec![1u8; 64].try_into().expect("proof too long");
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 1000.into(), None, None, None, None));
+
+        let proof_key = BlakeTwo256::hash(&proof);
+        System::assert_last_event(
+            Event::ProofStored {
+                proof_key,
+                proof,
+                issuer,
+                expiry_block: 1001.into(),
+                max_uses: None,
+                status_index: None,
+            }
+            .into(),
+        );
+        assert!(Proofs::<Test>::contains_key(proof_key));
+    });
+}
+
+#[test]
+fn test_whitelist_status_migration(){
+        build_and_execute(|| {
+        let active = Test::create_user_account(0);
+        let flagged = Test::create_user_account(1);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&active, (u32::MAX << 10).into());
+        assert_ok!(<mock::Test as pallet::Config>::NativeBalance::hold(
+            &HoldReason::WhitelistEntity.into(),
+            &active,
+            1_000_000_000,
+        ));
+        migrations::v2::WhitelistEntity::<Test>::insert(active, true);
+        migrations::v2::WhitelistEntity::<Test>::insert(flagged, false);
+
+        StorageVersion::new(1).put::<Proof>();
+        migrations::v2::MigrateV1ToV2::<Test>::on_runtime_upgrade();
+        assert_eq!(Proof::on_chain_storage_version(), 2);
+
+        assert_eq!(
+            WhitelistEntity::<Test>::get(active),
+            Some(EntityStatus { state: EntityState::Active, held: 1_000_000_000, registered_at: None })
+        );
+        assert_eq!(WhitelistEntity::<Test>::get(flagged).unwrap().state, EntityState::Suspended);
+
+        // suspended entities cannot store proofs
+        let proof: ProofBytes<Test> = vec![1u8; 64].try_into().expect("proof too long");
+        assert_noop!(
+            Proof::do_store_proof(flagged, proof.clone(), 1000.into(), None, None, None, None),
+            Error::<Test>::NotWhitelistEntity
+        );
+        assert_ok!(Proof::do_store_proof(active, proof, 1000.into(), None, None, None, None));
+
+        // the migration only runs once
+        migrations::v2::MigrateV1ToV2::<Test>::on_runtime_upgrade();
+        assert_eq!(WhitelistEntity::<Test>::get(flagged).unwrap().state, EntityState::Suspended);
+
+        // the entities are counted by the next migration
+        migrations::v3::MigrateV2ToV3::<Test>::on_runtime_upgrade();
+        assert_eq!(Proof::on_chain_storage_version(), 3);
+        assert_eq!(
+            Proof::entity_counts(),
+            EntityCounts { active_issuers: 1, suspended_issuers: 1, verifiers: 0 }
+        );
+    });
+}
+
+#[test]
+fn test_register_entity_status(){
+        build_and_execute(|| {
+        System::set_block_number(5);
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+
+        assert_eq!(
+            WhitelistEntity::<Test>::get(issuer),
+            Some(EntityStatus { state: EntityState::Active, held: 1_000_000_000, registered_at: Some(5.into()) })
+        );
+    });
+}
+
+#[test]
+fn test_try_state_detects_inconsistencies(){
+        new_test_ext().execute_with(|| {
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let proof: ProofBytes<Test> = vec![1u8; 64].try_into().expect("proof too long");
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 1000.into(), None, None, None, None));
+        assert_ok!(Proof::do_try_state());
+
+        // the registration hold is released behind the pallet's back
+        assert_ok!(<mock::Test as pallet::Config>::NativeBalance::release(
+            &HoldReason::WhitelistEntity.into(),
+            &issuer,
+            1,
+            frame_support::traits::tokens::Precision::Exact,
+        ));
+        assert!(Proof::do_try_state().is_err());
+        assert_ok!(<mock::Test as pallet::Config>::NativeBalance::hold(
+            &HoldReason::WhitelistEntity.into(),
+            &issuer,
+            1,
+        ));
+        assert_ok!(Proof::do_try_state());
+
+        // a proof of an unknown issuer
+        WhitelistEntity::<Test>::remove(issuer);
+        assert_ok!(<mock::Test as pallet::Config>::NativeBalance::release_all(
+            &HoldReason::WhitelistEntity.into(),
+            &issuer,
+            frame_support::traits::tokens::Precision::Exact,
+        ));
+        assert!(Proof::do_try_state().is_err());
+    });
+}
+
+#[test]
+fn test_proofs_by_issuer_pagination(){
+        build_and_execute(|| {
+        let issuer = Test::create_user_account(0);
+        let other_issuer = Test::create_user_account(1);
+        for entity in [issuer, other_issuer] {
+            let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&entity, (u32::MAX << 10).into());
+            assert_ok!(Proof::do_register_entity(entity));
+        }
+        let mut stored = Vec::new();
+        for i in 0..5u8 {
+            let proof: ProofBytes<Test> = vec![i; 64].try_into().expect("proof too long");
+            assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 1000.into(), None, None, None, None));
+            stored.push(Proof::proof_key(&proof));
+        }
+        let proof: ProofBytes<Test> = vec![u8::MAX; 64].try_into().expect("proof too long");
+        assert_ok!(Proof::do_store_proof(other_issuer, proof, 1000.into(), None, None, None, None));
+
+        let mut listed = Vec::new();
+        let mut cursor = None;
+        let mut pages = 0;
+        loop {
+            let page = Proof::proofs_by_issuer(issuer, cursor, 2);
+            assert_eq!(page.total, 5);
+            listed.extend(page.proofs);
+            pages += 1;
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(pages, 3);
+        listed.sort();
+        stored.sort();
+        assert_eq!(listed, stored);
+
+        // other issuers' proofs are not listed
+        let page = Proof::proofs_by_issuer(other_issuer, None, 10);
+        assert_eq!((page.proofs.len(), page.next_cursor, page.total), (1, None, 1));
+    });
+}
+
+#[test]
+fn test_entity_counts(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        assert_eq!(Proof::entity_counts(), EntityCounts::default());
+        for i in 0..3 {
+            let issuer = Test::create_user_account(i);
+            let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+            assert_ok!(Proof::do_register_entity(issuer));
+        }
+        assert_ok!(Proof::do_declare_key_compromise(Test::create_user_account(0), 1.into()));
+        // a suspended issuer is not counted again
+        assert_ok!(Proof::do_declare_key_compromise(Test::create_user_account(0), 1.into()));
+        // an issuer can also be a verifier
+        assert_ok!(Proof::do_authorize_verifier(Test::create_user_account(1)));
+        assert_ok!(Proof::do_authorize_verifier(Test::create_user_account(3)));
+
+        assert_eq!(
+            Proof::entity_counts(),
+            EntityCounts { active_issuers: 2, suspended_issuers: 1, verifiers: 2 }
+        );
+    });
+}
+
+#[test]
+fn test_anchor_batch(){
+        build_and_execute(|| {
+        let issuer = Test::create_user_account(0);
+        let other_issuer = Test::create_user_account(1);
+        for entity in [issuer, other_issuer] {
+            let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&entity, (u32::MAX << 10).into());
+            assert_ok!(Proof::do_register_entity(entity));
+        }
+        let leaves: Vec<_> = (0..5u8).map(|i| merkle::leaf_hash(&[i; 64])).collect();
+        let root = merkle::merkle_root(&leaves).unwrap();
+
+        assert_noop!(
+            Proof::do_anchor_batch(Test::create_user_account(2), root, 1000.into()),
+            Error::<Test>::NotWhitelistEntity
+        );
+        assert_ok!(Proof::do_anchor_batch(issuer, root, 1000.into()));
+        assert_noop!(
+            Proof::do_anchor_batch(other_issuer, root, 1000.into()),
+            Error::<Test>::BatchAlreadyAnchored
+        );
+
+        let proof = merkle::merkle_proof(&leaves, 3).unwrap();
+        assert_eq!(Proof::batch_document_status(root, leaves[3], &proof), ProofStatus::Valid);
+        // a document that is not part of the batch
+        let outsider = merkle::leaf_hash(&[9u8; 64]);
+        assert_eq!(
+            Proof::batch_document_status(root, outsider, &proof),
+            ProofStatus::Invalid(InvalidReason::NotFound)
+        );
+
+        System::set_block_number(1001);
+        assert_eq!(
+            Proof::batch_document_status(root, leaves[3], &proof),
+            ProofStatus::Invalid(InvalidReason::Expired)
+        );
+    });
+}
+
+#[test]
+fn test_expiration_overflow(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let proof: ProofBytes<Test> = vec![1u8; 64].try_into().expect("proof too long");
+        assert_noop!(
+            Proof::do_store_proof(issuer, proof, U256::MAX, None, None, None, None),
+            Error::<Test>::InvalidExpiration
+        );
+        assert_noop!(
+            Proof::do_anchor_batch(issuer, H256::repeat_byte(1), U256::MAX),
+            Error::<Test>::InvalidExpiration
+        );
+        // the largest expiry block that can be reached is accepted
+        assert_ok!(Proof::do_anchor_batch(issuer, H256::repeat_byte(1), U256::MAX - 1));
+    });
+}
+
+#[test]
+fn test_revoke_batch_leaves(){
+        build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let leaves: Vec<_> = (0..5u8).map(|i| merkle::leaf_hash(&[i; 64])).collect();
+        let root = merkle::merkle_root(&leaves).unwrap();
+        assert_ok!(Proof::do_anchor_batch(issuer, root, 1000.into()));
+
+        assert_noop!(
+            Proof::do_revoke_batch_leaves(issuer, H256::repeat_byte(1), bounded_vec![leaves[1]]),
+            Error::<Test>::BatchNotFound
+        );
+        assert_noop!(
+            Proof::do_revoke_batch_leaves(Test::create_user_account(1), root, bounded_vec![leaves[1]]),
+            Error::<Test>::NotBatchIssuer
+        );
+
+        assert_ok!(Proof::do_revoke_batch_leaves(issuer, root, bounded_vec![leaves[1], leaves[2]]));
+        // revoking again only counts the new leaves
+        assert_ok!(Proof::do_revoke_batch_leaves(issuer, root, bounded_vec![leaves[2], leaves[4]]));
+        System::assert_last_event(Event::BatchLeavesRevoked { root, revoked: 1 }.into());
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = merkle::merkle_proof(&leaves, index as u32).unwrap();
+            let expected = if [1, 2, 4].contains(&index) {
+                ProofStatus::Invalid(InvalidReason::Revoked)
+            } else {
+                ProofStatus::Valid
+            };
+            assert_eq!(Proof::batch_document_status(root, *leaf, &proof), expected);
+        }
+    });
+}
+
+#[test]
+fn test_revocation_status_list(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let verifier = Test::create_user_account(1);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        assert_ok!(Proof::do_authorize_verifier(verifier));
+        let proof: ProofBytes<Test> = bounded_vec![1; 64];
+        let other: ProofBytes<Test> = bounded_vec![2; 64];
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 1000.into(), None, None, None, Some(300)));
+        assert_ok!(Proof::do_store_proof(issuer, other.clone(), 1000.into(), None, None, None, Some(301)));
+
+        assert_noop!(
+            Proof::do_update_revocation_status(Test::create_user_account(2), bounded_vec![(300, true)]),
+            Error::<Test>::NotWhitelistEntity
+        );
+        assert_ok!(Proof::do_update_revocation_status(issuer, bounded_vec![(300, true), (7, true)]));
+        System::assert_last_event(
+            Event::RevocationStatusUpdated {
+                issuer,
+                revoked: 2,
+                reinstated: 0,
+                indexes: bounded_vec![300, 7],
+            }
+            .into()
+        );
+
+        assert_eq!(Proof::proof_status(&proof), ProofStatus::Invalid(InvalidReason::Revoked));
+        assert_eq!(Proof::proof_status(&other), ProofStatus::Valid);
+        assert_noop!(Proof::do_consume_proof(verifier, proof.clone()), Error::<Test>::ProofRevoked);
+
+        // reinstated entries become valid again, and an empty chunk is not kept
+        assert_ok!(Proof::do_update_revocation_status(issuer, bounded_vec![(300, false), (7, false)]));
+        System::assert_last_event(
+            Event::RevocationStatusUpdated {
+                issuer,
+                revoked: 0,
+                reinstated: 2,
+                indexes: bounded_vec![300, 7],
+            }
+            .into()
+        );
+        assert_eq!(Proof::proof_status(&proof), ProofStatus::Valid);
+        assert!(StatusListChunks::<Test>::iter_prefix(issuer).next().is_none());
+    });
+}
+
+#[test]
+fn test_status_index_out_of_range(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let proof: ProofBytes<Test> = bounded_vec![1; 64];
+
+        assert_noop!(
+            Proof::do_store_proof(issuer, proof, 1000.into(), None, None, None, Some(1024)),
+            Error::<Test>::StatusIndexOutOfRange
+        );
+        assert_noop!(
+            Proof::do_update_revocation_status(issuer, bounded_vec![(0, true), (1024, true)]),
+            Error::<Test>::StatusIndexOutOfRange
+        );
+    });
+}
+
+#[test]
+fn test_status_index_has_one_owner(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let other_issuer = Test::create_user_account(1);
+        for entity in [issuer, other_issuer] {
+            let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&entity, (u32::MAX << 10).into());
+            assert_ok!(Proof::do_register_entity(entity));
+        }
+        let proof: ProofBytes<Test> = bounded_vec![1; 64];
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 1000.into(), None, None, None, Some(5)));
+        assert_eq!(StatusIndexOwner::<Test>::get(issuer, 5), Some(Proof::proof_key(&proof)));
+
+        // the entry is taken while the proof lives
+        assert_noop!(
+            Proof::do_store_proof(issuer, bounded_vec![2; 64], 1000.into(), None, None, None, Some(5)),
+            Error::<Test>::StatusIndexTaken
+        );
+        // a revoked entry without owner can't be given to a new proof either
+        assert_ok!(Proof::do_update_revocation_status(issuer, bounded_vec![(6, true)]));
+        assert_noop!(
+            Proof::do_store_proof(issuer, bounded_vec![2; 64], 1000.into(), None, None, None, Some(6)),
+            Error::<Test>::StatusIndexTaken
+        );
+        // status lists are per issuer
+        assert_ok!(Proof::do_store_proof(other_issuer, bounded_vec![2; 64], 1000.into(), None, None, None, Some(5)));
+    });
+}
+
+#[test]
+fn test_status_index_released_on_expiry_and_pruning(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let expiring: ProofBytes<Test> = bounded_vec![1; 64];
+        let pruned: ProofBytes<Test> = bounded_vec![2; 64];
+        assert_ok!(Proof::do_store_proof(issuer, expiring, 10.into(), None, None, None, Some(0)));
+        assert_ok!(Proof::do_store_proof(issuer, pruned.clone(), 10.into(), None, None, None, Some(1)));
+        assert_ok!(Proof::do_update_revocation_status(issuer, bounded_vec![(0, true), (1, true)]));
+
+        // a pruned proof releases its entry
+        System::set_block_number(1000);
+        assert_ok!(Proof::do_prune_expired(bounded_vec![Proof::proof_key(&pruned)]));
+        assert_eq!(StatusIndexOwner::<Test>::get(issuer, 1), None);
+        assert!(!Proof::status(&issuer, 1));
+
+        // an expired proof releases its entry once collected, cleared, to the next proof
+        assert_eq!(StatusIndexOwner::<Test>::get(issuer, 0), Some(Proof::proof_key(&[1u8; 64])));
+        Proof::on_idle(1000, Weight::MAX);
+        assert_eq!(StatusIndexOwner::<Test>::get(issuer, 0), None);
+        let reused: ProofBytes<Test> = bounded_vec![3; 64];
+        assert_ok!(Proof::do_store_proof(issuer, reused.clone(), 1000.into(), None, None, None, Some(0)));
+        assert_eq!(Proof::proof_status(&reused), ProofStatus::Valid);
+    });
+}
+
+#[test]
+fn test_expired_status_entries_are_released(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let proof: ProofBytes<Test> = bounded_vec![1; 64];
+        let other: ProofBytes<Test> = bounded_vec![2; 64];
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 10.into(), None, None, None, Some(0)));
+        assert_ok!(Proof::do_store_proof(issuer, other.clone(), 1000.into(), None, None, None, Some(1)));
+        assert_ok!(Proof::do_update_revocation_status(issuer, bounded_vec![(0, true), (1, true)]));
+        let proof_key = Proof::proof_key(&proof);
+        let other_key = Proof::proof_key(&other);
+
+        // still valid in its expiry block
+        System::set_block_number(11);
+        Proof::on_idle(11, Weight::MAX);
+        assert_eq!(StatusIndexOf::<Test>::get(proof_key), Some(0));
+
+        System::set_block_number(12);
+        Proof::on_idle(12, Weight::MAX);
+        assert_eq!(StatusIndexOf::<Test>::get(proof_key), None);
+        assert!(!StatusExpiry::<Test>::contains_key(U256::from(11), proof_key));
+        assert_eq!(StatusListChunks::<Test>::get(issuer, 0), {
+            let mut chunk = StatusChunk::default();
+            chunk[0] = 0b10;
+            chunk
+        });
+        // the unexpired proof keeps its entry
+        assert_eq!(StatusIndexOf::<Test>::get(other_key), Some(1));
+        assert_eq!(Proof::proof_status(&other), ProofStatus::Invalid(InvalidReason::Revoked));
+    });
+}
+
+#[test]
+fn test_declare_key_compromise(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let verifier = Test::create_user_account(1);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        assert_ok!(Proof::do_authorize_verifier(verifier));
+        let before: ProofBytes<Test> = bounded_vec![1; 64];
+        let during: ProofBytes<Test> = bounded_vec![2; 64];
+        assert_ok!(Proof::do_store_proof(issuer, before.clone(), 1000.into(), None, None, None, None));
+        System::set_block_number(5);
+        assert_ok!(Proof::do_store_proof(issuer, during.clone(), 1000.into(), None, None, None, None));
+
+        System::set_block_number(10);
+        assert_noop!(
+            Proof::declare_key_compromise(RuntimeOrigin::signed(verifier), issuer, 5.into()),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            Proof::do_declare_key_compromise(issuer, 11.into()),
+            Error::<Test>::CompromiseInFuture
+        );
+        assert_ok!(Proof::declare_key_compromise(RuntimeOrigin::signed(issuer), issuer, 5.into()));
+        System::assert_last_event(
+            Event::KeyCompromiseDeclared { key: issuer, since: 5.into(), until: 10.into() }.into(),
+        );
+
+        assert_eq!(Proof::proof_status(&before), ProofStatus::Valid);
+        assert_eq!(Proof::proof_status(&during), ProofStatus::Invalid(InvalidReason::KeyCompromised));
+        assert_noop!(Proof::do_consume_proof(verifier, during), Error::<Test>::ProofRevoked);
+        // the key can no longer store proofs
+        assert_noop!(
+            Proof::do_store_proof(issuer, bounded_vec![3; 64], 1000.into(), None, None, None, None),
+            Error::<Test>::NotWhitelistEntity
+        );
+    });
+}
+
+#[test]
+fn test_key_compromise_revokes_batch_documents(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let leaves: Vec<_> = (0..4u8).map(|i| merkle::leaf_hash(&[i; 64])).collect();
+        let before = merkle::merkle_root(&leaves[..2]).unwrap();
+        let during = merkle::merkle_root(&leaves[2..]).unwrap();
+        assert_ok!(Proof::do_anchor_batch(issuer, before, 1000.into()));
+        System::set_block_number(5);
+        assert_ok!(Proof::do_anchor_batch(issuer, during, 1000.into()));
+        assert_eq!(Batches::<Test>::get(during).unwrap().anchored_at, 5.into());
+
+        System::set_block_number(10);
+        assert_ok!(Proof::do_declare_key_compromise(issuer, 5.into()));
+
+        let proof = merkle::merkle_proof(&leaves[..2], 0).unwrap();
+        assert_eq!(Proof::batch_document_status(before, leaves[0], &proof), ProofStatus::Valid);
+        // every document of a batch anchored within the window is revoked
+        for index in 0..2 {
+            let proof = merkle::merkle_proof(&leaves[2..], index).unwrap();
+            assert_eq!(
+                Proof::batch_document_status(during, leaves[2 + index as usize], &proof),
+                ProofStatus::Invalid(InvalidReason::KeyCompromised)
+            );
+        }
+    });
+}
+
+#[test]
+fn test_key_compromise_window_widens(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let proof: ProofBytes<Test> = bounded_vec![1; 64];
+        System::set_block_number(3);
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 1000.into(), None, None, None, None));
+
+        System::set_block_number(10);
+        assert_ok!(Proof::declare_key_compromise(RuntimeOrigin::root(), issuer, 8.into()));
+        assert_eq!(Proof::proof_status(&proof), ProofStatus::Valid);
+
+        // a later declaration cannot narrow the window
+        System::set_block_number(12);
+        assert_ok!(Proof::do_declare_key_compromise(issuer, 2.into()));
+        assert_ok!(Proof::do_declare_key_compromise(issuer, 11.into()));
+        assert_eq!(
+            CompromisedKeys::<Test>::get(issuer),
+            Some(CompromiseWindow { since: 2.into(), until: 12.into() })
+        );
+        assert_eq!(Proof::proof_status(&proof), ProofStatus::Invalid(InvalidReason::KeyCompromised));
+    });
+}
+
+#[test]
+fn test_store_proof_commit_reveal(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let proof: ProofBytes<Test> = bounded_vec![1; 64];
+        let salt = [7; 32];
+        let commitment = Proof::commitment(&issuer, &Proof::proof_key(&proof), salt);
+
+        assert_noop!(
+            Proof::commit_proof(RuntimeOrigin::signed(Test::create_user_account(1)), commitment),
+            Error::<Test>::NotWhitelistEntity
+        );
+        assert_ok!(Proof::commit_proof(RuntimeOrigin::signed(issuer), commitment));
+        System::assert_last_event(Event::ProofCommitted { issuer, commitment }.into());
+        assert_noop!(
+            Proof::commit_proof(RuntimeOrigin::signed(issuer), commitment),
+            Error::<Test>::CommitmentAlreadyExist
+        );
+
+        // the commitment can only be revealed in a later block
+        assert_noop!(
+            Proof::store_proof(RuntimeOrigin::signed(issuer), proof.clone(), 1000.into(), None, None, None, None, salt),
+            Error::<Test>::CommitmentNotMatured
+        );
+        System::set_block_number(2);
+        assert_noop!(
+            Proof::store_proof(RuntimeOrigin::signed(issuer), proof.clone(), 1000.into(), None, None, None, None, [8; 32]),
+            Error::<Test>::CommitmentNotFound
+        );
+        assert_ok!(Proof::store_proof(RuntimeOrigin::signed(issuer), proof.clone(), 1000.into(), None, None, None, None, salt));
+        assert!(!ProofCommitments::<Test>::contains_key(issuer, commitment));
+        assert_eq!(Proof::proof_status(&proof), ProofStatus::Valid);
+    });
+}
+
+#[test]
+fn test_revealed_proof_cannot_be_squatted(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let squatter = Test::create_user_account(1);
+        for entity in [issuer, squatter] {
+            let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&entity, (u32::MAX << 10).into());
+            assert_ok!(Proof::do_register_entity(entity));
+        }
+        let proof: ProofBytes<Test> = bounded_vec![1; 64];
+        let root = H256::repeat_byte(1);
+        let salt = [7; 32];
+        assert_ok!(Proof::commit_proof(
+            RuntimeOrigin::signed(issuer),
+            Proof::commitment(&issuer, &Proof::proof_key(&proof), salt)
+        ));
+        assert_ok!(Proof::commit_proof(RuntimeOrigin::signed(issuer), Proof::commitment(&issuer, &root, salt)));
+
+        // the squatter learns the proof, the root and the salt from the pending reveals
+        System::set_block_number(2);
+        assert_noop!(
+            Proof::store_proof(RuntimeOrigin::signed(squatter), proof.clone(), 1000.into(), None, None, None, None, salt),
+            Error::<Test>::CommitmentNotFound
+        );
+        assert_noop!(
+            Proof::anchor_batch(RuntimeOrigin::signed(squatter), root, 1000.into(), salt),
+            Error::<Test>::CommitmentNotFound
+        );
+        assert_ok!(Proof::commit_proof(
+            RuntimeOrigin::signed(squatter),
+            Proof::commitment(&squatter, &Proof::proof_key(&proof), salt)
+        ));
+        assert_noop!(
+            Proof::store_proof(RuntimeOrigin::signed(squatter), proof.clone(), 1000.into(), None, None, None, None, salt),
+            Error::<Test>::CommitmentNotMatured
+        );
+
+        assert_ok!(Proof::store_proof(RuntimeOrigin::signed(issuer), proof.clone(), 1000.into(), None, None, None, None, salt));
+        assert_ok!(Proof::anchor_batch(RuntimeOrigin::signed(issuer), root, 1000.into(), salt));
+        assert_eq!(Proofs::<Test>::get(Proof::proof_key(&proof)).map(|meta| meta.issuer), Some(issuer));
+        assert_eq!(Batches::<Test>::get(root).map(|batch| batch.issuer), Some(issuer));
+    });
+}
+
+#[test]
+fn test_stale_commitment_is_removed(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let proof: ProofBytes<Test> = bounded_vec![1; 64];
+        let salt = [7; 32];
+        let stale = Proof::commitment(&issuer, &Proof::proof_key(&proof), salt);
+        let fresh = H256::repeat_byte(1);
+        assert_ok!(Proof::commit_proof(RuntimeOrigin::signed(issuer), stale));
+        System::set_block_number(5);
+        assert_ok!(Proof::commit_proof(RuntimeOrigin::signed(issuer), fresh));
+
+        // the commitment TTL is 20 blocks, after which the commitment cannot be revealed
+        System::set_block_number(22);
+        assert_noop!(
+            Proof::store_proof(RuntimeOrigin::signed(issuer), proof.clone(), 1000.into(), None, None, None, None, salt),
+            Error::<Test>::CommitmentExpired
+        );
+        let store = Call::store_proof {
+            proof,
+            expiration: 1000.into(),
+            max_uses: None,
+            holder: None,
+            attribute_root: None,
+            status_index: None,
+            salt,
+        };
+        assert_eq!(
+            validate_issuance(issuer, store),
+            Err(extension::ValidityError::CommitmentNotFound.into())
+        );
+
+        // and it is removed once the chain is idle, the fresh one is kept
+        Proof::on_idle(22, Weight::MAX);
+        assert!(!ProofCommitments::<Test>::contains_key(issuer, stale));
+        assert!(ProofCommitments::<Test>::contains_key(issuer, fresh));
+        assert_eq!(CommitmentGcCursor::<Test>::get(), 22.into());
+    });
+}
+
+fn validate_issuance(who: u64, call: Call<Test>) -> TransactionValidity {
+    use sp_runtime::traits::TransactionExtension;
+    extension::ValidateProofIssuance::<Test>::new()
+        .validate_only(
+            RuntimeOrigin::signed(who),
+            &RuntimeCall::Proof(call),
+            &Default::default(),
+            0,
+            TransactionSource::External,
+            0,
+        )
+        .map(|(validity, _, _)| validity)
+}
+
+#[test]
+fn test_extension_rejects_invalid_issuance(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let proof: ProofBytes<Test> = bounded_vec![1; 64];
+        let salt = [7; 32];
+        let store = Call::store_proof {
+            proof: proof.clone(),
+            expiration: 1000.into(),
+            max_uses: None,
+            holder: None,
+            attribute_root: None,
+            status_index: None,
+            salt,
+        };
+        let rejected = |error: extension::ValidityError| -> TransactionValidity { Err(error.into()) };
+
+        // not committed, then committed in the block the transaction would be included in
+        assert_eq!(validate_issuance(issuer, store.clone()), rejected(extension::ValidityError::CommitmentNotFound));
+        assert_ok!(Proof::do_commit_proof(issuer, Proof::commitment(&issuer, &Proof::proof_key(&proof), salt)));
+        assert_eq!(validate_issuance(issuer, store.clone()), rejected(extension::ValidityError::CommitmentNotFound));
+
+        System::set_block_number(2);
+        assert_ok!(validate_issuance(issuer, store.clone()));
+        assert_eq!(
+            validate_issuance(Test::create_user_account(1), Call::commit_proof { commitment: H256::repeat_byte(1) }),
+            rejected(extension::ValidityError::NotWhitelistEntity)
+        );
+
+        assert_ok!(Proof::do_store_proof(issuer, proof, 1000.into(), None, None, None, None));
+        assert_eq!(validate_issuance(issuer, store), rejected(extension::ValidityError::ProofAlreadyExist));
+    });
+}
+
+#[test]
+fn test_extension_tags_and_prioritizes_issuance(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let root = H256::repeat_byte(1);
+        let salt = [7; 32];
+        assert_ok!(Proof::do_commit_proof(issuer, Proof::commitment(&issuer, &root, salt)));
+        assert_ok!(Proof::do_commit_proof(issuer, Proof::commitment(&issuer, &root, [8; 32])));
+        System::set_block_number(2);
+
+        // two pending anchors of the same root provide the same tag, the pool keeps one of them
+        let first = validate_issuance(issuer, Call::anchor_batch { root, expiration: 1000.into(), salt }).unwrap();
+        let second =
+            validate_issuance(issuer, Call::anchor_batch { root, expiration: 10.into(), salt: [8; 32] }).unwrap();
+        assert_eq!(first.provides, second.provides);
+        assert_eq!(first.priority, 100);
+
+        // other calls are left to the rest of the pipeline
+        let other = validate_issuance(issuer, Call::authorize_verifier { verifier: issuer }).unwrap();
+        assert_eq!(other, ValidTransaction::default());
+    });
+}
+
+/// Whether `call` from `who` is drawn from the quota, running the extension around a dispatch
+/// ending with `result` as a block would.
+fn drawn_from_quota_with(who: u64, call: RuntimeCall, result: DispatchResult) -> bool {
+    use sp_runtime::traits::TransactionExtension;
+    type Extension = extension::ChargeIssuanceQuota<Test, ()>;
+    let (pre, _) = Extension::new(())
+        .validate_and_prepare(RuntimeOrigin::signed(who), &call, &Default::default(), 0, 0)
+        .unwrap();
+    let drawn = matches!(pre, extension::QuotaOrCharge::Quota(payer) if payer == who);
+    Extension::post_dispatch_details(pre, &Default::default(), &Default::default(), 0, &result)
+        .unwrap();
+    drawn
+}
+
+/// Whether `call` from `who` is drawn from the quota, dispatching successfully.
+fn drawn_from_quota(who: u64, call: RuntimeCall) -> bool {
+    drawn_from_quota_with(who, call, Ok(()))
+}
+
+#[test]
+fn test_issuance_quota(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let commit = RuntimeCall::Proof(Call::commit_proof { commitment: H256::repeat_byte(1) });
+
+        assert_eq!(Proof::remaining_quota(&issuer), 2);
+        // a failed call is not paid for by the quota
+        assert!(drawn_from_quota_with(issuer, commit.clone(), Err(Error::<Test>::CommitmentAlreadyExist.into())));
+        assert_eq!(Proof::remaining_quota(&issuer), 2);
+        assert!(drawn_from_quota(issuer, commit.clone()));
+        assert!(drawn_from_quota(issuer, commit.clone()));
+        assert_eq!(Proof::remaining_quota(&issuer), 0);
+        // the entity pays fees once the quota of the era is used up
+        assert!(!drawn_from_quota(issuer, commit.clone()));
+
+        System::set_block_number(10);
+        assert_eq!(Proof::remaining_quota(&issuer), 2);
+        assert!(drawn_from_quota(issuer, commit));
+        assert_eq!(QuotaUsed::<Test>::get(issuer), Some(QuotaUsage { era: 1, used: 1 }));
+    });
+}
+
+#[test]
+fn test_issuance_quota_only_for_issuance_of_entities(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let other = Test::create_user_account(1);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let commit = RuntimeCall::Proof(Call::commit_proof { commitment: H256::repeat_byte(1) });
+
+        assert!(!drawn_from_quota(other, commit.clone()));
+        assert!(!drawn_from_quota(issuer, RuntimeCall::System(frame_system::Call::remark { remark: vec![] })));
+        // only issuance is drawn from the quota, not every call to the pallet
+        let proof: ProofBytes<Test> = bounded_vec![1; 64];
+        assert!(!drawn_from_quota(issuer, RuntimeCall::Proof(Call::consume_proof { proof })));
+        assert!(drawn_from_quota(issuer, RuntimeCall::Proof(Call::update_revocation_status { updates: bounded_vec![(0, true)] })));
+
+        // a suspended entity pays fees again
+        assert_ok!(Proof::do_declare_key_compromise(issuer, 1.into()));
+        assert!(!drawn_from_quota(issuer, commit));
+        assert_eq!(QuotaUsed::<Test>::get(issuer), Some(QuotaUsage { era: 0, used: 1 }));
+    });
+}
+
+#[test]
+fn test_prune_expired_proofs(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let verifier = Test::create_user_account(1);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        assert_ok!(Proof::do_authorize_verifier(verifier));
+        let proof: ProofBytes<Test> = bounded_vec![1; 64];
+        let inside: ProofBytes<Test> = bounded_vec![2; 64];
+        let fresh: ProofBytes<Test> = bounded_vec![3; 64];
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 10.into(), None, None, None, Some(0)));
+        assert_ok!(Proof::do_store_proof(issuer, inside.clone(), 10.into(), None, None, None, None));
+        assert_ok!(Proof::do_store_proof(issuer, fresh.clone(), 1000.into(), None, None, None, None));
+        assert_ok!(Proof::do_update_revocation_status(issuer, bounded_vec![(0, true)]));
+        let checkpoint: CheckpointCode = b"NRT-T1".to_vec().try_into().unwrap();
+        assert_ok!(Proof::do_record_crossing(verifier, proof.clone(), CrossingDirection::Entry, checkpoint.clone()));
+        assert_ok!(Proof::do_record_crossing(verifier, proof.clone(), CrossingDirection::Exit, checkpoint.clone()));
+        assert_ok!(Proof::do_record_crossing(verifier, inside.clone(), CrossingDirection::Entry, checkpoint));
+        let proof_keys: BoundedVec<_, _> =
+            bounded_vec![Proof::proof_key(&proof), Proof::proof_key(&inside), Proof::proof_key(&fresh)];
+
+        // expired at block 11, but a challenge issued in its last block stays open until 21
+        System::set_block_number(21);
+        assert_ok!(Proof::prune_expired(RuntimeOrigin::none(), proof_keys.clone()));
+        System::assert_last_event(Event::ProofsPruned { proof_keys: bounded_vec![] }.into());
+
+        System::set_block_number(22);
+        assert_noop!(
+            Proof::prune_expired(RuntimeOrigin::signed(issuer), proof_keys.clone()),
+            DispatchError::BadOrigin
+        );
+        assert_ok!(Proof::prune_expired(RuntimeOrigin::none(), proof_keys));
+        System::assert_last_event(
+            Event::ProofsPruned { proof_keys: bounded_vec![Proof::proof_key(&proof)] }.into()
+        );
+
+        let proof_key = Proof::proof_key(&proof);
+        assert!(!Proofs::<Test>::contains_key(proof_key));
+        assert!(!ProofsByIssuer::<Test>::contains_key(issuer, proof_key));
+        assert!(!ProofIssuedAt::<Test>::contains_key(proof_key));
+        assert!(!StatusIndexOf::<Test>::contains_key(proof_key));
+        assert!(!StatusListChunks::<Test>::contains_key(issuer, 0));
+        assert!(CrossingLog::<Test>::get(proof_key).is_empty());
+        assert_eq!(ProofCountByIssuer::<Test>::get(issuer), 2);
+        // the holder still in the country has to be able to leave with it
+        assert!(Proofs::<Test>::contains_key(Proof::proof_key(&inside)));
+    });
+}
+
+#[test]
+fn test_validate_unsigned_prune(){
+    build_and_execute(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        let proof: ProofBytes<Test> = bounded_vec![1; 64];
+        let fresh: ProofBytes<Test> = bounded_vec![2; 64];
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 10.into(), None, None, None, None));
+        assert_ok!(Proof::do_store_proof(issuer, fresh.clone(), 1000.into(), None, None, None, None));
+        System::set_block_number(22);
+
+        let validate = |proof_keys: Vec<H256>| {
+            let call = Call::prune_expired { proof_keys: proof_keys.try_into().unwrap() };
+            <Proof as ValidateUnsigned>::validate_unsigned(TransactionSource::External, &call)
+        };
+        let stale = Err(InvalidTransaction::Stale.into());
+        assert_eq!(validate(vec![]), Err(InvalidTransaction::Call.into()));
+        assert_eq!(validate(vec![Proof::proof_key(&proof), Proof::proof_key(&fresh)]), stale);
+        assert_eq!(validate(vec![H256::repeat_byte(1)]), stale);
+        assert_eq!(
+            <Proof as ValidateUnsigned>::validate_unsigned(
+                TransactionSource::External,
+                &Call::commit_proof { commitment: H256::repeat_byte(1) },
+            ),
+            Err(InvalidTransaction::Call.into())
+        );
+
+        let valid = validate(vec![Proof::proof_key(&proof)]).unwrap();
+        assert_eq!(valid.priority, 10);
+        assert_eq!(valid.provides, vec![("ProofPruning", U256::from(22)).encode()]);
+
+        // one pruning per block
+        assert_ok!(Proof::prune_expired(RuntimeOrigin::none(), bounded_vec![H256::repeat_byte(1)]));
+        assert_eq!(validate(vec![Proof::proof_key(&proof)]), stale);
+    });
+}
+
+#[test]
+fn test_offchain_worker_submits_pruning(){
+    let mut ext = new_test_ext();
+    let (offchain, _) = TestOffchainExt::new();
+    let (pool, pool_state) = TestTransactionPoolExt::new();
+    ext.register_extension(OffchainDbExt::new(offchain.clone()));
+    ext.register_extension(OffchainWorkerExt::new(offchain));
+    ext.register_extension(TransactionPoolExt::new(pool));
+
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        let issuer = Test::create_user_account(0);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        for i in 0..5 {
+            let proof: ProofBytes<Test> = bounded_vec![i; 64];
+            assert_ok!(Proof::do_store_proof(issuer, proof, 10.into(), None, None, None, None));
+        }
+        let submitted = || {
+            let tx = pool_state.write().transactions.pop().expect("a transaction was submitted");
+            let tx = frame_system::mocking::MockUncheckedExtrinsic::<Test>::decode(&mut &*tx).unwrap();
+            match tx.function {
+                RuntimeCall::Proof(Call::prune_expired { proof_keys }) => proof_keys,
+                call => panic!("unexpected call {call:?}"),
+            }
+        };
+
+        Proof::offchain_worker(21);
+        assert!(pool_state.read().transactions.is_empty());
+
+        // at most MaxPrunePerBlock per transaction, the rest is left for the next block
+        System::set_block_number(22);
+        Proof::offchain_worker(22);
+        let proof_keys = submitted();
+        assert_eq!(proof_keys.len(), 4);
+        assert_ok!(Proof::prune_expired(RuntimeOrigin::none(), proof_keys));
+
+        System::set_block_number(23);
+        Proof::offchain_worker(23);
+        let proof_keys = submitted();
+        assert_eq!(proof_keys.len(), 1);
+        assert_ok!(Proof::prune_expired(RuntimeOrigin::none(), proof_keys));
+        assert_eq!(ProofCountByIssuer::<Test>::get(issuer), 0);
+
+        Proof::do_try_state().expect("pallet invariants must hold");
+    });
+}
+
+/// Decode the offchain index entry under `key`, once the externalities are persisted.
+fn indexed<V: Decode>(ext: &sp_io::TestExternalities, key: &[u8]) -> Option<V> {
+    ext.offchain_db().get(key).map(|value| V::decode(&mut &*value).unwrap())
+}
+
+/// Id under which the records of the block being executed are written.
+fn current_block_id() -> [u8; 32] {
+    indexing::block_id(&System::parent_hash(), &System::digest())
+}
+
+/// The records written to the offchain index for `block`, in the order of the events.
+fn indexed_records(ext: &mut sp_io::TestExternalities, block: u64) -> Vec<BlockRecord<u64, H256>> {
+    let block_id = ext.execute_with(current_block_id);
+    let length = indexed::<u32>(ext, &indexing::block_events_len_key(block.into(), &block_id)).unwrap_or(0);
+    (0..length)
+        .filter_map(|n| indexed(ext, &indexing::block_event_key(block.into(), &block_id, n)))
+        .collect()
+}
+
+/// What happened to proofs in `block`, as written to the offchain index, in the order of the
+/// events.
+fn indexed_events(ext: &mut sp_io::TestExternalities, block: u64) -> Vec<(H256, ProofEvent<u64>)> {
+    indexed_records(ext, block)
+        .into_iter()
+        .flat_map(|record| match record {
+            BlockRecord::Proofs(events) => events,
+            BlockRecord::KeyCompromised { .. } => Vec::new(),
+        })
+        .collect()
+}
+
+#[test]
+fn test_offchain_index_of_stored_proofs(){
+    let mut ext = new_test_ext();
+    let (issuer, other) = (Test::create_user_account(0), Test::create_user_account(1));
+    let proofs: Vec<ProofBytes<Test>> = vec![bounded_vec![1; 64], bounded_vec![2; 64], bounded_vec![3; 64]];
+    let mut event_indexes = Vec::new();
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        for entity in [issuer, other] {
+            let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&entity, (u32::MAX << 10).into());
+            assert_ok!(Proof::do_register_entity(entity));
+        }
+        for (entity, proof) in [(issuer, &proofs[0]), (other, &proofs[1]), (issuer, &proofs[2])] {
+            assert_ok!(Proof::do_store_proof(entity, proof.clone(), 10.into(), None, None, None, None));
+            event_indexes.push(System::event_count() - 1);
+        }
+    });
+    ext.persist_offchain_overlay();
+
+    // under the index of the `ProofStored` event
+    let block_id = ext.execute_with(current_block_id);
+    assert_eq!(
+        indexed(&ext, &indexing::block_event_key(1.into(), &block_id, event_indexes[1])),
+        Some(BlockRecord::Proofs(vec![(Proof::proof_key(&proofs[1]), ProofEvent::Stored { issuer: other })]))
+    );
+    assert_eq!(
+        indexed_events(&mut ext, 1),
+        vec![
+            (Proof::proof_key(&proofs[0]), ProofEvent::Stored { issuer }),
+            (Proof::proof_key(&proofs[1]), ProofEvent::Stored { issuer: other }),
+            (Proof::proof_key(&proofs[2]), ProofEvent::Stored { issuer }),
+        ]
+    );
+}
+
+#[test]
+fn test_offchain_index_of_proof_history(){
+    let mut ext = new_test_ext();
+    let (issuer, verifier) = (Test::create_user_account(0), Test::create_user_account(1));
+    let proof: ProofBytes<Test> = bounded_vec![1; 64];
+    let proof_key = Proof::proof_key(&proof);
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        assert_ok!(Proof::do_authorize_verifier(verifier));
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 10.into(), None, None, None, None));
+
+        System::set_block_number(2);
+        assert_ok!(Proof::do_consume_proof(verifier, proof.clone()));
+        let checkpoint: CheckpointCode = b"NRT-T1".to_vec().try_into().unwrap();
+        assert_ok!(Proof::do_record_crossing(verifier, proof.clone(), CrossingDirection::Exit, checkpoint));
+        // a failed call leaves nothing behind
+        assert!(Proof::do_consume_proof(issuer, proof.clone()).is_err());
+    });
+    ext.persist_offchain_overlay();
+
+    assert_eq!(indexed_events(&mut ext, 1), vec![(proof_key, ProofEvent::Stored { issuer })]);
+    assert_eq!(
+        indexed_events(&mut ext, 2),
+        vec![
+            (proof_key, ProofEvent::Consumed { verifier }),
+            (proof_key, ProofEvent::CrossingRecorded { verifier, direction: CrossingDirection::Exit }),
+        ]
+    );
+}
+
+#[test]
+fn test_offchain_index_of_pruned_proofs(){
+    let mut ext = new_test_ext();
+    let issuer = Test::create_user_account(0);
+    let proofs: Vec<ProofBytes<Test>> = vec![bounded_vec![1; 64], bounded_vec![2; 64]];
+    let proof_keys: Vec<H256> = proofs.iter().map(|proof| Proof::proof_key(proof)).collect();
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        for proof in &proofs {
+            assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 10.into(), None, None, None, None));
+        }
+
+        // the proofs pruned together are written under the one `ProofsPruned` event
+        System::set_block_number(22);
+        assert_ok!(Proof::prune_expired(RuntimeOrigin::none(), proof_keys.clone().try_into().unwrap()));
+        assert_ok!(Proof::do_store_proof(issuer, proofs[0].clone(), 10.into(), None, None, None, None));
+        Proof::do_try_state().expect("pallet invariants must hold");
+    });
+    ext.persist_offchain_overlay();
+
+    assert_eq!(
+        indexed_events(&mut ext, 22),
+        vec![
+            (proof_keys[0], ProofEvent::Pruned),
+            (proof_keys[1], ProofEvent::Pruned),
+            (proof_keys[0], ProofEvent::Stored { issuer }),
+        ]
+    );
+}
+
+#[test]
+fn test_offchain_index_of_revocations(){
+    let mut ext = new_test_ext();
+    let issuer = Test::create_user_account(0);
+    let proof: ProofBytes<Test> = bounded_vec![1; 64];
+    let proof_key = Proof::proof_key(&proof);
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        let _ = <mock::Test as pallet::Config>::NativeBalance::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+        assert_ok!(Proof::do_store_proof(issuer, proof.clone(), 10.into(), None, None, None, Some(5)));
+
+        System::set_block_number(2);
+        // entries without a proof are not recorded
+        assert_ok!(Proof::do_update_revocation_status(issuer, bounded_vec![(5, true), (6, true)]));
+        assert_ok!(Proof::do_update_revocation_status(issuer, bounded_vec![(5, false)]));
+        assert_ok!(Proof::do_declare_key_compromise(issuer, 1.into()));
+    });
+    ext.persist_offchain_overlay();
+
+    assert_eq!(
+        indexed_records(&mut ext, 2),
+        vec![
+            BlockRecord::Proofs(vec![(proof_key, ProofEvent::Revoked)]),
+            BlockRecord::Proofs(vec![(proof_key, ProofEvent::Reinstated)]),
+            BlockRecord::KeyCompromised { issuer, since: 1.into(), until: 2.into() },
+        ]
+    );
+}
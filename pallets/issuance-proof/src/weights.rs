@@ -1,25 +1,14 @@
-
-//! Autogenerated weights for `pallet_issuance_proof`
+//! Weights for `pallet_issuance_proof`
 //!
-//! THIS FILE WAS AUTO-GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION 50.0.0
-//! DATE: 2025-09-26, STEPS: `50`, REPEAT: `20`, LOW RANGE: `[]`, HIGH RANGE: `[]`
-//! WORST CASE MAP SIZE: `1000000`
-//! HOSTNAME: `TW-ZIV-Y-CHANG`, CPU: `<UNKNOWN>`
-//! WASM-EXECUTION: `Compiled`, CHAIN: `None`, DB CACHE: 1024
-
-// Executed Command:
-// frame-omni-bencher
-// v1
-// benchmark
-// pallet
-// --runtime
-// ./target/release/wbuild/solochain-template-runtime/solochain_template_runtime.compact.compressed.wasm
-// --pallet
-// pallet-issuance-proof
-// --extrinsic
-// 
-// --output
-// pallets/issuance-proof/src/weights/issuance-proof.rs
+//! The benchmarks in `benchmarking.rs` have not been run on reference hardware for this version
+//! of the pallet, so the execution times below are estimates and no measured proof size is
+//! given. The estimated proof sizes are computed the way the benchmark CLI does in
+//! `MaxEncodedLen` mode: every storage read counts with the maximum encoded length of its key and
+//! value plus the trie nodes above it (`added`), and the reads of a call are summed.
+//!
+//! Regenerate this file once the benchmarks can be run:
+//! `frame-omni-bencher v1 benchmark pallet --runtime <runtime wasm> --pallet pallet-issuance-proof
+//! --extrinsic "*" --output pallets/issuance-proof/src/weights.rs`
 
 #![cfg_attr(rustfmt, rustfmt_skip)]
 #![allow(unused_parens)]
@@ -32,42 +21,416 @@ use core::marker::PhantomData;
 /// Weight functions needed for pallet_template.
 pub trait WeightInfo {
 	fn register_entity() -> Weight;
-	fn store_proof() -> Weight;
+	fn store_proof(l: u32, ) -> Weight;
+	fn authorize_verifier() -> Weight;
+	fn consume_proof(l: u32, ) -> Weight;
+	fn record_crossing(l: u32, ) -> Weight;
+	fn issue_challenge(l: u32, ) -> Weight;
+	fn complete_challenge() -> Weight;
+	fn store_composite_proof(c: u32, l: u32, ) -> Weight;
+	fn rekey_proof() -> Weight;
+	fn anchor_batch() -> Weight;
+	fn revoke_batch_leaves(n: u32, ) -> Weight;
+	fn update_revocation_status(n: u32, ) -> Weight;
+	fn release_expired_status() -> Weight;
+	fn remove_stale_commitment() -> Weight;
+	fn remove_expired_challenge() -> Weight;
+	fn declare_key_compromise() -> Weight;
+	fn commit_proof() -> Weight;
+	fn prune_expired(n: u32, ) -> Weight;
 }
 
 /// Weights for pallet_template using the Substrate node and recommended hardware.
 pub struct IssuanceProofWeightInfo<T>(PhantomData<T>);
 impl<T: frame_system::Config> WeightInfo for IssuanceProofWeightInfo<T> {
+	/// Storage: `Proof::WhitelistEntity` (r:1 w:1)
+	/// Proof: `Proof::WhitelistEntity` (`max_values`: None, `max_size`: Some(98), added: 2573, mode: `MaxEncodedLen`)
 	/// Storage: `System::Account` (r:1 w:1)
 	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
 	/// Storage: `Balances::Holds` (r:1 w:1)
 	/// Proof: `Balances::Holds` (`max_values`: None, `max_size`: Some(67), added: 2542, mode: `MaxEncodedLen`)
-	/// Storage: `Proof::WhitelistEntity` (r:0 w:1)
-	/// Proof: `Proof::WhitelistEntity` (`max_values`: None, `max_size`: Some(49), added: 2524, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::EntityCount` (r:1 w:1)
+	/// Proof: `Proof::EntityCount` (`max_values`: Some(1), `max_size`: Some(12), added: 507, mode: `MaxEncodedLen`)
 	fn register_entity() -> Weight {
 		// Proof Size summary in bytes:
-		//  Measured:  `103`
-		//  Estimated: `3593`
-		// Minimum execution time: 38_000_000 picoseconds.
+		//  Estimated: `8225`
 		Weight::from_parts(42_000_000, 0)
-			.saturating_add(Weight::from_parts(0, 3593))
+			.saturating_add(Weight::from_parts(0, 8225))
+			.saturating_add(T::DbWeight::get().reads(4))
+			.saturating_add(T::DbWeight::get().writes(4))
+	}
+	/// Storage: `Proof::ProofCommitments` (r:1 w:1)
+	/// Proof: `Proof::ProofCommitments` (`max_values`: None, `max_size`: Some(112), added: 2587, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::CommitmentExpiry` (r:0 w:1)
+	/// Proof: `Proof::CommitmentExpiry` (`max_values`: None, `max_size`: Some(120), added: 2595, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::Proofs` (r:1 w:1)
+	/// Proof: `Proof::Proofs` (`max_values`: None, `max_size`: Some(167), added: 2642, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::RekeyOngoing` (r:1 w:0)
+	/// Proof: `Proof::RekeyOngoing` (`max_values`: Some(1), `max_size`: Some(1), added: 496, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::IssuanceProof` (r:1 w:0)
+	/// Proof: `Proof::IssuanceProof` (`max_values`: None, `max_size`: Some(4178), added: 6653, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::WhitelistEntity` (r:1 w:0)
+	/// Proof: `Proof::WhitelistEntity` (`max_values`: None, `max_size`: Some(98), added: 2573, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusIndexOwner` (r:1 w:1)
+	/// Proof: `Proof::StatusIndexOwner` (`max_values`: None, `max_size`: Some(92), added: 2567, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusListChunks` (r:1 w:0)
+	/// Proof: `Proof::StatusListChunks` (`max_values`: None, `max_size`: Some(92), added: 2567, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofsByIssuer` (r:0 w:1)
+	/// Proof: `Proof::ProofsByIssuer` (`max_values`: None, `max_size`: Some(80), added: 2555, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofCountByIssuer` (r:1 w:1)
+	/// Proof: `Proof::ProofCountByIssuer` (`max_values`: None, `max_size`: Some(52), added: 2527, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofIssuedAt` (r:0 w:1)
+	/// Proof: `Proof::ProofIssuedAt` (`max_values`: None, `max_size`: Some(64), added: 2539, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusExpiry` (r:1 w:1)
+	/// Proof: `Proof::StatusExpiry` (`max_values`: None, `max_size`: Some(72), added: 2547, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusGcCursor` (r:0 w:1)
+	/// Proof: `Proof::StatusGcCursor` (`max_values`: Some(1), `max_size`: Some(32), added: 527, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusIndexOf` (r:0 w:1)
+	/// Proof: `Proof::StatusIndexOf` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	/// The range of component `l` is `[1, 4096]`.
+	fn store_proof(l: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `21063 + l * (1 ±0)`
+		Weight::from_parts(14_871_000, 0)
+			.saturating_add(Weight::from_parts(0, 21063))
+			.saturating_add(Weight::from_parts(2_183, 0).saturating_mul(l.into()))
+			.saturating_add(T::DbWeight::get().reads(9))
+			.saturating_add(T::DbWeight::get().writes(10))
+			.saturating_add(Weight::from_parts(0, 1).saturating_mul(l.into()))
+	}
+	/// Storage: `Proof::VerifierEntity` (r:1 w:1)
+	/// Proof: `Proof::VerifierEntity` (`max_values`: None, `max_size`: Some(49), added: 2524, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::EntityCount` (r:1 w:1)
+	/// Proof: `Proof::EntityCount` (`max_values`: Some(1), `max_size`: Some(12), added: 507, mode: `MaxEncodedLen`)
+	fn authorize_verifier() -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `3031`
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 3031))
 			.saturating_add(T::DbWeight::get().reads(2))
-			.saturating_add(T::DbWeight::get().writes(3))
+			.saturating_add(T::DbWeight::get().writes(2))
 	}
+	/// Storage: `Proof::VerifierEntity` (r:1 w:0)
+	/// Proof: `Proof::VerifierEntity` (`max_values`: None, `max_size`: Some(49), added: 2524, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::RekeyOngoing` (r:1 w:0)
+	/// Proof: `Proof::RekeyOngoing` (`max_values`: Some(1), `max_size`: Some(1), added: 496, mode: `MaxEncodedLen`)
 	/// Storage: `Proof::IssuanceProof` (r:1 w:1)
-	/// Proof: `Proof::IssuanceProof` (`max_values`: None, `max_size`: Some(594), added: 3069, mode: `MaxEncodedLen`)
+	/// Proof: `Proof::IssuanceProof` (`max_values`: None, `max_size`: Some(4178), added: 6653, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofsByIssuer` (r:0 w:1)
+	/// Proof: `Proof::ProofsByIssuer` (`max_values`: None, `max_size`: Some(80), added: 2555, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofCountByIssuer` (r:1 w:1)
+	/// Proof: `Proof::ProofCountByIssuer` (`max_values`: None, `max_size`: Some(52), added: 2527, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::Proofs` (r:1 w:1)
+	/// Proof: `Proof::Proofs` (`max_values`: None, `max_size`: Some(167), added: 2642, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusIndexOf` (r:1 w:0)
+	/// Proof: `Proof::StatusIndexOf` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusListChunks` (r:1 w:0)
+	/// Proof: `Proof::StatusListChunks` (`max_values`: None, `max_size`: Some(92), added: 2567, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::CompromisedKeys` (r:1 w:0)
+	/// Proof: `Proof::CompromisedKeys` (`max_values`: None, `max_size`: Some(112), added: 2587, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofIssuedAt` (r:1 w:0)
+	/// Proof: `Proof::ProofIssuedAt` (`max_values`: None, `max_size`: Some(64), added: 2539, mode: `MaxEncodedLen`)
+	/// The range of component `l` is `[1, 4096]`.
+	fn consume_proof(l: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `20950 + l * (1 ±0)`
+		Weight::from_parts(16_942_000, 0)
+			.saturating_add(Weight::from_parts(0, 20950))
+			.saturating_add(Weight::from_parts(1_352, 0).saturating_mul(l.into()))
+			.saturating_add(T::DbWeight::get().reads(9))
+			.saturating_add(T::DbWeight::get().writes(4))
+			.saturating_add(Weight::from_parts(0, 1).saturating_mul(l.into()))
+	}
+	/// Storage: `Proof::VerifierEntity` (r:1 w:0)
+	/// Proof: `Proof::VerifierEntity` (`max_values`: None, `max_size`: Some(49), added: 2524, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::Proofs` (r:1 w:0)
+	/// Proof: `Proof::Proofs` (`max_values`: None, `max_size`: Some(167), added: 2642, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::RekeyOngoing` (r:1 w:0)
+	/// Proof: `Proof::RekeyOngoing` (`max_values`: Some(1), `max_size`: Some(1), added: 496, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::IssuanceProof` (r:1 w:0)
+	/// Proof: `Proof::IssuanceProof` (`max_values`: None, `max_size`: Some(4178), added: 6653, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusIndexOf` (r:1 w:0)
+	/// Proof: `Proof::StatusIndexOf` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusListChunks` (r:1 w:0)
+	/// Proof: `Proof::StatusListChunks` (`max_values`: None, `max_size`: Some(92), added: 2567, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::CompromisedKeys` (r:1 w:0)
+	/// Proof: `Proof::CompromisedKeys` (`max_values`: None, `max_size`: Some(112), added: 2587, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofIssuedAt` (r:1 w:0)
+	/// Proof: `Proof::ProofIssuedAt` (`max_values`: None, `max_size`: Some(64), added: 2539, mode: `MaxEncodedLen`)
+	/// Storage: `Timestamp::Now` (r:1 w:0)
+	/// Proof: `Timestamp::Now` (`max_values`: Some(1), `max_size`: Some(8), added: 503, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::CrossingLog` (r:1 w:1)
+	/// Proof: `Proof::CrossingLog` (`max_values`: None, `max_size`: Some(3362), added: 5837, mode: `MaxEncodedLen`)
+	/// The range of component `l` is `[1, 4096]`.
+	fn record_crossing(l: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `24763 + l * (1 ±0)`
+		Weight::from_parts(27_905_000, 0)
+			.saturating_add(Weight::from_parts(0, 24763))
+			.saturating_add(Weight::from_parts(1_341, 0).saturating_mul(l.into()))
+			.saturating_add(T::DbWeight::get().reads(10))
+			.saturating_add(T::DbWeight::get().writes(1))
+			.saturating_add(Weight::from_parts(0, 1).saturating_mul(l.into()))
+	}
+	/// Storage: `Proof::VerifierEntity` (r:1 w:0)
+	/// Proof: `Proof::VerifierEntity` (`max_values`: None, `max_size`: Some(49), added: 2524, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::RekeyOngoing` (r:1 w:0)
+	/// Proof: `Proof::RekeyOngoing` (`max_values`: Some(1), `max_size`: Some(1), added: 496, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::IssuanceProof` (r:1 w:1)
+	/// Proof: `Proof::IssuanceProof` (`max_values`: None, `max_size`: Some(4178), added: 6653, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofsByIssuer` (r:0 w:1)
+	/// Proof: `Proof::ProofsByIssuer` (`max_values`: None, `max_size`: Some(80), added: 2555, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofCountByIssuer` (r:1 w:1)
+	/// Proof: `Proof::ProofCountByIssuer` (`max_values`: None, `max_size`: Some(52), added: 2527, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::Proofs` (r:1 w:1)
+	/// Proof: `Proof::Proofs` (`max_values`: None, `max_size`: Some(167), added: 2642, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ChallengeCount` (r:1 w:1)
+	/// Proof: `Proof::ChallengeCount` (`max_values`: Some(1), `max_size`: Some(8), added: 503, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::Challenges` (r:0 w:1)
+	/// Proof: `Proof::Challenges` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ChallengeExpiry` (r:1 w:1)
+	/// Proof: `Proof::ChallengeExpiry` (`max_values`: None, `max_size`: Some(72), added: 2547, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ChallengeGcCursor` (r:0 w:1)
+	/// Proof: `Proof::ChallengeGcCursor` (`max_values`: Some(1), `max_size`: Some(32), added: 527, mode: `MaxEncodedLen`)
+	/// The range of component `l` is `[1, 4096]`.
+	fn issue_challenge(l: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `13796 + l * (1 ±0)`
+		Weight::from_parts(21_963_000, 0)
+			.saturating_add(Weight::from_parts(0, 13796))
+			.saturating_add(Weight::from_parts(1_364, 0).saturating_mul(l.into()))
+			.saturating_add(T::DbWeight::get().reads(7))
+			.saturating_add(T::DbWeight::get().writes(8))
+			.saturating_add(Weight::from_parts(0, 1).saturating_mul(l.into()))
+	}
+	/// Storage: `Proof::Challenges` (r:1 w:1)
+	/// Proof: `Proof::Challenges` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::Proofs` (r:1 w:0)
+	/// Proof: `Proof::Proofs` (`max_values`: None, `max_size`: Some(167), added: 2642, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ChallengeExpiry` (r:0 w:1)
+	/// Proof: `Proof::ChallengeExpiry` (`max_values`: None, `max_size`: Some(72), added: 2547, mode: `MaxEncodedLen`)
+	fn complete_challenge() -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `5245`
+		Weight::from_parts(61_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 5245))
+			.saturating_add(T::DbWeight::get().reads(2))
+			.saturating_add(T::DbWeight::get().writes(2))
+	}
+	/// Storage: `Proof::ProofCommitments` (r:1 w:1)
+	/// Proof: `Proof::ProofCommitments` (`max_values`: None, `max_size`: Some(112), added: 2587, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::CommitmentExpiry` (r:0 w:1)
+	/// Proof: `Proof::CommitmentExpiry` (`max_values`: None, `max_size`: Some(120), added: 2595, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::RekeyOngoing` (r:1 w:0)
+	/// Proof: `Proof::RekeyOngoing` (`max_values`: Some(1), `max_size`: Some(1), added: 496, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::IssuanceProof` (r:9 w:8)
+	/// Proof: `Proof::IssuanceProof` (`max_values`: None, `max_size`: Some(4178), added: 6653, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofsByIssuer` (r:0 w:9)
+	/// Proof: `Proof::ProofsByIssuer` (`max_values`: None, `max_size`: Some(80), added: 2555, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofCountByIssuer` (r:9 w:9)
+	/// Proof: `Proof::ProofCountByIssuer` (`max_values`: None, `max_size`: Some(52), added: 2527, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::Proofs` (r:9 w:9)
+	/// Proof: `Proof::Proofs` (`max_values`: None, `max_size`: Some(167), added: 2642, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::CompositeComponents` (r:8 w:1)
+	/// Proof: `Proof::CompositeComponents` (`max_values`: None, `max_size`: Some(289), added: 2764, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusIndexOf` (r:8 w:0)
+	/// Proof: `Proof::StatusIndexOf` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusListChunks` (r:8 w:0)
+	/// Proof: `Proof::StatusListChunks` (`max_values`: None, `max_size`: Some(92), added: 2567, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::CompromisedKeys` (r:8 w:0)
+	/// Proof: `Proof::CompromisedKeys` (`max_values`: None, `max_size`: Some(112), added: 2587, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofIssuedAt` (r:8 w:1)
+	/// Proof: `Proof::ProofIssuedAt` (`max_values`: None, `max_size`: Some(64), added: 2539, mode: `MaxEncodedLen`)
 	/// Storage: `Proof::WhitelistEntity` (r:1 w:0)
-	/// Proof: `Proof::WhitelistEntity` (`max_values`: None, `max_size`: Some(49), added: 2524, mode: `MaxEncodedLen`)
-	fn store_proof() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `78`
-		//  Estimated: `4059`
-		// Minimum execution time: 15_000_000 picoseconds.
-		Weight::from_parts(16_000_000, 0)
-			.saturating_add(Weight::from_parts(0, 4059))
+	/// Proof: `Proof::WhitelistEntity` (`max_values`: None, `max_size`: Some(98), added: 2573, mode: `MaxEncodedLen`)
+	/// The range of component `c` is `[2, 8]`.
+	/// The range of component `l` is `[1, 4096]`.
+	fn store_composite_proof(c: u32, l: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `13382 + c * (20694 ±0) + l * (1 ±0) + c * l * (1 ±0)`
+		Weight::from_parts(21_108_000, 0)
+			.saturating_add(Weight::from_parts(0, 13382))
+			.saturating_add(Weight::from_parts(8_512_000, 0).saturating_mul(c.into()))
+			.saturating_add(Weight::from_parts(2_207, 0).saturating_mul(l.into()))
+			.saturating_add(Weight::from_parts(1_318, 0).saturating_mul(c.into()).saturating_mul(l.into()))
+			.saturating_add(T::DbWeight::get().reads(6))
+			.saturating_add(T::DbWeight::get().reads((8_u64).saturating_mul(c.into())))
+			.saturating_add(T::DbWeight::get().writes(7))
+			.saturating_add(T::DbWeight::get().writes((4_u64).saturating_mul(c.into())))
+			.saturating_add(Weight::from_parts(0, 20694).saturating_mul(c.into()))
+			.saturating_add(Weight::from_parts(0, 1).saturating_mul(l.into()))
+			.saturating_add(Weight::from_parts(0, 1).saturating_mul(c.into()).saturating_mul(l.into()))
+	}
+	/// Storage: `Proof::IssuanceProof` (r:1 w:1)
+	/// Proof: `Proof::IssuanceProof` (`max_values`: None, `max_size`: Some(4178), added: 6653, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofsByIssuer` (r:0 w:1)
+	/// Proof: `Proof::ProofsByIssuer` (`max_values`: None, `max_size`: Some(80), added: 2555, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofCountByIssuer` (r:1 w:1)
+	/// Proof: `Proof::ProofCountByIssuer` (`max_values`: None, `max_size`: Some(52), added: 2527, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::Proofs` (r:0 w:1)
+	/// Proof: `Proof::Proofs` (`max_values`: None, `max_size`: Some(167), added: 2642, mode: `MaxEncodedLen`)
+	fn rekey_proof() -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `9180`
+		Weight::from_parts(15_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 9180))
 			.saturating_add(T::DbWeight::get().reads(2))
+			.saturating_add(T::DbWeight::get().writes(4))
+	}
+	/// Storage: `Proof::ProofCommitments` (r:1 w:1)
+	/// Proof: `Proof::ProofCommitments` (`max_values`: None, `max_size`: Some(112), added: 2587, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::CommitmentExpiry` (r:0 w:1)
+	/// Proof: `Proof::CommitmentExpiry` (`max_values`: None, `max_size`: Some(120), added: 2595, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::Batches` (r:1 w:1)
+	/// Proof: `Proof::Batches` (`max_values`: None, `max_size`: Some(144), added: 2619, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::WhitelistEntity` (r:1 w:0)
+	/// Proof: `Proof::WhitelistEntity` (`max_values`: None, `max_size`: Some(98), added: 2573, mode: `MaxEncodedLen`)
+	fn anchor_batch() -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `7779`
+		Weight::from_parts(14_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 7779))
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().writes(3))
+	}
+	/// Storage: `Proof::Batches` (r:1 w:0)
+	/// Proof: `Proof::Batches` (`max_values`: None, `max_size`: Some(144), added: 2619, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::RevokedLeaves` (r:1024 w:1024)
+	/// Proof: `Proof::RevokedLeaves` (`max_values`: None, `max_size`: Some(96), added: 2571, mode: `MaxEncodedLen`)
+	/// The range of component `n` is `[1, 1024]`.
+	fn revoke_batch_leaves(n: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `2619 + n * (2571 ±0)`
+		Weight::from_parts(10_264_000, 0)
+			.saturating_add(Weight::from_parts(0, 2619))
+			.saturating_add(Weight::from_parts(4_318_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 2571).saturating_mul(n.into()))
+	}
+	/// Storage: `Proof::WhitelistEntity` (r:1 w:0)
+	/// Proof: `Proof::WhitelistEntity` (`max_values`: None, `max_size`: Some(98), added: 2573, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusListChunks` (r:1024 w:1024)
+	/// Proof: `Proof::StatusListChunks` (`max_values`: None, `max_size`: Some(92), added: 2567, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusIndexOwner` (r:1024 w:0)
+	/// Proof: `Proof::StatusIndexOwner` (`max_values`: None, `max_size`: Some(92), added: 2567, mode: `MaxEncodedLen`)
+	/// The range of component `n` is `[1, 1024]`.
+	fn update_revocation_status(n: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `2573 + n * (5134 ±0)`
+		Weight::from_parts(9_871_000, 0)
+			.saturating_add(Weight::from_parts(0, 2573))
+			.saturating_add(Weight::from_parts(3_954_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 5134).saturating_mul(n.into()))
+	}
+	/// Storage: `Proof::StatusExpiry` (r:0 w:1)
+	/// Proof: `Proof::StatusExpiry` (`max_values`: None, `max_size`: Some(72), added: 2547, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusIndexOf` (r:1 w:1)
+	/// Proof: `Proof::StatusIndexOf` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::Proofs` (r:1 w:0)
+	/// Proof: `Proof::Proofs` (`max_values`: None, `max_size`: Some(167), added: 2642, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusIndexOwner` (r:0 w:1)
+	/// Proof: `Proof::StatusIndexOwner` (`max_values`: None, `max_size`: Some(92), added: 2567, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusListChunks` (r:1 w:1)
+	/// Proof: `Proof::StatusListChunks` (`max_values`: None, `max_size`: Some(92), added: 2567, mode: `MaxEncodedLen`)
+	fn release_expired_status() -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `7720`
+		Weight::from_parts(17_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 7720))
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().writes(4))
+	}
+	/// Storage: `Proof::CommitmentExpiry` (r:1 w:1)
+	/// Proof: `Proof::CommitmentExpiry` (`max_values`: None, `max_size`: Some(120), added: 2595, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofCommitments` (r:0 w:1)
+	/// Proof: `Proof::ProofCommitments` (`max_values`: None, `max_size`: Some(112), added: 2587, mode: `MaxEncodedLen`)
+	fn remove_stale_commitment() -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `2595`
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 2595))
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(2))
+	}
+	/// Storage: `Proof::ChallengeExpiry` (r:1 w:1)
+	/// Proof: `Proof::ChallengeExpiry` (`max_values`: None, `max_size`: Some(72), added: 2547, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::Challenges` (r:0 w:1)
+	/// Proof: `Proof::Challenges` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	fn remove_expired_challenge() -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `2547`
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 2547))
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(2))
+	}
+	/// Storage: `Proof::WhitelistEntity` (r:1 w:1)
+	/// Proof: `Proof::WhitelistEntity` (`max_values`: None, `max_size`: Some(98), added: 2573, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::EntityCount` (r:1 w:1)
+	/// Proof: `Proof::EntityCount` (`max_values`: Some(1), `max_size`: Some(12), added: 507, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::CompromisedKeys` (r:1 w:1)
+	/// Proof: `Proof::CompromisedKeys` (`max_values`: None, `max_size`: Some(112), added: 2587, mode: `MaxEncodedLen`)
+	fn declare_key_compromise() -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `5667`
+		Weight::from_parts(15_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 5667))
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().writes(3))
+	}
+	/// Storage: `Proof::WhitelistEntity` (r:1 w:0)
+	/// Proof: `Proof::WhitelistEntity` (`max_values`: None, `max_size`: Some(98), added: 2573, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofCommitments` (r:1 w:1)
+	/// Proof: `Proof::ProofCommitments` (`max_values`: None, `max_size`: Some(112), added: 2587, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::CommitmentExpiry` (r:1 w:1)
+	/// Proof: `Proof::CommitmentExpiry` (`max_values`: None, `max_size`: Some(120), added: 2595, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::CommitmentGcCursor` (r:0 w:1)
+	/// Proof: `Proof::CommitmentGcCursor` (`max_values`: Some(1), `max_size`: Some(32), added: 527, mode: `MaxEncodedLen`)
+	fn commit_proof() -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `7755`
+		Weight::from_parts(12_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 7755))
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().writes(3))
+	}
+	/// Storage: `Proof::LastPruneBlock` (r:0 w:1)
+	/// Proof: `Proof::LastPruneBlock` (`max_values`: Some(1), `max_size`: Some(32), added: 527, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::Proofs` (r:256 w:256)
+	/// Proof: `Proof::Proofs` (`max_values`: None, `max_size`: Some(167), added: 2642, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::CrossingLog` (r:256 w:256)
+	/// Proof: `Proof::CrossingLog` (`max_values`: None, `max_size`: Some(3362), added: 5837, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofsByIssuer` (r:256 w:256)
+	/// Proof: `Proof::ProofsByIssuer` (`max_values`: None, `max_size`: Some(80), added: 2555, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofCountByIssuer` (r:256 w:256)
+	/// Proof: `Proof::ProofCountByIssuer` (`max_values`: None, `max_size`: Some(52), added: 2527, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::ProofIssuedAt` (r:0 w:256)
+	/// Proof: `Proof::ProofIssuedAt` (`max_values`: None, `max_size`: Some(64), added: 2539, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusIndexOf` (r:256 w:256)
+	/// Proof: `Proof::StatusIndexOf` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusExpiry` (r:0 w:256)
+	/// Proof: `Proof::StatusExpiry` (`max_values`: None, `max_size`: Some(72), added: 2547, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusIndexOwner` (r:0 w:256)
+	/// Proof: `Proof::StatusIndexOwner` (`max_values`: None, `max_size`: Some(92), added: 2567, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::StatusListChunks` (r:256 w:256)
+	/// Proof: `Proof::StatusListChunks` (`max_values`: None, `max_size`: Some(92), added: 2567, mode: `MaxEncodedLen`)
+	/// Storage: `Proof::CompositeComponents` (r:0 w:256)
+	/// Proof: `Proof::CompositeComponents` (`max_values`: None, `max_size`: Some(289), added: 2764, mode: `MaxEncodedLen`)
+	/// The range of component `n` is `[1, 256]`.
+	fn prune_expired(n: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `0 + n * (18639 ±0)`
+		Weight::from_parts(8_912_000, 0)
+			.saturating_add(Weight::from_parts(0, 0))
+			.saturating_add(Weight::from_parts(27_615_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads((6_u64).saturating_mul(n.into())))
 			.saturating_add(T::DbWeight::get().writes(1))
+			.saturating_add(T::DbWeight::get().writes((10_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 18639).saturating_mul(n.into()))
 	}
 }
-
-
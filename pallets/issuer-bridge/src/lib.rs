@@ -0,0 +1,554 @@
+//! # Issuer Bridge Pallet
+//!
+//! An optional offchain worker keeping the proofs of an issuer in sync with the issuance system
+//! it already runs, e.g. an internal visa database.
+//!
+//! Every block, the worker brings up to [`BATCH_SIZE`] changes of the local system on chain with
+//! transactions of the issuance proof pallet: `commit_proof` and then `store_proof` for a newly
+//! issued document, `update_revocation_status` for revoked ones. The transactions are signed with
+//! the first key of type [`KEY_TYPE`] in the node keystore, which must belong to a whitelisted
+//! entity, and take consecutive nonces from the one of its account, so they can be included in
+//! the same block. Once all changes are on chain, the worker polls the local system for new ones.
+//!
+//! The URL of the local system is read from the persistent offchain storage under
+//! [`ENDPOINT_KEY`], e.g. set with the `offchain_localStorageSet` RPC, and the worker does
+//! nothing until it is set. `GET {endpoint}?since={cursor}&limit={limit}` must answer with at
+//! most `limit` ([`POLL_LIMIT`]) of the changes made since `cursor`, counting each issued document
+//! and each revoked index:
+//!
+//! ```json
+//! {
+//!   "cursor": 42,
+//!   "issued": [{ "proof": "0x…", "expiration": 100800, "status_index": 7 }],
+//!   "revoked": [3, 9]
+//! }
+//! ```
+//!
+//! where `expiration` is in blocks, `revoked` lists status list indexes and `cursor` is passed
+//! back on the next poll.
+//!
+//! The cursor and the changes not on chain yet are kept in offchain storage, each change under a
+//! key of its own, so a run only reads the changes it submits and a restarted node carries on where
+//! it stopped instead of submitting them again. Commitment salts are derived
+//! from a secret of the node kept there as well, so a commitment submitted again after a restart
+//! is the same as the first one.
+//!
+//! A change the registry rejects, e.g. a status list index taken by another proof, is dropped
+//! with a warning instead of holding up the ones after it. So is a change whose transaction has
+//! not made it on chain after [`MAX_RESUBMITS`] resubmissions.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use sp_core::crypto::KeyTypeId;
+
+/// Key type of the issuer keys the bridge signs its transactions with.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"prob");
+
+/// Issuer keys held in the node keystore.
+pub mod crypto {
+    use super::KEY_TYPE;
+    use sp_core::sr25519::Signature as Sr25519Signature;
+    use sp_runtime::{
+        app_crypto::{app_crypto, sr25519},
+        traits::Verify,
+        MultiSignature, MultiSigner,
+    };
+
+    app_crypto!(sr25519, KEY_TYPE);
+
+    /// Signs the transactions of the bridge with an sr25519 issuer key.
+    pub struct IssuerAuthId;
+
+    impl frame_system::offchain::AppCrypto<MultiSigner, MultiSignature> for IssuerAuthId {
+        type RuntimeAppPublic = Public;
+        type GenericSignature = sp_core::sr25519::Signature;
+        type GenericPublic = sp_core::sr25519::Public;
+    }
+
+    impl frame_system::offchain::AppCrypto<<Sr25519Signature as Verify>::Signer, Sr25519Signature>
+        for IssuerAuthId
+    {
+        type RuntimeAppPublic = Public;
+        type GenericSignature = sp_core::sr25519::Signature;
+        type GenericPublic = sp_core::sr25519::Public;
+    }
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+
+    use super::*;
+    use alloc::{format, string::String, vec::Vec};
+    use frame_support::pallet_prelude::*;
+    use frame_support::storage::{with_transaction, TransactionOutcome};
+    use frame_support::traits::UnfilteredDispatchable;
+    use frame_system::offchain::{AppCrypto, CreateSignedTransaction, SubmitTransaction};
+    use frame_system::pallet_prelude::*;
+    use pallet_issuance_proof::{
+        Call as ProofCall, EntityState, Pallet as Proof, ProofCommitments, Proofs, WhitelistEntity,
+    };
+    use sp_core::hashing::blake2_256;
+    use sp_core::H256;
+    use sp_runtime::offchain::storage::StorageValueRef;
+    use sp_runtime::offchain::storage_lock::{StorageLock, Time};
+    use sp_runtime::offchain::{http, Duration, StorageKind};
+    use sp_runtime::traits::{IdentifyAccount, One};
+    use sp_runtime::{RuntimeAppPublic, SaturatedConversion};
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    #[pallet::config]
+    pub trait Config:
+        pallet_issuance_proof::Config + CreateSignedTransaction<ProofCall<Self>>
+    {
+        /// The issuer key the transactions of the bridge are signed with.
+        type AuthorityId: AppCrypto<Self::Public, Self::Signature>;
+    }
+
+    /// Persistent offchain storage key of the URL of the local issuance system. The bridge is
+    /// off while it is not set.
+    pub const ENDPOINT_KEY: &[u8] = b"probo::bridge::endpoint";
+
+    /// Persistent offchain storage key of the [`BridgeState`].
+    pub const STATE_KEY: &[u8] = b"probo::bridge::state";
+
+    /// Prefix of the persistent offchain storage keys of the [`QueuedTask`]s, followed by their
+    /// position in the queue.
+    pub const TASK_PREFIX: &[u8] = b"probo::bridge::task";
+
+    /// Persistent offchain storage key of the secret commitment salts are derived from.
+    const SECRET_KEY: &[u8] = b"probo::bridge::secret";
+
+    /// Offchain storage key of the lock keeping workers of overlapping blocks from running
+    /// together.
+    const LOCK_KEY: &[u8] = b"probo::bridge::lock";
+
+    /// Milliseconds the local issuance system has to answer a poll.
+    const HTTP_TIMEOUT_MS: u64 = 2_000;
+
+    /// Number of blocks after which a transaction that has not made it on chain is submitted
+    /// again.
+    pub const RESUBMIT_AFTER: u64 = 5;
+
+    /// Number of times a transaction is submitted again before the change is dropped.
+    pub const MAX_RESUBMITS: u32 = 3;
+
+    /// Number of changes brought on chain per block, each with a transaction of its own.
+    pub const BATCH_SIZE: usize = 8;
+
+    /// Number of changes the local system answers a poll with at most.
+    pub const POLL_LIMIT: usize = 1_000;
+
+    const LOG_TARGET: &str = "issuer-bridge";
+
+    /// A document issued by the local system, to be stored as a proof.
+    #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+    pub struct Document {
+        pub proof: Vec<u8>,
+        /// Number of blocks the proof stays valid.
+        pub expiration: u64,
+        pub status_index: Option<u32>,
+    }
+
+    /// A change of the local system to bring on chain.
+    #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+    pub enum Task {
+        Store(Document),
+        /// Status list indexes to revoke, at most `MaxStatusUpdates` of them.
+        Revoke(Vec<u32>),
+    }
+
+    /// A change polled but not on chain yet, kept in offchain storage under [`Pallet::task_key`].
+    #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+    pub struct QueuedTask {
+        pub task: Task,
+        /// Hash of the last call submitted for the change with its nonce, and the block it was
+        /// submitted in.
+        pub submitted: Option<(H256, u64)>,
+        /// Number of times the last call was submitted again.
+        pub resubmits: u32,
+    }
+
+    /// Progress of the bridge, kept in offchain storage.
+    #[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug)]
+    pub struct BridgeState {
+        /// Cursor of the local system to poll from.
+        pub cursor: u64,
+        /// Position of the first change in the queue that may not be on chain yet.
+        pub head: u64,
+        /// Position the next change polled is queued at. The changes in `head..tail` are brought
+        /// on chain in order, those done already have been removed.
+        pub tail: u64,
+    }
+
+    /// Why a run of the bridge stopped early. It is tried again in the next block.
+    #[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+    pub enum SyncError {
+        /// There is no issuer key in the keystore.
+        NoIssuerKey,
+        /// The issuer key does not belong to an active entity.
+        NotEntity,
+        /// The local system could not be reached or answered with an error.
+        Http,
+        /// The local system answered with something else than a list of changes, or with more
+        /// than [`POLL_LIMIT`] of them.
+        InvalidResponse,
+        /// The transaction could not be submitted to the pool.
+        Submission,
+    }
+
+    /// Changes of the local system, as answered to a poll.
+    #[derive(serde::Deserialize)]
+    struct Changes {
+        cursor: u64,
+        #[serde(default)]
+        issued: Vec<IssuedDocument>,
+        #[serde(default)]
+        revoked: Vec<u32>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct IssuedDocument {
+        /// Hex encoded proof bytes.
+        proof: String,
+        expiration: u64,
+        status_index: Option<u32>,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        /// Bring the next changes of the local issuance system on chain.
+        fn offchain_worker(_n: BlockNumberFor<T>) {
+            // nobody to report to, whatever failed is tried again in the next block
+            let _ = Self::sync();
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+
+        /// Drop the changes that made it on chain, poll the local system once all of them did,
+        /// and submit the transactions for the next ones.
+        pub fn sync() -> Result<(), SyncError> {
+            let Some(endpoint) =
+                sp_io::offchain::local_storage_get(StorageKind::PERSISTENT, ENDPOINT_KEY)
+            else {
+                return Ok(());
+            };
+            let mut lock = StorageLock::<Time>::new(LOCK_KEY);
+            let Ok(_guard) = lock.try_lock() else {
+                return Ok(());
+            };
+
+            let (public, issuer) = Self::issuer_key().ok_or(SyncError::NoIssuerKey)?;
+            // the registry would reject every change, they are kept until the entity is active
+            ensure!(
+                WhitelistEntity::<T>::get(&issuer)
+                    .is_some_and(|status| status.state == EntityState::Active),
+                SyncError::NotEntity
+            );
+            let state_ref = StorageValueRef::persistent(STATE_KEY);
+            let mut state = state_ref.get::<BridgeState>().ok().flatten().unwrap_or_default();
+
+            let mut batch = Self::next_batch(&issuer, &mut state);
+            if state.head == state.tail {
+                let (cursor, tasks) = Self::poll(&endpoint, state.cursor)?;
+                for task in tasks.into_iter().filter(|task| !Self::is_synced(&issuer, task)) {
+                    let queued = QueuedTask { task, submitted: None, resubmits: 0 };
+                    StorageValueRef::persistent(&Self::task_key(state.tail)).set(&queued);
+                    state.tail += 1;
+                }
+                state.cursor = cursor;
+                batch = Self::next_batch(&issuer, &mut state);
+            }
+
+            let result = Self::submit_batch(public, &issuer, batch);
+            // the changes dropped at the front of the queue are done
+            while state.head < state.tail && Self::queued(state.head).is_none() {
+                state.head += 1;
+            }
+            state_ref.set(&state);
+            result
+        }
+
+        /// Persistent offchain storage key of the change at `position` in the queue.
+        pub fn task_key(position: u64) -> Vec<u8> {
+            (TASK_PREFIX, position).encode()
+        }
+
+        fn queued(position: u64) -> Option<QueuedTask> {
+            StorageValueRef::persistent(&Self::task_key(position))
+                .get::<QueuedTask>()
+                .ok()
+                .flatten()
+        }
+
+        /// The first [`BATCH_SIZE`] changes of the queue that are not on chain yet, with their
+        /// position. The changes on chain are removed from the queue on the way.
+        fn next_batch(issuer: &T::AccountId, state: &mut BridgeState) -> Vec<(u64, QueuedTask)> {
+            let mut batch = Vec::new();
+            let mut position = state.head;
+            while position < state.tail && batch.len() < BATCH_SIZE {
+                match Self::queued(position) {
+                    Some(queued) if !Self::is_synced(issuer, &queued.task) => {
+                        batch.push((position, queued))
+                    }
+                    Some(_) => StorageValueRef::persistent(&Self::task_key(position)).clear(),
+                    None => {}
+                }
+                position += 1;
+                if batch.is_empty() {
+                    state.head = position;
+                }
+            }
+            batch
+        }
+
+        /// Submit the calls bringing the changes of `batch` on chain, in order. Each call is
+        /// checked on top of the ones before it, with the next nonce of the issuer account, and a
+        /// change the registry rejects is dropped instead.
+        fn submit_batch(
+            public: T::Public,
+            issuer: &T::AccountId,
+            batch: Vec<(u64, QueuedTask)>,
+        ) -> Result<(), SyncError> {
+            let mut nonce = frame_system::Pallet::<T>::account_nonce(issuer);
+            // nothing dispatched here is kept, the state of an offchain worker is thrown away
+            with_transaction::<_, DispatchError, _>(|| {
+                for (position, mut queued) in batch {
+                    let Some(call) = Self::next_call(issuer, &queued.task) else {
+                        continue;
+                    };
+                    let task_ref = StorageValueRef::persistent(&Self::task_key(position));
+                    let hash = H256((&call, nonce).using_encoded(blake2_256));
+                    if let Err(reason) = Self::check(issuer, &call, hash, &queued) {
+                        log::warn!(target: LOG_TARGET, "Dropped {:?}: {reason}", queued.task);
+                        task_ref.clear();
+                        continue;
+                    }
+                    let result =
+                        Self::submit(public.clone(), issuer, call, nonce, hash, &mut queued);
+                    task_ref.set(&queued);
+                    if result.is_err() {
+                        return TransactionOutcome::Rollback(Ok(result));
+                    }
+                    nonce += One::one();
+                }
+                TransactionOutcome::Rollback(Ok(Ok(())))
+            })
+            .unwrap_or(Err(SyncError::Submission))
+        }
+
+        /// The first issuer key in the keystore and its account.
+        fn issuer_key() -> Option<(T::Public, T::AccountId)> {
+            let key = <T::AuthorityId as AppCrypto<T::Public, T::Signature>>::RuntimeAppPublic::all()
+                .into_iter()
+                .next()?;
+            let key: <T::AuthorityId as AppCrypto<T::Public, T::Signature>>::GenericPublic =
+                key.into();
+            let public: T::Public = key.into();
+            Some((public.clone(), public.into_account()))
+        }
+
+        /// Whether a change is already on chain.
+        fn is_synced(issuer: &T::AccountId, task: &Task) -> bool {
+            match task {
+                Task::Store(document) => {
+                    Proofs::<T>::contains_key(Proof::<T>::proof_key(&document.proof))
+                }
+                Task::Revoke(indexes) => {
+                    indexes.iter().all(|index| Proof::<T>::status(issuer, *index))
+                }
+            }
+        }
+
+        /// The call bringing a change on chain, if one can be submitted in this block.
+        fn next_call(issuer: &T::AccountId, task: &Task) -> Option<ProofCall<T>> {
+            match task {
+                Task::Store(document) => {
+                    let proof_key = Proof::<T>::proof_key(&document.proof);
+                    let salt = Self::salt(&proof_key);
+                    let commitment = Proof::<T>::commitment(issuer, &proof_key, salt);
+                    match ProofCommitments::<T>::get(issuer, commitment) {
+                        None => Some(ProofCall::commit_proof { commitment }),
+                        // a stale commitment is removed in `on_idle`, then committed to again
+                        Some(committed_at)
+                            if Proof::<T>::current_block()
+                                > Proof::<T>::commitment_expiry(committed_at) =>
+                        {
+                            None
+                        }
+                        Some(committed_at) if committed_at < Proof::<T>::current_block() => {
+                            Some(ProofCall::store_proof {
+                                proof: document.proof.clone().try_into().ok()?,
+                                expiration: document.expiration.into(),
+                                max_uses: None,
+                                holder: None,
+                                attribute_root: None,
+                                status_index: document.status_index,
+                                salt,
+                            })
+                        }
+                        // the commitment can only be revealed in a later block
+                        Some(_) => None,
+                    }
+                }
+                Task::Revoke(indexes) => {
+                    let updates: Vec<_> = indexes
+                        .iter()
+                        .filter(|index| !Proof::<T>::status(issuer, **index))
+                        .map(|index| (*index, true))
+                        .collect();
+                    Some(ProofCall::update_revocation_status { updates: updates.try_into().ok()? })
+                }
+            }
+        }
+
+        /// Why the change `call` brings on chain has to be dropped, if it does: the registry
+        /// rejects `call` after the calls submitted before it, or it was submitted again too
+        /// often. `hash` is the hash of the call with its nonce.
+        fn check(
+            issuer: &T::AccountId,
+            call: &ProofCall<T>,
+            hash: H256,
+            queued: &QueuedTask,
+        ) -> Result<(), &'static str> {
+            ensure!(
+                !(Self::is_resubmit(hash, queued) && queued.resubmits >= MAX_RESUBMITS),
+                "not on chain after the last resubmission"
+            );
+
+            // kept for the calls after it, until the batch is rolled back
+            with_transaction(|| {
+                let origin = frame_system::RawOrigin::Signed(issuer.clone()).into();
+                match call.clone().dispatch_bypass_filter(origin) {
+                    Ok(_) => TransactionOutcome::Commit(Ok(())),
+                    Err(error) => TransactionOutcome::Rollback(Err(error.error)),
+                }
+            })
+            .map_err(<&'static str>::from)
+        }
+
+        /// Whether submitting the call with hash `hash` now submits it again.
+        fn is_resubmit(hash: H256, queued: &QueuedTask) -> bool {
+            let now = frame_system::Pallet::<T>::block_number().saturated_into::<u64>();
+            queued.submitted.is_some_and(|(submitted, at)| {
+                submitted == hash && now >= at.saturating_add(RESUBMIT_AFTER)
+            })
+        }
+
+        /// Submit `call` signed by the issuer key with `nonce`, unless it was submitted with the
+        /// same nonce lately and may still be waiting in the pool.
+        fn submit(
+            public: T::Public,
+            issuer: &T::AccountId,
+            call: ProofCall<T>,
+            nonce: T::Nonce,
+            hash: H256,
+            queued: &mut QueuedTask,
+        ) -> Result<(), SyncError> {
+            let now = frame_system::Pallet::<T>::block_number().saturated_into::<u64>();
+            let resubmit = Self::is_resubmit(hash, queued);
+            if queued.submitted.is_some_and(|(submitted, _)| submitted == hash) && !resubmit {
+                return Ok(());
+            }
+
+            let xt = <T as CreateSignedTransaction<ProofCall<T>>>::create_signed_transaction::<
+                T::AuthorityId,
+            >(call.into(), public, issuer.clone(), nonce)
+            .ok_or(SyncError::NoIssuerKey)?;
+            SubmitTransaction::<T, ProofCall<T>>::submit_transaction(xt)
+                .map_err(|_| SyncError::Submission)?;
+            queued.resubmits = if resubmit { queued.resubmits + 1 } else { 0 };
+            queued.submitted = Some((hash, now));
+            Ok(())
+        }
+
+        /// Salt of the commitment to a proof. It is derived from a secret of the node, so a
+        /// commitment submitted again is the same as the first one.
+        fn salt(proof_key: &T::ProofKey) -> [u8; 32] {
+            let secret_ref = StorageValueRef::persistent(SECRET_KEY);
+            let secret = match secret_ref.get::<[u8; 32]>() {
+                Ok(Some(secret)) => secret,
+                _ => {
+                    let secret = sp_io::offchain::random_seed();
+                    secret_ref.set(&secret);
+                    secret
+                }
+            };
+            (b"probo/bridge-salt", secret, proof_key).using_encoded(blake2_256)
+        }
+
+        /// Fetch the changes of the local system since `cursor`. Documents and revocations the
+        /// registry can't take, e.g. proofs longer than `MaxProofLength`, are left out, so they
+        /// don't hold up the others.
+        fn poll(endpoint: &[u8], cursor: u64) -> Result<(u64, Vec<Task>), SyncError> {
+            let endpoint = core::str::from_utf8(endpoint).map_err(|_| SyncError::Http)?;
+            let url = format!("{endpoint}?since={cursor}&limit={POLL_LIMIT}");
+            let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(HTTP_TIMEOUT_MS));
+            let response = http::Request::get(&url)
+                .deadline(deadline)
+                .send()
+                .map_err(|_| SyncError::Http)?
+                .try_wait(deadline)
+                .map_err(|_| SyncError::Http)?
+                .map_err(|_| SyncError::Http)?;
+            ensure!(response.code == 200, SyncError::Http);
+
+            let body = response.body().collect::<Vec<u8>>();
+            let changes: Changes =
+                serde_json::from_slice(&body).map_err(|_| SyncError::InvalidResponse)?;
+            ensure!(
+                changes.issued.len().saturating_add(changes.revoked.len()) <= POLL_LIMIT,
+                SyncError::InvalidResponse
+            );
+
+            let max_length = T::MaxProofLength::get() as usize;
+            let list_length = T::StatusListLength::get();
+            let mut tasks = Vec::new();
+            for document in changes.issued {
+                let proof = decode_hex(&document.proof).ok_or(SyncError::InvalidResponse)?;
+                if proof.is_empty()
+                    || proof.len() > max_length
+                    || document.status_index.is_some_and(|index| index >= list_length)
+                {
+                    continue;
+                }
+                tasks.push(Task::Store(Document {
+                    proof,
+                    expiration: document.expiration,
+                    status_index: document.status_index,
+                }));
+            }
+
+            let revoked: Vec<u32> =
+                changes.revoked.into_iter().filter(|index| *index < list_length).collect();
+            for indexes in revoked.chunks(T::MaxStatusUpdates::get().max(1) as usize) {
+                tasks.push(Task::Revoke(indexes.to_vec()));
+            }
+            Ok((changes.cursor, tasks))
+        }
+    }
+
+    /// Bytes of a hex string, with or without `0x` prefix.
+    fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+        let hex = hex.strip_prefix("0x").unwrap_or(hex);
+        if hex.len() % 2 != 0 {
+            return None;
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect()
+    }
+}
@@ -0,0 +1,162 @@
+use crate as pallet_issuer_bridge;
+use frame_support::{
+	derive_impl,
+	traits::{ConstU128, ConstU32, ConstU64},
+};
+use sp_core::{sr25519::Signature, H256};
+use sp_runtime::{
+	testing::{TestSignature, TestXt},
+	traits::{BlakeTwo256, IdentifyAccount, IdentityLookup, Verify},
+	BuildStorage,
+};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+type Balance = u128;
+pub type AccountId = <<Signature as Verify>::Signer as IdentifyAccount>::AccountId;
+pub type Extrinsic = TestXt<RuntimeCall, ()>;
+
+#[frame_support::runtime]
+mod runtime {
+	// The main runtime
+	#[runtime::runtime]
+	// Runtime Types to be generated
+	#[runtime::derive(
+		RuntimeCall,
+		RuntimeEvent,
+		RuntimeError,
+		RuntimeOrigin,
+		RuntimeFreezeReason,
+		RuntimeHoldReason,
+		RuntimeSlashReason,
+		RuntimeLockId,
+		RuntimeTask,
+		RuntimeViewFunction
+	)]
+	pub struct Test;
+
+	#[runtime::pallet_index(0)]
+	pub type System = frame_system::Pallet<Test>;
+
+	#[runtime::pallet_index(1)]
+	pub type Balances = pallet_balances::Pallet<Test>;
+
+	#[runtime::pallet_index(2)]
+	pub type Proof = pallet_issuance_proof::Pallet<Test>;
+
+	#[runtime::pallet_index(3)]
+	pub type Timestamp = pallet_timestamp::Pallet<Test>;
+
+	#[runtime::pallet_index(4)]
+	pub type Bridge = pallet_issuer_bridge::Pallet<Test>;
+}
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+	type Nonce = u64;
+	type AccountId = AccountId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Block = Block;
+	type AccountData = pallet_balances::AccountData<Balance>;
+}
+
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig)]
+impl pallet_balances::Config for Test {
+	type Balance = Balance;
+	type ExistentialDeposit = ConstU128<1>;
+	type AccountStore = System;
+	type RuntimeHoldReason = RuntimeHoldReason;
+}
+
+impl pallet_timestamp::Config for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = ConstU64<5>;
+	type WeightInfo = ();
+}
+
+impl pallet_issuance_proof::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type NativeBalance = Balances;
+	type RuntimeCall = RuntimeCall;
+	type RuntimeHoldReason = RuntimeHoldReason;
+	type WeightInfo = pallet_issuance_proof::IssuanceProofWeightInfo<Test>;
+	type VerifierAuthorityOrigin = frame_system::EnsureRoot<Self::AccountId>;
+	type KeyCompromiseOrigin = frame_system::EnsureRoot<Self::AccountId>;
+	type UnixTime = Timestamp;
+	type MaxCrossingRecords = ConstU32<4>;
+	type HolderKey = u64;
+	type HolderSignature = TestSignature;
+	type ChallengeTtl = ConstU64<10>;
+	type MaxComponents = ConstU32<4>;
+	type MaxProofLength = ConstU32<512>;
+	type ProofKey = H256;
+	type ProofHashing = BlakeTwo256;
+	type MaxLeavesPerRevocation = ConstU32<4>;
+	type StatusListLength = ConstU32<1024>;
+	type MaxStatusUpdates = ConstU32<4>;
+	type IssuancePriority = ConstU64<100>;
+	type IssuanceQuota = ConstU32<2>;
+	type QuotaEraLength = ConstU64<10>;
+	type MaxPrunePerBlock = ConstU32<4>;
+	type PruneUnsignedPriority = ConstU64<10>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ProofBenchmarkHelper;
+}
+
+impl pallet_issuer_bridge::Config for Test {
+	type AuthorityId = pallet_issuer_bridge::crypto::IssuerAuthId;
+}
+
+impl frame_system::offchain::SigningTypes for Test {
+	type Public = <Signature as Verify>::Signer;
+	type Signature = Signature;
+}
+
+impl<C> frame_system::offchain::CreateTransactionBase<C> for Test
+where
+	RuntimeCall: From<C>,
+{
+	type Extrinsic = Extrinsic;
+	type RuntimeCall = RuntimeCall;
+}
+
+impl<C> frame_system::offchain::CreateInherent<C> for Test
+where
+	RuntimeCall: From<C>,
+{
+	fn create_inherent(call: RuntimeCall) -> Extrinsic {
+		Extrinsic::new_bare(call)
+	}
+}
+
+impl<C> frame_system::offchain::CreateSignedTransaction<C> for Test
+where
+	RuntimeCall: From<C>,
+{
+	fn create_signed_transaction<
+		S: frame_system::offchain::AppCrypto<Self::Public, Self::Signature>,
+	>(
+		call: RuntimeCall,
+		_public: <Signature as Verify>::Signer,
+		_account: AccountId,
+		nonce: u64,
+	) -> Option<Extrinsic> {
+		Some(Extrinsic::new_signed(call, nonce, (), ()))
+	}
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+pub struct ProofBenchmarkHelper;
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_issuance_proof::BenchmarkHelper<u64, TestSignature> for ProofBenchmarkHelper {
+	fn create_holder_key() -> u64 {
+		1
+	}
+	fn sign_presentation(holder: &u64, message: &[u8]) -> TestSignature {
+		TestSignature(*holder, message.to_vec())
+	}
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	frame_system::GenesisConfig::<Test>::default().build_storage().unwrap().into()
+}
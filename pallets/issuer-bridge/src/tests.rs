@@ -0,0 +1,296 @@
+use crate::{mock::*, *};
+use codec::Decode;
+use frame_support::assert_ok;
+use frame_support::traits::fungible::Mutate;
+use sp_core::offchain::{
+    testing::{PendingRequest, TestOffchainExt, TestTransactionPoolExt},
+    OffchainDbExt, OffchainWorkerExt, StorageKind, TransactionPoolExt,
+};
+use sp_keystore::{testing::MemoryKeystore, Keystore, KeystoreExt};
+use sp_runtime::{generic::Preamble, offchain::storage::StorageValueRef, traits::Dispatchable};
+
+const ENDPOINT: &str = "http://localhost:8000/changes";
+
+/// Externalities with a registered issuer whose key is in the keystore, a mocked local issuance
+/// system and a transaction pool. Also returns a function expecting a poll of the local system
+/// and one taking the calls submitted to the pool, checking they take consecutive nonces from the
+/// one of the issuer.
+fn bridge_ext() -> (
+    sp_io::TestExternalities,
+    AccountId,
+    impl Fn(u64, &str),
+    impl Fn() -> Vec<RuntimeCall>,
+) {
+    let mut ext = new_test_ext();
+    let (offchain, offchain_state) = TestOffchainExt::new();
+    let (pool, pool_state) = TestTransactionPoolExt::new();
+    let keystore = MemoryKeystore::new();
+    let issuer = keystore.sr25519_generate_new(KEY_TYPE, Some("//Alice")).unwrap();
+    ext.register_extension(OffchainDbExt::new(offchain.clone()));
+    ext.register_extension(OffchainWorkerExt::new(offchain));
+    ext.register_extension(TransactionPoolExt::new(pool));
+    ext.register_extension(KeystoreExt::new(keystore));
+
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        let _ = Balances::mint_into(&issuer, (u32::MAX << 10).into());
+        assert_ok!(Proof::do_register_entity(issuer));
+    });
+
+    let expect_poll = move |since: u64, changes: &str| {
+        offchain_state.write().expect_request(PendingRequest {
+            method: "GET".into(),
+            uri: format!("{ENDPOINT}?since={since}&limit={POLL_LIMIT}"),
+            response: Some(changes.as_bytes().to_vec()),
+            sent: true,
+            ..Default::default()
+        });
+    };
+    let submitted = move || {
+        let nonce = System::account_nonce(issuer);
+        pool_state
+            .write()
+            .transactions
+            .drain(..)
+            .zip(nonce..)
+            .map(|(tx, nonce)| {
+                let xt = Extrinsic::decode(&mut &*tx).unwrap();
+                assert!(matches!(xt.preamble, Preamble::Signed(signed, ..) if signed == nonce));
+                xt.function
+            })
+            .collect::<Vec<_>>()
+    };
+    (ext, issuer, expect_poll, submitted)
+}
+
+fn set_endpoint() {
+    sp_io::offchain::local_storage_set(StorageKind::PERSISTENT, ENDPOINT_KEY, ENDPOINT.as_bytes());
+}
+
+fn bridge_state() -> BridgeState {
+    StorageValueRef::persistent(STATE_KEY).get::<BridgeState>().unwrap().unwrap()
+}
+
+fn queued(position: u64) -> Option<QueuedTask> {
+    StorageValueRef::persistent(&Bridge::task_key(position)).get::<QueuedTask>().unwrap()
+}
+
+#[test]
+fn test_bridge_stores_issued_documents(){
+    let (mut ext, issuer, expect_poll, submitted) = bridge_ext();
+    ext.execute_with(|| {
+        set_endpoint();
+        expect_poll(0, r#"{"cursor":1,"issued":[{"proof":"0x0101","expiration":100,"status_index":3}]}"#);
+
+        assert_ok!(Bridge::sync());
+        let calls = submitted();
+        assert!(matches!(
+            calls.as_slice(),
+            [RuntimeCall::Proof(pallet_issuance_proof::Call::commit_proof { .. })]
+        ));
+        assert_ok!(calls[0].clone().dispatch(RuntimeOrigin::signed(issuer)));
+
+        // the commitment can only be revealed in a later block
+        assert_ok!(Bridge::sync());
+        assert!(submitted().is_empty());
+
+        System::set_block_number(2);
+        assert_ok!(Bridge::sync());
+        let calls = submitted();
+        match calls.as_slice() {
+            [RuntimeCall::Proof(pallet_issuance_proof::Call::store_proof {
+                proof,
+                expiration,
+                status_index,
+                ..
+            })] => {
+                assert_eq!(proof.to_vec(), vec![1, 1]);
+                assert_eq!(*expiration, 100.into());
+                assert_eq!(*status_index, Some(3));
+            }
+            calls => panic!("unexpected calls {calls:?}"),
+        }
+        assert_ok!(calls[0].clone().dispatch(RuntimeOrigin::signed(issuer)));
+
+        // polls again from the new cursor once everything is on chain
+        System::set_block_number(3);
+        expect_poll(1, r#"{"cursor":1}"#);
+        assert_ok!(Bridge::sync());
+        assert!(submitted().is_empty());
+        assert_eq!(pallet_issuance_proof::StatusIndexOf::<Test>::get(Proof::proof_key(&[1, 1])), Some(3));
+    });
+}
+
+#[test]
+fn test_bridge_revokes_documents(){
+    let (mut ext, issuer, expect_poll, submitted) = bridge_ext();
+    ext.execute_with(|| {
+        set_endpoint();
+        let proof: pallet_issuance_proof::ProofBytes<Test> = vec![1u8; 64].try_into().unwrap();
+        assert_ok!(Proof::do_store_proof(issuer, proof, 100.into(), None, None, None, Some(3)));
+        // index 2000 is out of the status list and left out
+        expect_poll(0, r#"{"cursor":5,"revoked":[3,2000]}"#);
+
+        assert_ok!(Bridge::sync());
+        let calls = submitted();
+        assert_eq!(
+            calls,
+            vec![RuntimeCall::Proof(pallet_issuance_proof::Call::update_revocation_status {
+                updates: vec![(3, true)].try_into().unwrap(),
+            })]
+        );
+        assert_ok!(calls[0].clone().dispatch(RuntimeOrigin::signed(issuer)));
+        assert!(Proof::status(&issuer, 3));
+
+        System::set_block_number(2);
+        expect_poll(5, r#"{"cursor":5}"#);
+        assert_ok!(Bridge::sync());
+        assert!(submitted().is_empty());
+    });
+}
+
+#[test]
+fn test_bridge_does_not_submit_twice(){
+    let (mut ext, _, expect_poll, submitted) = bridge_ext();
+    ext.execute_with(|| {
+        set_endpoint();
+        expect_poll(0, r#"{"cursor":1,"issued":[{"proof":"0x0101","expiration":100}]}"#);
+        assert_ok!(Bridge::sync());
+        let first = submitted();
+        assert_eq!(first.len(), 1);
+
+        // the commitment is left in the pool for a while, and the local system is not polled
+        // again while it is pending
+        for block in 2..=5 {
+            System::set_block_number(block);
+            assert_ok!(Bridge::sync());
+            assert!(submitted().is_empty());
+        }
+
+        // then submitted again, with the same salt
+        System::set_block_number(6);
+        assert_ok!(Bridge::sync());
+        assert_eq!(submitted(), first);
+        assert_eq!(bridge_state(), BridgeState { cursor: 1, head: 0, tail: 1 });
+        assert_eq!(queued(0).map(|queued| queued.resubmits), Some(1));
+    });
+}
+
+#[test]
+fn test_bridge_drops_rejected_tasks(){
+    let (mut ext, issuer, expect_poll, submitted) = bridge_ext();
+    ext.execute_with(|| {
+        set_endpoint();
+        let proof: pallet_issuance_proof::ProofBytes<Test> = vec![1u8; 64].try_into().unwrap();
+        assert_ok!(Proof::do_store_proof(issuer, proof, 100.into(), None, None, None, Some(3)));
+        // status list index 3 is taken, so the registry rejects the first document
+        expect_poll(
+            0,
+            r#"{"cursor":1,"issued":[{"proof":"0x0101","expiration":100,"status_index":3},{"proof":"0x0202","expiration":100}]}"#,
+        );
+        assert_ok!(Bridge::sync());
+        let calls = submitted();
+        assert_eq!(calls.len(), 2);
+        for call in calls {
+            assert_ok!(call.dispatch(RuntimeOrigin::signed(issuer)));
+        }
+
+        // the rejected reveal is dropped and the next document goes ahead in the same run
+        System::set_block_number(2);
+        assert_ok!(Bridge::sync());
+        let calls = submitted();
+        match calls.as_slice() {
+            [RuntimeCall::Proof(pallet_issuance_proof::Call::store_proof { proof, .. })] => {
+                assert_eq!(proof.to_vec(), vec![2, 2]);
+            }
+            calls => panic!("unexpected calls {calls:?}"),
+        }
+        assert_eq!(queued(0), None);
+        assert_eq!(bridge_state(), BridgeState { cursor: 1, head: 1, tail: 2 });
+
+        // a reveal that never makes it on chain is dropped after the last resubmission
+        for resubmit in 1..=MAX_RESUBMITS {
+            System::set_block_number(2 + RESUBMIT_AFTER * u64::from(resubmit));
+            assert_ok!(Bridge::sync());
+            assert_eq!(submitted(), calls);
+        }
+        System::set_block_number(2 + RESUBMIT_AFTER * u64::from(MAX_RESUBMITS + 1));
+        assert_ok!(Bridge::sync());
+        assert!(submitted().is_empty());
+        assert_eq!(queued(1), None);
+        assert_eq!(bridge_state(), BridgeState { cursor: 1, head: 2, tail: 2 });
+    });
+}
+
+#[test]
+fn test_bridge_submits_batches(){
+    let (mut ext, issuer, expect_poll, submitted) = bridge_ext();
+    ext.execute_with(|| {
+        set_endpoint();
+        let issued: Vec<String> = (1..=BATCH_SIZE as u8 + 2)
+            .map(|i| format!(r#"{{"proof":"0x{i:02x}{i:02x}","expiration":100}}"#))
+            .collect();
+        expect_poll(0, &format!(r#"{{"cursor":1,"issued":[{}]}}"#, issued.join(",")));
+
+        // the first documents are committed to in one block, with consecutive nonces
+        assert_ok!(Bridge::sync());
+        let calls = submitted();
+        assert_eq!(calls.len(), BATCH_SIZE);
+        for call in calls {
+            assert!(matches!(call, RuntimeCall::Proof(pallet_issuance_proof::Call::commit_proof { .. })));
+            assert_ok!(call.dispatch(RuntimeOrigin::signed(issuer)));
+            System::inc_account_nonce(issuer);
+        }
+
+        // then revealed, the nonces following the ones included
+        System::set_block_number(2);
+        assert_ok!(Bridge::sync());
+        let calls = submitted();
+        assert_eq!(calls.len(), BATCH_SIZE);
+        for call in calls {
+            assert!(matches!(call, RuntimeCall::Proof(pallet_issuance_proof::Call::store_proof { .. })));
+            assert_ok!(call.dispatch(RuntimeOrigin::signed(issuer)));
+            System::inc_account_nonce(issuer);
+        }
+
+        // the stored documents leave the queue and the last ones go ahead
+        System::set_block_number(3);
+        assert_ok!(Bridge::sync());
+        assert_eq!(submitted().len(), 2);
+        assert_eq!(queued(0), None);
+        assert_eq!(bridge_state(), BridgeState { cursor: 1, head: BATCH_SIZE as u64, tail: BATCH_SIZE as u64 + 2 });
+    });
+}
+
+#[test]
+fn test_bridge_rejects_long_polls(){
+    let (mut ext, _, expect_poll, submitted) = bridge_ext();
+    ext.execute_with(|| {
+        set_endpoint();
+        let revoked: Vec<String> = (0..=POLL_LIMIT).map(|index| index.to_string()).collect();
+        expect_poll(0, &format!(r#"{{"cursor":1,"revoked":[{}]}}"#, revoked.join(",")));
+
+        assert_eq!(Bridge::sync(), Err(SyncError::InvalidResponse));
+        assert!(submitted().is_empty());
+    });
+}
+
+#[test]
+fn test_bridge_needs_endpoint_and_key(){
+    let (mut ext, _, _, submitted) = bridge_ext();
+    ext.execute_with(|| {
+        // off until an endpoint is set
+        assert_ok!(Bridge::sync());
+        assert!(submitted().is_empty());
+    });
+
+    let mut ext = new_test_ext();
+    let (offchain, _) = TestOffchainExt::new();
+    ext.register_extension(OffchainDbExt::new(offchain.clone()));
+    ext.register_extension(OffchainWorkerExt::new(offchain));
+    ext.register_extension(KeystoreExt::new(MemoryKeystore::new()));
+    ext.execute_with(|| {
+        set_endpoint();
+        assert_eq!(Bridge::sync(), Err(SyncError::NoIssuerKey));
+    });
+}
@@ -32,7 +32,7 @@ use frame_support::{
 use pallet_grandpa::AuthorityId as GrandpaId;
 use sp_api::impl_runtime_apis;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
+use sp_core::{crypto::KeyTypeId, OpaqueMetadata, H256};
 use sp_runtime::{
 	traits::{Block as BlockT, NumberFor},
 	transaction_validity::{TransactionSource, TransactionValidity},
@@ -42,8 +42,9 @@ use sp_version::RuntimeVersion;
 
 // Local module imports
 use super::{
-	AccountId, Aura, Balance, Block, Executive, Grandpa, InherentDataExt, Nonce, Runtime,
-	RuntimeCall, RuntimeGenesisConfig, SessionKeys, System, TransactionPayment, VERSION,
+	AccountId, Aura, Balance, Block, Executive, Grandpa, Hash, InherentDataExt, Nonce, Proof,
+	Runtime, RuntimeCall, RuntimeGenesisConfig, SessionKeys, Signature, System, TransactionPayment,
+	VERSION,
 };
 
 impl_runtime_apis! {
@@ -220,6 +221,64 @@ impl_runtime_apis! {
 		}
 	}
 
+	#[api_version(3)]
+	impl
+		pallet_issuance_proof_runtime_api::IssuanceProofApi<
+			Block,
+			AccountId,
+			pallet_issuance_proof::ProofBytes<Runtime>,
+			Hash,
+			Signature,
+		> for Runtime
+	{
+		fn proof_status(
+			proof: pallet_issuance_proof::ProofBytes<Runtime>,
+		) -> pallet_issuance_proof::ProofStatus {
+			Proof::proof_status(&proof)
+		}
+
+		fn crossing_summary(
+			proof: pallet_issuance_proof::ProofBytes<Runtime>,
+		) -> Option<pallet_issuance_proof::CrossingSummary> {
+			Proof::crossing_summary(proof)
+		}
+
+		fn verify_presentation(nonce: [u8; 32], signature: Signature) -> bool {
+			Proof::check_presentation(nonce, &signature).is_ok()
+		}
+
+		fn verify_disclosure(
+			proof: pallet_issuance_proof::ProofBytes<Runtime>,
+			attributes: Vec<pallet_issuance_proof::merkle::DisclosedAttribute>,
+		) -> bool {
+			Proof::verify_disclosure(&proof, &attributes)
+		}
+
+		fn batch_document_status(
+			root: H256,
+			leaf: H256,
+			proof: pallet_issuance_proof::merkle::MerkleProof,
+		) -> pallet_issuance_proof::ProofStatus {
+			Proof::batch_document_status(root, leaf, &proof)
+		}
+
+		fn proofs_by_issuer(
+			issuer: AccountId,
+			cursor: Option<Hash>,
+			limit: u32,
+		) -> pallet_issuance_proof::ProofPage<Hash> {
+			Proof::proofs_by_issuer(issuer, cursor, limit)
+		}
+
+		fn proof_status_by_key(proof_key: Hash) -> pallet_issuance_proof::ProofStatus {
+			Proof::proof_status_by_key(&proof_key)
+		}
+
+		fn entity_counts() -> pallet_issuance_proof::EntityCounts {
+			Proof::entity_counts()
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn benchmark_metadata(extra: bool) -> (
@@ -35,14 +35,23 @@ use frame_support::{
 use frame_system::limits::{BlockLength, BlockWeights};
 use pallet_transaction_payment::{ConstFeeMultiplier, FungibleAdapter, Multiplier};
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use sp_runtime::{traits::One, Perbill};
+use codec::Encode;
+use sp_runtime::{
+    generic,
+    traits::{BlakeTwo256, One, Verify},
+    transaction_validity::TransactionPriority,
+    MultiAddress, Perbill, SaturatedConversion,
+};
+#[cfg(feature = "runtime-benchmarks")]
+use sp_runtime::traits::IdentifyAccount;
 use sp_version::RuntimeVersion;
 
 // Local module imports
 use super::{
     AccountId, Aura, Balance, Balances, Block, BlockNumber, Hash, Nonce, PalletInfo, Runtime,
     RuntimeCall, RuntimeEvent, RuntimeFreezeReason, RuntimeHoldReason, RuntimeOrigin, RuntimeTask,
-    System, EXISTENTIAL_DEPOSIT, SLOT_DURATION, VERSION,
+    Signature, SignedPayload, System, Timestamp, TxExtension, UncheckedExtrinsic, DAYS,
+    EXISTENTIAL_DEPOSIT, HOURS, MINUTES, SLOT_DURATION, VERSION,
 };
 
 const NORMAL_DISPATCH_RATIO: Perbill = Perbill::from_percent(75);
@@ -163,10 +172,120 @@ impl pallet_template::Config for Runtime {
     type WeightInfo = pallet_template::weights::SubstrateWeight<Runtime>;
 }
 
+parameter_types! {
+    /// Valid proof issuance is included ahead of ordinary transactions.
+    pub const IssuancePriority: TransactionPriority = TransactionPriority::MAX / 2;
+    /// Pruning expired proofs can wait for the issuance to be included.
+    pub const PruneUnsignedPriority: TransactionPriority = TransactionPriority::MAX / 4;
+}
+
+impl<C> frame_system::offchain::CreateTransactionBase<C> for Runtime
+where
+    RuntimeCall: From<C>,
+{
+    type Extrinsic = UncheckedExtrinsic;
+    type RuntimeCall = RuntimeCall;
+}
+
+impl<C> frame_system::offchain::CreateInherent<C> for Runtime
+where
+    RuntimeCall: From<C>,
+{
+    fn create_inherent(call: RuntimeCall) -> UncheckedExtrinsic {
+        UncheckedExtrinsic::new_bare(call)
+    }
+}
+
+impl frame_system::offchain::SigningTypes for Runtime {
+    type Public = <Signature as Verify>::Signer;
+    type Signature = Signature;
+}
+
+impl<C> frame_system::offchain::CreateSignedTransaction<C> for Runtime
+where
+    RuntimeCall: From<C>,
+{
+    fn create_signed_transaction<
+        S: frame_system::offchain::AppCrypto<Self::Public, Self::Signature>,
+    >(
+        call: RuntimeCall,
+        public: <Signature as Verify>::Signer,
+        account: AccountId,
+        nonce: Nonce,
+    ) -> Option<UncheckedExtrinsic> {
+        let period =
+            BlockHashCount::get().checked_next_power_of_two().map(|c| c / 2).unwrap_or(2) as u64;
+        let current_block = System::block_number().saturated_into::<u64>().saturating_sub(1);
+        let tx_ext: TxExtension = (
+            frame_system::CheckNonZeroSender::<Runtime>::new(),
+            frame_system::CheckSpecVersion::<Runtime>::new(),
+            frame_system::CheckTxVersion::<Runtime>::new(),
+            frame_system::CheckGenesis::<Runtime>::new(),
+            frame_system::CheckEra::<Runtime>::from(generic::Era::mortal(period, current_block)),
+            frame_system::CheckNonce::<Runtime>::from(nonce),
+            frame_system::CheckWeight::<Runtime>::new(),
+            pallet_issuance_proof::extension::ValidateProofIssuance::<Runtime>::new(),
+            pallet_issuance_proof::extension::ChargeIssuanceQuota::from(
+                pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+            ),
+            frame_metadata_hash_extension::CheckMetadataHash::<Runtime>::new(false),
+            frame_system::WeightReclaim::<Runtime>::new(),
+        );
+        let raw_payload = SignedPayload::new(call, tx_ext).ok()?;
+        let signature = raw_payload.using_encoded(|payload| S::sign(payload, public))?;
+        let (call, tx_ext, _) = raw_payload.deconstruct();
+        Some(UncheckedExtrinsic::new_signed(call, MultiAddress::Id(account), signature, tx_ext))
+    }
+}
+
 impl pallet_issuance_proof::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
     type WeightInfo = pallet_issuance_proof::weights::IssuanceProofWeightInfo<Runtime>;
     type RuntimeHoldReason = RuntimeHoldReason;
     type NativeBalance  = Balances;
     type RuntimeCall = RuntimeCall;
+    type VerifierAuthorityOrigin = frame_system::EnsureRoot<AccountId>;
+    type KeyCompromiseOrigin = frame_system::EnsureRoot<AccountId>;
+    type UnixTime = Timestamp;
+    type MaxCrossingRecords = ConstU32<128>;
+    type HolderKey = AccountId;
+    type HolderSignature = Signature;
+    type ChallengeTtl = ConstU32<{ 10 * MINUTES }>;
+    type CommitmentTtl = ConstU32<HOURS>;
+    type MaxComponents = ConstU32<8>;
+    type MaxProofLength = ConstU32<4096>;
+    type ProofKey = Hash;
+    type ProofHashing = BlakeTwo256;
+    type MaxLeavesPerRevocation = ConstU32<1024>;
+    type StatusListLength = ConstU32<131_072>;
+    type MaxStatusUpdates = ConstU32<1024>;
+    type IssuancePriority = IssuancePriority;
+    type IssuanceQuota = ConstU32<10_000>;
+    type QuotaEraLength = ConstU32<{ 7 * DAYS }>;
+    type MaxPrunePerBlock = ConstU32<256>;
+    type PruneUnsignedPriority = PruneUnsignedPriority;
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = ProofBenchmarkHelper;
+}
+
+impl pallet_issuer_bridge::Config for Runtime {
+    type AuthorityId = pallet_issuer_bridge::crypto::IssuerAuthId;
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+pub struct ProofBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_issuance_proof::BenchmarkHelper<AccountId, Signature> for ProofBenchmarkHelper {
+    fn create_holder_key() -> AccountId {
+        let public = sp_io::crypto::sr25519_generate(sp_core::crypto::key_types::DUMMY, None);
+        sp_runtime::MultiSigner::from(public).into_account()
+    }
+
+    fn sign_presentation(holder: &AccountId, message: &[u8]) -> Signature {
+        let public = sp_core::sr25519::Public::from_raw(holder.clone().into());
+        sp_io::crypto::sr25519_sign(sp_core::crypto::key_types::DUMMY, &public, message)
+            .expect("holder key was generated in the keystore; qed")
+            .into()
+    }
 }
@@ -69,10 +69,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 100,
+	spec_version: 108,
 	impl_version: 1,
 	apis: apis::RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 	system_version: 1,
 };
 
@@ -155,7 +155,11 @@ pub type TxExtension = (
 	frame_system::CheckEra<Runtime>,
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
-	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+	pallet_issuance_proof::extension::ValidateProofIssuance<Runtime>,
+	pallet_issuance_proof::extension::ChargeIssuanceQuota<
+		Runtime,
+		pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+	>,
 	frame_metadata_hash_extension::CheckMetadataHash<Runtime>,
 	frame_system::WeightReclaim<Runtime>,
 );
@@ -171,7 +175,12 @@ pub type SignedPayload = generic::SignedPayload<RuntimeCall, TxExtension>;
 ///
 /// This can be a tuple of types, each implementing `OnRuntimeUpgrade`.
 #[allow(unused_parens)]
-type Migrations = ();
+type Migrations = (
+	pallet_issuance_proof::migrations::v1::MigrateV0ToV1<Runtime>,
+	pallet_issuance_proof::migrations::v2::MigrateV1ToV2<Runtime>,
+	pallet_issuance_proof::migrations::v3::MigrateV2ToV3<Runtime>,
+	pallet_issuance_proof::migrations::v4::MigrateV3ToV4<Runtime>,
+);
 
 /// Executive: handles dispatch to the various modules.
 pub type Executive = frame_executive::Executive<
@@ -228,4 +237,7 @@ mod runtime {
 
 	#[runtime::pallet_index(8)]
 	pub type Proof = pallet_issuance_proof;
+
+	#[runtime::pallet_index(9)]
+	pub type IssuerBridge = pallet_issuer_bridge;
 }